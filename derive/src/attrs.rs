@@ -0,0 +1,150 @@
+use proc_macro2::{Ident, Span};
+use quote::format_ident;
+use syn::ext::IdentExt;
+use syn::{Attribute, Error, Fields, LitStr, Member, Path, Result, Token, Type};
+
+pub enum Tagging {
+    External,
+    Internal(String),
+    Adjacent(String, String),
+    Untagged,
+}
+
+pub enum FieldDefault {
+    Trait,
+    Function(Path),
+}
+
+#[derive(Default)]
+pub struct FieldAttrs {
+    pub rename: Option<String>,
+    pub skip: bool,
+    pub default: Option<FieldDefault>,
+    pub flatten: bool,
+}
+
+#[derive(Copy, Clone, PartialEq)]
+pub enum Shape {
+    Unit,
+    Newtype,
+    Tuple,
+    Named,
+}
+
+pub struct Field<'a> {
+    pub member: Member,
+    pub binding: Ident,
+    pub ty: &'a Type,
+    pub key: String,
+    pub attrs: FieldAttrs,
+}
+
+fn item_attrs(attrs: &[Attribute]) -> impl Iterator<Item = &Attribute> {
+    attrs.iter().filter(|attr| attr.path().is_ident("item"))
+}
+
+fn string_value(meta: &syn::meta::ParseNestedMeta) -> Result<String> {
+    Ok(meta.value()?.parse::<LitStr>()?.value())
+}
+
+pub fn tagging(attrs: &[Attribute]) -> Result<Tagging> {
+    let mut tag = None;
+    let mut content = None;
+    let mut untagged = false;
+    for attr in item_attrs(attrs) {
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("tag") {
+                tag = Some(string_value(&meta)?);
+            } else if meta.path.is_ident("content") {
+                content = Some(string_value(&meta)?);
+            } else if meta.path.is_ident("untagged") {
+                untagged = true;
+            } else {
+                return Err(meta.error("unknown container attribute"));
+            }
+            Ok(())
+        })?;
+    }
+    match (tag, content, untagged) {
+        (None, None, false) => Ok(Tagging::External),
+        (Some(tag), None, false) => Ok(Tagging::Internal(tag)),
+        (Some(tag), Some(content), false) => Ok(Tagging::Adjacent(tag, content)),
+        (None, None, true) => Ok(Tagging::Untagged),
+        (None, Some(_), false) => Err(Error::new(Span::call_site(), "`content` requires `tag`")),
+        _ => Err(Error::new(Span::call_site(), "`untagged` cannot be combined with `tag` or `content`")),
+    }
+}
+
+pub fn variant_name(ident: &Ident, attrs: &[Attribute]) -> Result<String> {
+    let mut rename = None;
+    for attr in item_attrs(attrs) {
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("rename") {
+                rename = Some(string_value(&meta)?);
+                Ok(())
+            } else {
+                Err(meta.error("unknown variant attribute"))
+            }
+        })?;
+    }
+    Ok(rename.unwrap_or_else(|| ident.unraw().to_string()))
+}
+
+fn field_attrs(attrs: &[Attribute]) -> Result<FieldAttrs> {
+    let mut parsed = FieldAttrs::default();
+    for attr in item_attrs(attrs) {
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("rename") {
+                parsed.rename = Some(string_value(&meta)?);
+            } else if meta.path.is_ident("skip") {
+                parsed.skip = true;
+            } else if meta.path.is_ident("flatten") {
+                parsed.flatten = true;
+            } else if meta.path.is_ident("default") {
+                parsed.default = Some(if meta.input.peek(Token![=]) {
+                    FieldDefault::Function(meta.value()?.parse::<LitStr>()?.parse()?)
+                } else {
+                    FieldDefault::Trait
+                });
+            } else {
+                return Err(meta.error("unknown field attribute"));
+            }
+            Ok(())
+        })?;
+        if parsed.flatten && (parsed.rename.is_some() || parsed.skip || parsed.default.is_some()) {
+            return Err(Error::new_spanned(attr, "`flatten` cannot be combined with other field attributes"));
+        }
+    }
+    Ok(parsed)
+}
+
+pub fn shape(fields: &Fields) -> Shape {
+    match fields {
+        Fields::Unit => Shape::Unit,
+        Fields::Unnamed(unnamed) if unnamed.unnamed.len() == 1 => Shape::Newtype,
+        Fields::Unnamed(_) => Shape::Tuple,
+        Fields::Named(_) => Shape::Named,
+    }
+}
+
+pub fn fields(fields: &Fields) -> Result<Vec<Field<'_>>> {
+    let mut parsed = Vec::new();
+    for (index, field) in fields.iter().enumerate() {
+        let attrs = field_attrs(&field.attrs)?;
+        let (member, binding, key) = match &field.ident {
+            Some(ident) => {
+                let name = ident.unraw().to_string();
+                let key = attrs.rename.clone().unwrap_or_else(|| name.clone());
+                (Member::Named(ident.clone()), format_ident!("__{}", name), key)
+            },
+            None => {
+                if attrs.rename.is_some() || attrs.skip || attrs.default.is_some() || attrs.flatten {
+                    return Err(Error::new_spanned(field, "field attributes only apply to named fields"));
+                }
+                (Member::Unnamed(index.into()), format_ident!("__{}", index), index.to_string())
+            },
+        };
+        parsed.push(Field { member, binding, ty: &field.ty, key, attrs });
+    }
+    Ok(parsed)
+}
@@ -0,0 +1,225 @@
+use proc_macro2::TokenStream as TokenStream2;
+use quote::quote;
+use syn::{Data, DeriveInput, Error, Result};
+
+use crate::attrs::{self, Field, FieldDefault, Shape, Tagging};
+use crate::{core, mapping, with_bound};
+
+pub fn expand(input: &DeriveInput) -> Result<TokenStream2> {
+    let m = mapping();
+    let c = core();
+    let name = &input.ident;
+    let generics = with_bound(&input.generics, quote!(#m::FromItem));
+    let (impl_generics, type_generics, where_clause) = generics.split_for_impl();
+    let item = quote!(item);
+    let body = match &input.data {
+        Data::Struct(data) => {
+            if !matches!(attrs::tagging(&input.attrs)?, Tagging::External) {
+                return Err(Error::new_spanned(input, "tagging attributes only apply to enums"));
+            }
+            let fields = attrs::fields(&data.fields)?;
+            let value = construct(&quote!(Self), attrs::shape(&data.fields), &fields, &item, true);
+            quote!(::core::result::Result::Ok(#value))
+        },
+        Data::Enum(data) => {
+            let tagging = attrs::tagging(&input.attrs)?;
+            let mut variants = Vec::new();
+            for variant in &data.variants {
+                let shape = attrs::shape(&variant.fields);
+                if shape == Shape::Tuple && matches!(tagging, Tagging::Internal(_)) {
+                    return Err(Error::new_spanned(variant, "internally tagged enums cannot hold tuple variants"));
+                }
+                let ident = &variant.ident;
+                variants.push(Variant {
+                    ctor: quote!(Self::#ident),
+                    name: attrs::variant_name(ident, &variant.attrs)?,
+                    shape,
+                    fields: attrs::fields(&variant.fields)?,
+                });
+            }
+            match &tagging {
+                Tagging::External => external(&variants),
+                Tagging::Internal(tag) => internal(&variants, tag),
+                Tagging::Adjacent(tag, content) => adjacent(&variants, tag, content),
+                Tagging::Untagged => untagged(&variants),
+            }
+        },
+        Data::Union(_) => return Err(Error::new_spanned(input, "FromItem cannot be derived for unions")),
+    };
+    Ok(quote! {
+        #[automatically_derived]
+        impl #impl_generics #m::FromItem for #name #type_generics #where_clause {
+            fn from_item(item: &#c::items::ItemType) -> ::core::result::Result<Self, #m::FromItemError> {
+                #body
+            }
+        }
+    })
+}
+
+struct Variant<'a> {
+    ctor: TokenStream2,
+    name: String,
+    shape: Shape,
+    fields: Vec<Field<'a>>,
+}
+
+// An expression building `ctor` from `source`, returning early through `?`.
+// Unit values are only checked for null when `check_null` is set, since a
+// tagged unit variant has no content to check.
+fn construct(ctor: &TokenStream2, shape: Shape, fields: &[Field], source: &TokenStream2, check_null: bool) -> TokenStream2 {
+    let m = mapping();
+    let c = core();
+    let kind = quote!(#c::items::ItemKind);
+    match shape {
+        Shape::Unit if check_null => quote!({ #m::expect_kind(#source, #kind::Null)?; #ctor }),
+        Shape::Unit => quote!(#ctor),
+        Shape::Newtype => {
+            let ty = fields[0].ty;
+            quote!(#ctor(<#ty as #m::FromItem>::from_item(#source)?))
+        },
+        Shape::Tuple => {
+            let values = fields.iter().enumerate().map(|(index, field)| {
+                let ty = field.ty;
+                quote!(#m::list_member::<#ty>(#source, #index)?)
+            });
+            quote!({ #m::expect_kind(#source, #kind::List)?; #ctor(#(#values),*) })
+        },
+        Shape::Named => {
+            let values = fields.iter().map(|field| {
+                let member = &field.member;
+                let value = field_value(field, source);
+                quote!(#member: #value)
+            });
+            quote!({ #m::expect_kind(#source, #kind::Hash)?; #ctor { #(#values),* } })
+        },
+    }
+}
+
+fn field_value(field: &Field, source: &TokenStream2) -> TokenStream2 {
+    let m = mapping();
+    let ty = field.ty;
+    let key = &field.key;
+    let default = match &field.attrs.default {
+        Some(FieldDefault::Function(path)) => quote!(#path),
+        Some(FieldDefault::Trait) | None => quote!(::core::default::Default::default),
+    };
+    if field.attrs.skip {
+        quote!(#default())
+    } else if field.attrs.flatten {
+        quote!(<#ty as #m::FromItem>::from_item(#source)?)
+    } else if field.attrs.default.is_some() {
+        quote!(#m::hash_field_or_else::<#ty, _>(#source, #key, #default)?)
+    } else {
+        quote!(#m::hash_field::<#ty>(#source, #key)?)
+    }
+}
+
+fn unknown_variant(path: TokenStream2) -> TokenStream2 {
+    let m = mapping();
+    quote!(other => ::core::result::Result::Err(#m::FromItemError::UnknownVariant(#path, ::std::string::String::from(other))),)
+}
+
+// Unit variants are plain strings; anything else is a hash whose only key is
+// the variant name.
+fn external(variants: &[Variant]) -> TokenStream2 {
+    let m = mapping();
+    let c = core();
+    let root = quote!(#c::pointers::ItemPointer::root());
+    let value = quote!(value);
+    let by_string = variants.iter().map(|variant| {
+        let (ctor, name) = (&variant.ctor, &variant.name);
+        match variant.shape {
+            Shape::Unit => quote!(#name => ::core::result::Result::Ok(#ctor),),
+            _ => quote!(#name => ::core::result::Result::Err(#m::FromItemError::wrong_kind(#c::items::ItemKind::Hash, item)),),
+        }
+    });
+    let by_hash = variants.iter().map(|variant| {
+        let name = &variant.name;
+        let value = construct(&variant.ctor, variant.shape, &variant.fields, &value, false);
+        quote!(#name => #m::nested(name, || ::core::result::Result::Ok(#value)),)
+    });
+    let unknown = unknown_variant(root.clone());
+    quote! {
+        if let #c::items::ItemType::TString(name) = item {
+            return match name.get_value().as_str() {
+                #(#by_string)*
+                #unknown
+            };
+        }
+        match #m::single_entry(item) {
+            ::core::option::Option::Some((name, value)) => match name {
+                #(#by_hash)*
+                #unknown
+            },
+            ::core::option::Option::None => ::core::result::Result::Err(#m::FromItemError::wrong_kind(#c::items::ItemKind::Hash, item)),
+        }
+    }
+}
+
+// The variant name sits beside the fields in the same hash.
+fn internal(variants: &[Variant], tag: &str) -> TokenStream2 {
+    let m = mapping();
+    let c = core();
+    let item = quote!(item);
+    let arms = variants.iter().map(|variant| {
+        let name = &variant.name;
+        let value = construct(&variant.ctor, variant.shape, &variant.fields, &item, false);
+        quote!(#name => ::core::result::Result::Ok(#value),)
+    });
+    let unknown = unknown_variant(quote!(#c::pointers::ItemPointer::root().join(#tag)));
+    quote! {
+        #m::expect_kind(item, #c::items::ItemKind::Hash)?;
+        let name: ::std::string::String = #m::hash_field(item, #tag)?;
+        match name.as_str() {
+            #(#arms)*
+            #unknown
+        }
+    }
+}
+
+fn adjacent(variants: &[Variant], tag: &str, content: &str) -> TokenStream2 {
+    let m = mapping();
+    let c = core();
+    let value = quote!(value);
+    let arms = variants.iter().map(|variant| {
+        let (ctor, name) = (&variant.ctor, &variant.name);
+        match variant.shape {
+            Shape::Unit => quote!(#name => ::core::result::Result::Ok(#ctor),),
+            _ => {
+                let value = construct(ctor, variant.shape, &variant.fields, &value, false);
+                quote!(#name => {
+                    let value = #m::hash_member(item, #content)?;
+                    #m::nested(#content, || ::core::result::Result::Ok(#value))
+                },)
+            },
+        }
+    });
+    let unknown = unknown_variant(quote!(#c::pointers::ItemPointer::root().join(#tag)));
+    quote! {
+        #m::expect_kind(item, #c::items::ItemKind::Hash)?;
+        let name: ::std::string::String = #m::hash_field(item, #tag)?;
+        match name.as_str() {
+            #(#arms)*
+            #unknown
+        }
+    }
+}
+
+// Variants are tried in declaration order and the first that fits wins.
+fn untagged(variants: &[Variant]) -> TokenStream2 {
+    let m = mapping();
+    let c = core();
+    let item = quote!(item);
+    let attempts = variants.iter().map(|variant| {
+        let value = construct(&variant.ctor, variant.shape, &variant.fields, &item, true);
+        quote! {
+            if let ::core::result::Result::Ok(value) = #m::attempt(|| ::core::result::Result::Ok(#value)) {
+                return ::core::result::Result::Ok(value);
+            }
+        }
+    });
+    quote! {
+        #(#attempts)*
+        ::core::result::Result::Err(#m::FromItemError::NoVariantMatched(#c::pointers::ItemPointer::root(), item.get_kind()))
+    }
+}
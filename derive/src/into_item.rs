@@ -0,0 +1,167 @@
+use proc_macro2::TokenStream as TokenStream2;
+use quote::quote;
+use syn::spanned::Spanned;
+use syn::{Data, DeriveInput, Error, Result, Type};
+
+use crate::attrs::{self, Field, Shape, Tagging};
+use crate::{core, mapping, with_bound};
+
+pub fn expand(input: &DeriveInput) -> Result<TokenStream2> {
+    let m = mapping();
+    let name = &input.ident;
+    let mut generics = with_bound(&input.generics, quote!(#m::IntoItem));
+    // Types whose members get merged into a hash; each must be an IntoHash.
+    let mut merged: Vec<&Type> = Vec::new();
+    let always_hash;
+    let body = match &input.data {
+        Data::Struct(data) => {
+            if !matches!(attrs::tagging(&input.attrs)?, Tagging::External) {
+                return Err(Error::new_spanned(input, "tagging attributes only apply to enums"));
+            }
+            let fields = attrs::fields(&data.fields)?;
+            merged.extend(fields.iter().filter(|f| f.attrs.flatten).map(|f| f.ty));
+            always_hash = attrs::shape(&data.fields) == Shape::Named;
+            let access = |field: &Field| {
+                let member = &field.member;
+                quote!(&self.#member)
+            };
+            value_of(attrs::shape(&data.fields), &fields, access)
+        },
+        Data::Enum(data) => {
+            let tagging = attrs::tagging(&input.attrs)?;
+            always_hash = matches!(tagging, Tagging::Internal(_) | Tagging::Adjacent(..));
+            let mut arms = Vec::new();
+            for variant in &data.variants {
+                let ident = &variant.ident;
+                let key = attrs::variant_name(ident, &variant.attrs)?;
+                let shape = attrs::shape(&variant.fields);
+                let fields = attrs::fields(&variant.fields)?;
+                merged.extend(fields.iter().filter(|f| f.attrs.flatten).map(|f| f.ty));
+                if shape == Shape::Newtype && matches!(tagging, Tagging::Internal(_)) {
+                    merged.push(fields[0].ty);
+                }
+                let bindings = fields.iter().filter(|f| !f.attrs.skip).map(|f| &f.binding);
+                let pattern = match shape {
+                    Shape::Unit => quote!(Self::#ident),
+                    Shape::Newtype | Shape::Tuple => quote!(Self::#ident(#(#bindings),*)),
+                    Shape::Named => {
+                        let members = fields.iter().filter(|f| !f.attrs.skip).map(|f| &f.member);
+                        quote!(Self::#ident { #(#members: #bindings,)* .. })
+                    },
+                };
+                let content = match shape {
+                    Shape::Unit => None,
+                    _ => Some(value_of(shape, &fields, |field| {
+                        let binding = &field.binding;
+                        quote!(#binding)
+                    })),
+                };
+                let value = tagged(&tagging, &key, shape, content)
+                    .ok_or_else(|| Error::new_spanned(variant, "internally tagged enums cannot hold tuple variants"))?;
+                arms.push(quote!(#pattern => #value,));
+            }
+            quote! {
+                match self {
+                    #(#arms)*
+                }
+            }
+        },
+        Data::Union(_) => return Err(Error::new_spanned(input, "IntoItem cannot be derived for unions")),
+    };
+    let predicates = &mut generics.make_where_clause().predicates;
+    for ty in merged {
+        predicates.push(syn::parse_quote_spanned!(ty.span()=> #ty: #m::IntoHash));
+    }
+    let (impl_generics, type_generics, where_clause) = generics.split_for_impl();
+    let into_hash = always_hash.then(|| quote! {
+        #[automatically_derived]
+        impl #impl_generics #m::IntoHash for #name #type_generics #where_clause {}
+    });
+    let c = core();
+    Ok(quote! {
+        #[automatically_derived]
+        impl #impl_generics #m::IntoItem for #name #type_generics #where_clause {
+            fn into_item(&self) -> #c::items::ItemType {
+                #body
+            }
+        }
+        #into_hash
+    })
+}
+
+// The item for a struct or variant body, reading each field through `access`.
+fn value_of<F: Fn(&Field) -> TokenStream2>(shape: Shape, fields: &[Field], access: F) -> TokenStream2 {
+    let m = mapping();
+    let c = core();
+    match shape {
+        Shape::Unit => quote!(#c::nulls::ItemNull::new()),
+        Shape::Newtype => {
+            let value = access(&fields[0]);
+            quote!(#m::IntoItem::into_item(#value))
+        },
+        Shape::Tuple => {
+            let values = fields.iter().map(&access);
+            quote! {{
+                let mut list = #c::lists::ItemList::new();
+                #(list.add_item(#m::IntoItem::into_item(#values), ::core::option::Option::None).ok();)*
+                list
+            }}
+        },
+        Shape::Named => {
+            let members = fields.iter().filter(|f| !f.attrs.skip).map(|field| {
+                let value = access(field);
+                let key = &field.key;
+                if field.attrs.flatten {
+                    quote!(#m::flatten_into(&mut hash, #m::IntoItem::into_item(#value));)
+                } else {
+                    quote!(hash.add_item(#m::IntoItem::into_item(#value), ::core::option::Option::Some(#key)).ok();)
+                }
+            });
+            quote! {{
+                #[allow(unused_mut)]
+                let mut hash = #c::hashes::ItemHash::new();
+                #(#members)*
+                hash
+            }}
+        },
+    }
+}
+
+// Wraps a variant's content according to the enum's tagging; None when the
+// combination cannot be represented.
+fn tagged(tagging: &Tagging, name: &str, shape: Shape, content: Option<TokenStream2>) -> Option<TokenStream2> {
+    let m = mapping();
+    let c = core();
+    let item_type = quote!(#c::items::ItemType);
+    let new_hash = quote!(let mut hash = #c::hashes::ItemHash::new(););
+    let some = quote!(::core::option::Option::Some);
+    Some(match (tagging, content) {
+        (Tagging::External, None) => quote!(#item_type::from(#name)),
+        (Tagging::External, Some(content)) => quote! {{
+            #new_hash
+            hash.add_item(#content, #some(#name)).ok();
+            hash
+        }},
+        (Tagging::Internal(_), Some(_)) if shape == Shape::Tuple => return None,
+        (Tagging::Internal(tag), content) => {
+            let content = content.map(|content| quote!(#m::flatten_into(&mut hash, #content);));
+            quote! {{
+                #new_hash
+                hash.add_item(#item_type::from(#name), #some(#tag)).ok();
+                #content
+                hash
+            }}
+        },
+        (Tagging::Adjacent(tag, key), content) => {
+            let content = content.map(|content| quote!(hash.add_item(#content, #some(#key)).ok();));
+            quote! {{
+                #new_hash
+                hash.add_item(#item_type::from(#name), #some(#tag)).ok();
+                #content
+                hash
+            }}
+        },
+        (Tagging::Untagged, None) => quote!(#c::nulls::ItemNull::new()),
+        (Tagging::Untagged, Some(content)) => content,
+    })
+}
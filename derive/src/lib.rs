@@ -0,0 +1,55 @@
+//! Derive macros for the itemdoc `IntoItem` and `FromItem` traits.
+//!
+//! Structs with named fields map to hashes, newtype structs to their inner
+//! value, tuple structs to lists and unit structs to null. Enums are
+//! externally tagged unless the container says otherwise:
+//!
+//! - `#[item(tag = "type")]` stores the variant name under `type` inside the
+//!   variant's own hash (internal tagging);
+//! - `#[item(tag = "t", content = "c")]` stores the name under `t` and the
+//!   variant's value under `c` (adjacent tagging);
+//! - `#[item(untagged)]` stores the value alone and tries each variant in
+//!   order when extracting.
+//!
+//! Fields accept `rename = "key"`, `skip`, `default`, `default = "path"` and
+//! `flatten`; variants accept `rename = "name"`. Flattened fields and the
+//! newtype variants of internally tagged enums must hold an `IntoHash` type,
+//! so a payload that would not be a hash is rejected at compile time.
+
+use proc_macro::TokenStream;
+use proc_macro2::TokenStream as TokenStream2;
+use quote::quote;
+use syn::{parse_macro_input, DeriveInput, Error, Generics};
+
+mod attrs;
+mod from_item;
+mod into_item;
+
+#[proc_macro_derive(IntoItem, attributes(item))]
+pub fn derive_into_item(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    into_item::expand(&input).unwrap_or_else(Error::into_compile_error).into()
+}
+
+#[proc_macro_derive(FromItem, attributes(item))]
+pub fn derive_from_item(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    from_item::expand(&input).unwrap_or_else(Error::into_compile_error).into()
+}
+
+fn mapping() -> TokenStream2 {
+    quote!(::itemdoc::itemdoc::core::mapping)
+}
+
+fn core() -> TokenStream2 {
+    quote!(::itemdoc::itemdoc::core)
+}
+
+// Requires every type parameter to implement the derived trait.
+fn with_bound(generics: &Generics, bound: TokenStream2) -> Generics {
+    let mut generics = generics.clone();
+    for param in generics.type_params_mut() {
+        param.bounds.push(syn::parse_quote!(#bound));
+    }
+    generics
+}
@@ -0,0 +1,803 @@
+use std::collections::HashMap;
+use std::io::Read;
+
+use super::items::*;
+use super::utility::RustType;
+use super::nulls::ItemNull;
+use super::booleans::ItemBoolean;
+use super::numbers::{ItemNumber, NumberValue};
+use super::strings::ItemString;
+use super::lists::ItemList;
+use super::hashes::ItemHash;
+use super::pointers::{self, ItemPointer};
+use super::errors::DocError;
+use crate::itemdoc::exports::core::ItemOutput;
+use crate::itemdoc::imports::json_events::{JsonEvent, JsonEventReader};
+
+// Hashes up to this size find keys by scanning; larger ones keep a map.
+const SCAN_LIMIT: usize = 16;
+
+// Addresses a node of an ItemArena. Ids stay valid until the arena is
+// compacted and mean nothing to any other arena. Methods returning a Result
+// report an id the arena never handed out as ItemNotFound; the others panic
+// on one, and an id kept across compact() may address a different node.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct NodeId(u32);
+
+impl NodeId {
+    pub fn get_index(&self) -> usize {
+        self.0 as usize
+    }
+}
+
+// An interned hash key, shared by every hash of the arena using that key.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct KeyId(u32);
+
+#[derive(Debug, Clone)]
+enum Node {
+    Null,
+    Boolean(bool),
+    Number(NumberValue),
+    // A byte range of the arena's text buffer.
+    String(usize, usize),
+    List(Vec<NodeId>),
+    Hash(HashMembers),
+}
+
+// The members of a hash node, in order, with a map from key to slot once
+// there are more than SCAN_LIMIT of them.
+#[derive(Debug, Clone, Default)]
+struct HashMembers {
+    members: Vec<(KeyId, NodeId)>,
+    slots: Option<HashMap<KeyId, usize>>,
+}
+
+impl HashMembers {
+
+    fn new(members: Vec<(KeyId, NodeId)>) -> Self {
+        let mut hash = HashMembers { members, slots: None };
+        hash.reindex();
+        hash
+    }
+
+    fn position(&self, key: KeyId) -> Option<usize> {
+        match &self.slots {
+            Some(slots) => slots.get(&key).copied(),
+            None => self.members.iter().position(|(k, _)| *k == key),
+        }
+    }
+
+    // An existing key has its member replaced in place, like ItemHash.
+    fn insert(&mut self, key: KeyId, member: NodeId) {
+        if let Some(slot) = self.position(key) {
+            self.members[slot].1 = member;
+            return;
+        }
+        self.members.push((key, member));
+        let slot = self.members.len() - 1;
+        match self.slots.as_mut() {
+            Some(slots) => { slots.insert(key, slot); },
+            None => self.reindex(),
+        }
+    }
+
+    fn remove(&mut self, slot: usize) {
+        self.members.remove(slot);
+        self.reindex();
+    }
+
+    // As with ItemHash, inserting a key that is already present moves it.
+    fn insert_at(&mut self, index: usize, key: KeyId, member: NodeId) {
+        if let Some(slot) = self.position(key) {
+            self.members.remove(slot);
+        }
+        self.members.insert(index, (key, member));
+        self.reindex();
+    }
+
+    fn clear(&mut self) {
+        self.members.clear();
+        self.slots = None;
+    }
+
+    fn reindex(&mut self) {
+        self.slots = (self.members.len() > SCAN_LIMIT)
+            .then(|| self.members.iter().enumerate().map(|(slot, (key, _))| (*key, slot)).collect());
+    }
+
+}
+
+// A document kept in contiguous storage: nodes live in one vector and refer
+// to their members by id, string values share one text buffer and hash keys
+// are interned. Removing or replacing members leaves the old nodes in place
+// until compact() is called. The container and pointer operations mirror
+// those of ItemType; queries, patches, merges, diffs and validation work on
+// the ItemType given by to_item().
+#[derive(Debug, Clone)]
+pub struct ItemArena {
+    nodes: Vec<Node>,
+    text: String,
+    keys: Vec<Box<str>>,
+    key_ids: HashMap<Box<str>, KeyId>,
+    root: NodeId,
+}
+
+impl ItemArena {
+
+    // An arena whose root is null.
+    pub fn new() -> Self {
+        ItemArena::from_item(&ItemNull::new())
+    }
+
+    // Panics if the item has more than u32::MAX nodes or distinct keys.
+    pub fn from_item(item: &ItemType) -> Self {
+        let mut arena = ItemArena::empty();
+        arena.root = arena.insert(item).expect("an arena holds at most u32::MAX nodes and keys");
+        arena
+    }
+
+    pub fn parse_json_str(input: &str) -> Result<Self, DocError> {
+        ItemArena::parse_json_reader(input.as_bytes())
+    }
+
+    // Builds the arena straight from parser events, without an ItemType tree
+    // in between.
+    pub fn parse_json_reader<R: Read>(reader: R) -> Result<Self, DocError> {
+        let mut arena = ItemArena::empty();
+        let mut events = JsonEventReader::new(reader);
+        let mut open: Vec<NodeId> = Vec::new();
+        let mut key: Option<String> = None;
+
+        while let Some(event) = events.next_event()? {
+            let opens = matches!(event, JsonEvent::ListOpen | JsonEvent::HashOpen);
+            let node = match event {
+                JsonEvent::Key(k) => {
+                    key = Some(k);
+                    continue;
+                },
+                JsonEvent::ListClose | JsonEvent::HashClose => {
+                    if let Some(Node::Hash(hash)) = open.pop().map(|id| &mut arena.nodes[id.get_index()]) {
+                        dedup_members(&mut hash.members);
+                        hash.reindex();
+                    }
+                    continue;
+                },
+                JsonEvent::ListOpen | JsonEvent::EmptyList => Node::List(Vec::new()),
+                JsonEvent::HashOpen | JsonEvent::EmptyHash => Node::Hash(HashMembers::default()),
+                JsonEvent::Null => Node::Null,
+                JsonEvent::Bool(b) => Node::Boolean(b),
+                JsonEvent::Number(n) => Node::Number(n),
+                JsonEvent::String(s) => arena.text_node(&s),
+            };
+            let id = arena.push(node)?;
+            match (open.last().copied(), key.take()) {
+                (Some(parent), Some(name)) => {
+                    let name = arena.intern(&name)?;
+                    if let Node::Hash(hash) = &mut arena.nodes[parent.get_index()] {
+                        hash.members.push((name, id));
+                    }
+                },
+                (Some(parent), None) => {
+                    if let Node::List(members) = &mut arena.nodes[parent.get_index()] {
+                        members.push(id);
+                    }
+                },
+                (None, _) => arena.root = id,
+            }
+            if opens {
+                open.push(id);
+            }
+        }
+        if arena.nodes.is_empty() {
+            arena.root = arena.push(Node::Null)?;
+        }
+        Ok(arena)
+    }
+
+    pub fn to_item(&self) -> ItemType {
+        self.node_to_item(self.root)
+    }
+    // Writes the same events as ItemType::export would for to_item().
+    pub fn export(&self, output: &mut dyn ItemOutput) -> Result<(), DocError> {
+        self.export_node(self.root, None, output)
+    }
+    pub fn node_to_item(&self, id: NodeId) -> ItemType {
+        match self.node(id) {
+            Node::Null => ItemNull::new(),
+            Node::Boolean(b) => ItemBoolean::new(Some(*b)),
+            Node::Number(n) => ItemNumber::with_value(n.clone()),
+            Node::String(start, end) => ItemString::new(Some(self.text[*start..*end].to_string())),
+            Node::List(members) => {
+                let mut list = ItemList::new();
+                for member in members {
+                    list.add_item(self.node_to_item(*member), None).ok();
+                }
+                list
+            },
+            Node::Hash(hash_members) => {
+                let mut hash = ItemHash::new();
+                for (key, member) in &hash_members.members {
+                    hash.add_item(self.node_to_item(*member), Some(self.get_key_name(*key))).ok();
+                }
+                hash
+            },
+        }
+    }
+
+
+    pub fn get_root(&self) -> NodeId {
+        self.root
+    }
+    // Every node stored, including those no longer reachable from the root.
+    pub fn node_count(&self) -> usize {
+        self.nodes.len()
+    }
+    pub fn key_count(&self) -> usize {
+        self.keys.len()
+    }
+    pub fn get_key_id(&self, name: &str) -> Option<KeyId> {
+        self.key_ids.get(name).copied()
+    }
+    pub fn get_key_name(&self, key: KeyId) -> &str {
+        &self.keys[key.0 as usize]
+    }
+
+
+    pub fn is_null(&self, id: NodeId) -> bool {
+        matches!(self.node(id), Node::Null)
+    }
+    pub fn is_boolean(&self, id: NodeId) -> bool {
+        matches!(self.node(id), Node::Boolean(_))
+    }
+    pub fn is_number(&self, id: NodeId) -> bool {
+        matches!(self.node(id), Node::Number(_))
+    }
+    pub fn is_string(&self, id: NodeId) -> bool {
+        matches!(self.node(id), Node::String(_, _))
+    }
+    pub fn is_container(&self, id: NodeId) -> bool {
+        matches!(self.node(id), Node::List(_) | Node::Hash(_))
+    }
+    pub fn is_list(&self, id: NodeId) -> bool {
+        matches!(self.node(id), Node::List(_))
+    }
+    pub fn is_hash(&self, id: NodeId) -> bool {
+        matches!(self.node(id), Node::Hash(_))
+    }
+    pub fn get_kind(&self, id: NodeId) -> ItemKind {
+        match self.node(id) {
+            Node::Null => ItemKind::Null,
+            Node::Boolean(_) => ItemKind::Boolean,
+            Node::Number(_) => ItemKind::Number,
+            Node::String(_, _) => ItemKind::String,
+            Node::List(_) => ItemKind::List,
+            Node::Hash(_) => ItemKind::Hash,
+        }
+    }
+    pub fn get_type_name(&self, id: NodeId) -> &'static str {
+        self.get_kind(id).get_name()
+    }
+
+
+    pub fn get_bool(&self, id: NodeId) -> Option<bool> {
+        match self.node(id) {
+            Node::Boolean(b) => Some(*b),
+            _ => None,
+        }
+    }
+    pub fn get_number(&self, id: NodeId) -> Option<&NumberValue> {
+        match self.node(id) {
+            Node::Number(n) => Some(n),
+            _ => None,
+        }
+    }
+    pub fn get_str(&self, id: NodeId) -> Option<&str> {
+        match self.node(id) {
+            Node::String(start, end) => Some(&self.text[*start..*end]),
+            _ => None,
+        }
+    }
+    // Overwrites the node with a copy of the item, keeping its id.
+    pub fn set_item(&mut self, id: NodeId, item: &ItemType) -> Result<(), DocError> {
+        self.try_node(id)?;
+        let node = self.build(item)?;
+        self.nodes[id.get_index()] = node;
+        Ok(())
+    }
+
+
+    pub fn get_item<'a, L: Into<ContainerKey<'a>>>(&self, id: NodeId, lookup: L) -> Result<Option<NodeId>, DocError> {
+        Ok(self.slot_of(id, lookup.into())?.map(|(_, member)| member))
+    }
+    pub fn iter(&self, id: NodeId) -> ArenaIter<'_> {
+        let members = match self.node(id) {
+            Node::List(members) => Members::List(members.iter().enumerate()),
+            Node::Hash(hash) => Members::Hash(hash.members.iter()),
+            _ => Members::Empty,
+        };
+        ArenaIter { arena: self, members }
+    }
+
+
+    pub fn add_null(&mut self, id: NodeId, key: Option<&str>) -> Result<NodeId, DocError> {
+        self.add_node(id, key, Node::Null)
+    }
+    pub fn add_value<V: Into<RustType>>(&mut self, id: NodeId, value: V, key: Option<&str>) -> Result<NodeId, DocError> {
+        self.check_member(id, key)?;
+        let node = match value.into() {
+            RustType::Bool(b) => Node::Boolean(b),
+            RustType::Number(n) => Node::Number(n),
+            RustType::String(s) => self.text_node(&s),
+        };
+        self.add_node(id, key, node)
+    }
+    pub fn add_item(&mut self, id: NodeId, item: &ItemType, key: Option<&str>) -> Result<NodeId, DocError> {
+        self.check_member(id, key)?;
+        let node = self.build(item)?;
+        self.add_node(id, key, node)
+    }
+    pub fn add_list(&mut self, id: NodeId, key: Option<&str>) -> Result<NodeId, DocError> {
+        self.add_node(id, key, Node::List(Vec::new()))
+    }
+    pub fn add_hash(&mut self, id: NodeId, key: Option<&str>) -> Result<NodeId, DocError> {
+        self.add_node(id, key, Node::Hash(HashMembers::default()))
+    }
+
+
+    // The removed member keeps its id, detached, until the arena is compacted.
+    pub fn remove_item<'a, L: Into<ContainerKey<'a>>>(&mut self, id: NodeId, lookup: L) -> Result<Option<NodeId>, DocError> {
+        let Some((slot, member)) = self.slot_of(id, lookup.into())? else { return Ok(None) };
+        match &mut self.nodes[id.get_index()] {
+            Node::List(members) => { members.remove(slot); },
+            Node::Hash(hash) => hash.remove(slot),
+            _ => {},
+        }
+        Ok(Some(member))
+    }
+    // Returns the id of the detached member that was replaced.
+    pub fn replace_item<'a, L: Into<ContainerKey<'a>>>(&mut self, id: NodeId, lookup: L, item: &ItemType) -> Result<NodeId, DocError> {
+        let lookup = lookup.into();
+        let (slot, member) = self.existing(id, lookup)?;
+        let replacement = self.insert(item)?;
+        match &mut self.nodes[id.get_index()] {
+            Node::List(members) => members[slot] = replacement,
+            Node::Hash(hash) => hash.members[slot].1 = replacement,
+            _ => {},
+        }
+        Ok(member)
+    }
+    pub fn insert_at(&mut self, id: NodeId, index: usize, item: &ItemType, key: Option<&str>) -> Result<NodeId, DocError> {
+        self.check_member(id, key)?;
+        let len = match key {
+            Some(name) => self.count(id) - usize::from(self.has_key(id, name)),
+            None => self.count(id),
+        };
+        if index > len {
+            return Err(out_of_range(index));
+        }
+        let member = self.insert(item)?;
+        let key = key.map(|name| self.intern(name)).transpose()?;
+        match (&mut self.nodes[id.get_index()], key) {
+            (Node::List(members), None) => members.insert(index, member),
+            (Node::Hash(hash), Some(key)) => hash.insert_at(index, key, member),
+            _ => return Err(ItemError::ItemAdditionFailed.into()),
+        }
+        Ok(member)
+    }
+    // Adds the member when the key is new or the index is just past the end,
+    // and otherwise returns the id of the detached member it replaced.
+    pub fn add_or_replace<'a, L: Into<ContainerKey<'a>>>(&mut self, id: NodeId, lookup: L, item: &ItemType) -> Result<Option<NodeId>, DocError> {
+        let lookup = lookup.into();
+        let slot = self.place_of(id, lookup)?;
+        let node = self.build(item)?;
+        Ok(self.place(id, lookup, slot, node)?.1)
+    }
+    pub fn swap<'a, L: Into<ContainerKey<'a>>>(&mut self, id: NodeId, a: L, b: L) -> Result<(), DocError> {
+        let (a, _) = self.existing(id, a.into())?;
+        let (b, _) = self.existing(id, b.into())?;
+        match &mut self.nodes[id.get_index()] {
+            Node::List(members) => members.swap(a, b),
+            Node::Hash(hash) => {
+                hash.members.swap(a, b);
+                hash.reindex();
+            },
+            _ => {},
+        }
+        Ok(())
+    }
+    pub fn move_item<'a, L: Into<ContainerKey<'a>>>(&mut self, id: NodeId, lookup: L, index: usize) -> Result<(), DocError> {
+        let (from, _) = self.existing(id, lookup.into())?;
+        if index >= self.count(id) {
+            return Err(out_of_range(index));
+        }
+        match &mut self.nodes[id.get_index()] {
+            Node::List(members) => {
+                let member = members.remove(from);
+                members.insert(index, member);
+            },
+            Node::Hash(hash) => {
+                let member = hash.members.remove(from);
+                hash.members.insert(index, member);
+                hash.reindex();
+            },
+            _ => {},
+        }
+        Ok(())
+    }
+    pub fn truncate(&mut self, id: NodeId, len: usize) -> Result<(), DocError> {
+        self.try_node(id)?;
+        match &mut self.nodes[id.get_index()] {
+            Node::List(members) => { members.truncate(len); Ok(()) },
+            Node::Hash(hash) => {
+                hash.members.truncate(len);
+                hash.reindex();
+                Ok(())
+            },
+            _ => Err(ItemError::NotAnItemContainer.into()),
+        }
+    }
+    pub fn clear(&mut self, id: NodeId) -> Result<(), DocError> {
+        self.try_node(id)?;
+        match &mut self.nodes[id.get_index()] {
+            Node::List(members) => { members.clear(); Ok(()) },
+            Node::Hash(hash) => { hash.clear(); Ok(()) },
+            _ => Err(ItemError::NotAnItemContainer.into()),
+        }
+    }
+
+
+    pub fn count(&self, id: NodeId) -> usize {
+        match self.node(id) {
+            Node::List(members) => members.len(),
+            Node::Hash(hash) => hash.members.len(),
+            _ => 0,
+        }
+    }
+    pub fn has_key<'a, K: Into<ContainerKey<'a>>>(&self, id: NodeId, key: K) -> bool {
+        matches!(self.slot_of(id, key.into()), Ok(Some(_)))
+    }
+    pub fn get_keys(&self, id: NodeId) -> Result<Vec<ContainerKey<'_>>, DocError> {
+        if !matches!(self.try_node(id)?, Node::List(_) | Node::Hash(_)) {
+            return Err(ItemError::NotAnItemContainer.into());
+        }
+        Ok(self.iter(id).map(|(key, _)| key).collect())
+    }
+
+
+    // Resolves the pointer from the root, with the errors of ItemType::pointer.
+    pub fn pointer(&self, pointer: &ItemPointer) -> Result<NodeId, DocError> {
+        self.pointer_from(self.root, pointer)
+    }
+    pub fn pointer_from(&self, id: NodeId, pointer: &ItemPointer) -> Result<NodeId, DocError> {
+        self.try_node(id)?;
+        let mut current = id;
+        for (position, segment) in pointer.get_segments().iter().enumerate() {
+            let key = self.child_key(current, segment, position).map_err(|e| e.at_pointer(pointer))?;
+            current = self.get_item(current, key)?
+                .ok_or_else(|| DocError::from(ItemError::PointerNotFound(position)).at_pointer(pointer))?;
+        }
+        Ok(current)
+    }
+    // Works like ItemType::set_at_pointer and returns the id of the detached
+    // node that was replaced, if any. An empty pointer replaces the root.
+    pub fn set_at_pointer(&mut self, pointer: &ItemPointer, item: &ItemType, create: bool) -> Result<Option<NodeId>, DocError> {
+        let segments = pointer.get_segments();
+        let Some((last, parents)) = segments.split_last() else {
+            let previous = self.root;
+            self.root = self.insert(item)?;
+            return Ok(Some(previous));
+        };
+        let mut current = self.root;
+        for (position, segment) in parents.iter().enumerate() {
+            let key = self.child_key(current, segment, position).map_err(|e| e.at_pointer(pointer))?;
+            current = match self.get_item(current, key)? {
+                Some(member) => member,
+                None if create => {
+                    let container = match pointers::parse_index(&segments[position + 1], 0) {
+                        Some(_) => Node::List(Vec::new()),
+                        None => Node::Hash(HashMembers::default()),
+                    };
+                    let slot = self.place_of(current, key).map_err(|e| pointers::not_found(e, position).at_pointer(pointer))?;
+                    self.place(current, key, slot, container)?.0
+                },
+                None => return Err(DocError::from(ItemError::PointerNotFound(position)).at_pointer(pointer)),
+            };
+        }
+        let position = parents.len();
+        let key = self.child_key(current, last, position).map_err(|e| e.at_pointer(pointer))?;
+        self.add_or_replace(current, key, item).map_err(|e| pointers::not_found(e, position).at_pointer(pointer))
+    }
+    // Returns the id of the detached node.
+    pub fn remove_at_pointer(&mut self, pointer: &ItemPointer) -> Result<NodeId, DocError> {
+        let parent = pointer.parent().ok_or(ItemError::InvalidPointer)?;
+        let position = parent.count();
+        let current = self.pointer(&parent)?;
+        let key = self.child_key(current, &pointer.get_segments()[position], position).map_err(|e| e.at_pointer(pointer))?;
+        self.remove_item(current, key)?
+            .ok_or_else(|| DocError::from(ItemError::PointerNotFound(position)).at_pointer(pointer))
+    }
+
+
+    // Drops the nodes, text and keys that are no longer reachable from the
+    // root. Every id handed out before is invalidated.
+    pub fn compact(&mut self) {
+        let mut compacted = ItemArena::empty();
+        compacted.root = compacted.copy_from(self, self.root).expect("a compacted arena is no larger than the original");
+        *self = compacted;
+    }
+
+
+    fn empty() -> Self {
+        ItemArena { nodes: Vec::new(), text: String::new(), keys: Vec::new(), key_ids: HashMap::new(), root: NodeId(0) }
+    }
+
+    fn node(&self, id: NodeId) -> &Node {
+        &self.nodes[id.get_index()]
+    }
+
+    fn try_node(&self, id: NodeId) -> Result<&Node, DocError> {
+        self.nodes.get(id.get_index()).ok_or_else(|| ItemError::ItemNotFound.into())
+    }
+
+    // Ids are 32 bits wide, so the arena refuses to grow past u32::MAX nodes
+    // or keys.
+    fn push(&mut self, node: Node) -> Result<NodeId, DocError> {
+        let id = NodeId(u32::try_from(self.nodes.len()).map_err(|_| ItemError::ItemAdditionFailed)?);
+        self.nodes.push(node);
+        Ok(id)
+    }
+
+    fn intern(&mut self, name: &str) -> Result<KeyId, DocError> {
+        if let Some(key) = self.key_ids.get(name) {
+            return Ok(*key);
+        }
+        let key = KeyId(u32::try_from(self.keys.len()).map_err(|_| ItemError::ItemAdditionFailed)?);
+        self.keys.push(name.into());
+        self.key_ids.insert(name.into(), key);
+        Ok(key)
+    }
+
+    fn text_node(&mut self, value: &str) -> Node {
+        let start = self.text.len();
+        self.text.push_str(value);
+        Node::String(start, self.text.len())
+    }
+
+    fn insert(&mut self, item: &ItemType) -> Result<NodeId, DocError> {
+        let node = self.build(item)?;
+        self.push(node)
+    }
+
+    // Stores the members of the item and returns the node that holds them.
+    fn build(&mut self, item: &ItemType) -> Result<Node, DocError> {
+        Ok(match item {
+            ItemType::TNull(_) => Node::Null,
+            ItemType::TBoolean(b) => Node::Boolean(b.get_value()),
+            ItemType::TNumber(n) => Node::Number(n.get_number().clone()),
+            ItemType::TString(s) => self.text_node(s.get_value()),
+            ItemType::TList(list) => Node::List(list.iter().map(|(_, member)| self.insert(member)).collect::<Result<_, _>>()?),
+            ItemType::THash(hash) => Node::Hash(HashMembers::new(hash.iter()
+                .filter_map(|(key, member)| match key {
+                    ContainerKey::Key(name) => Some(self.intern(name).and_then(|key| self.insert(member).map(|member| (key, member)))),
+                    ContainerKey::Idx(_) => None,
+                })
+                .collect::<Result<_, _>>()?)),
+        })
+    }
+
+    fn copy_from(&mut self, source: &ItemArena, id: NodeId) -> Result<NodeId, DocError> {
+        let node = match source.node(id) {
+            Node::String(start, end) => self.text_node(&source.text[*start..*end]),
+            Node::List(members) => Node::List(members.iter().map(|member| self.copy_from(source, *member)).collect::<Result<_, _>>()?),
+            Node::Hash(hash) => Node::Hash(HashMembers::new(hash.members.iter()
+                .map(|(key, member)| Ok((self.intern(source.get_key_name(*key))?, self.copy_from(source, *member)?)))
+                .collect::<Result<_, DocError>>()?)),
+            other => other.clone(),
+        };
+        self.push(node)
+    }
+
+    // The position and id of a member, with the errors of ItemType::get_item.
+    fn slot_of(&self, id: NodeId, lookup: ContainerKey<'_>) -> Result<Option<(usize, NodeId)>, DocError> {
+        match (self.try_node(id)?, lookup) {
+            (Node::List(members), ContainerKey::Idx(i)) => Ok(members.get(i).map(|member| (i, *member))),
+            (Node::Hash(hash), ContainerKey::Key(name)) => Ok(self.get_key_id(name)
+                .and_then(|key| hash.position(key))
+                .map(|slot| (slot, hash.members[slot].1))),
+            (_, lookup) => Err(wrong_member(self.get_kind(id), matches!(lookup, ContainerKey::Key(_)))),
+        }
+    }
+
+    fn existing(&self, id: NodeId, lookup: ContainerKey<'_>) -> Result<(usize, NodeId), DocError> {
+        self.slot_of(id, lookup)?.ok_or_else(|| DocError::from(ItemError::ItemNotFound).within(lookup))
+    }
+
+    // The slot a new member for the lookup would replace, or None when it
+    // would be added: a new key, or the index just past the end of a list.
+    fn place_of(&self, id: NodeId, lookup: ContainerKey<'_>) -> Result<Option<usize>, DocError> {
+        match (self.slot_of(id, lookup)?, self.node(id), lookup) {
+            (Some((slot, _)), _, _) => Ok(Some(slot)),
+            (None, Node::List(members), ContainerKey::Idx(i)) if i != members.len() => {
+                Err(DocError::from(ItemError::ItemNotFound).within(lookup))
+            },
+            (None, _, _) => Ok(None),
+        }
+    }
+
+    // Stores the node where place_of said and returns its id along with the
+    // id of the member it replaced.
+    fn place(&mut self, id: NodeId, lookup: ContainerKey<'_>, slot: Option<usize>, node: Node) -> Result<(NodeId, Option<NodeId>), DocError> {
+        let member = self.push(node)?;
+        let key = match (slot, lookup) {
+            (None, ContainerKey::Key(name)) => Some(self.intern(name)?),
+            _ => None,
+        };
+        let replaced = match (&mut self.nodes[id.get_index()], slot, key) {
+            (Node::List(members), Some(slot), _) => Some(std::mem::replace(&mut members[slot], member)),
+            (Node::Hash(hash), Some(slot), _) => Some(std::mem::replace(&mut hash.members[slot].1, member)),
+            (Node::List(members), None, None) => {
+                members.push(member);
+                None
+            },
+            (Node::Hash(hash), None, Some(key)) => {
+                hash.insert(key, member);
+                None
+            },
+            _ => return Err(ItemError::ItemAdditionFailed.into()),
+        };
+        Ok((member, replaced))
+    }
+
+    // The member a pointer segment names, with the errors of ItemType::pointer
+    // left for the caller to locate.
+    fn child_key<'p>(&self, id: NodeId, segment: &'p str, position: usize) -> Result<ContainerKey<'p>, DocError> {
+        match self.try_node(id)? {
+            Node::List(members) => pointers::parse_index(segment, members.len())
+                .map(ContainerKey::Idx)
+                .ok_or_else(|| ItemError::PointerInvalidIndex(position).into()),
+            Node::Hash(_) => Ok(ContainerKey::Key(segment)),
+            _ => Err(ItemError::PointerNotAContainer(position).into()),
+        }
+    }
+
+    // A hash member when key is set, and a list member (or the root) otherwise.
+    fn export_node(&self, id: NodeId, key: Option<&str>, output: &mut dyn ItemOutput) -> Result<(), DocError> {
+        let node = self.node(id);
+        match (node, key) {
+            (Node::Null, None) => return output.list_write_null(),
+            (Node::Null, Some(key)) => return output.hash_write_null(key),
+            (Node::Boolean(b), None) => return output.list_write_bool(*b),
+            (Node::Boolean(b), Some(key)) => return output.hash_write_bool(key, *b),
+            (Node::Number(n), None) => return output.list_write_number(n),
+            (Node::Number(n), Some(key)) => return output.hash_write_number(key, n),
+            (Node::String(start, end), None) => return output.list_write_string(&self.text[*start..*end]),
+            (Node::String(start, end), Some(key)) => return output.hash_write_string(key, &self.text[*start..*end]),
+            (Node::List(members), None) if members.is_empty() => return output.list_write_empty_list(),
+            (Node::List(members), Some(key)) if members.is_empty() => return output.hash_write_empty_list(key),
+            (Node::Hash(hash), None) if hash.members.is_empty() => return output.list_write_empty_hash(),
+            (Node::Hash(hash), Some(key)) if hash.members.is_empty() => return output.hash_write_empty_hash(key),
+            (_, Some(key)) => output.hash_write_key(key)?,
+            (_, None) => {},
+        }
+        match node {
+            Node::List(members) => {
+                output.list_open()?;
+                for (index, member) in members.iter().enumerate() {
+                    self.export_node(*member, None, output).map_err(|e| e.within(index))?;
+                }
+                output.list_close()?;
+            },
+            Node::Hash(hash) => {
+                output.hash_open()?;
+                for (key, member) in &hash.members {
+                    let name = self.get_key_name(*key);
+                    self.export_node(*member, Some(name), output).map_err(|e| e.within(name))?;
+                }
+                output.hash_close()?;
+            },
+            _ => {},
+        }
+        Ok(())
+    }
+
+    fn check_member(&self, id: NodeId, key: Option<&str>) -> Result<(), DocError> {
+        match (self.try_node(id)?, key) {
+            (Node::List(_), None) | (Node::Hash(_), Some(_)) => Ok(()),
+            (_, key) => Err(wrong_member(self.get_kind(id), key.is_some())),
+        }
+    }
+
+    fn add_node(&mut self, id: NodeId, key: Option<&str>, node: Node) -> Result<NodeId, DocError> {
+        self.check_member(id, key)?;
+        let member = self.push(node)?;
+        let key = key.map(|name| self.intern(name)).transpose()?;
+        match (&mut self.nodes[id.get_index()], key) {
+            (Node::List(members), None) => members.push(member),
+            (Node::Hash(hash), Some(key)) => hash.insert(key, member),
+            _ => return Err(ItemError::ItemAdditionFailed.into()),
+        }
+        Ok(member)
+    }
+
+}
+
+impl Default for ItemArena {
+    fn default() -> Self {
+        ItemArena::new()
+    }
+}
+
+impl From<&ItemType> for ItemArena {
+    fn from(item: &ItemType) -> Self {
+        ItemArena::from_item(item)
+    }
+}
+
+impl From<&ItemArena> for ItemType {
+    fn from(arena: &ItemArena) -> Self {
+        arena.to_item()
+    }
+}
+
+// The error for looking up or adding a member by key, or by index, in a node
+// of the wrong kind; the same errors as ItemType gives.
+fn wrong_member(found: ItemKind, keyed: bool) -> DocError {
+    match (found, keyed) {
+        (ItemKind::List, true) => DocError::wrong_kind(ItemError::NotAnItemHash, ItemKind::Hash, found),
+        (ItemKind::Hash, false) => DocError::wrong_kind(ItemError::NotAnItemList, ItemKind::List, found),
+        (_, true) => DocError::wrong_kind(ItemError::NotAnItemContainer, ItemKind::Hash, found),
+        (_, false) => DocError::wrong_kind(ItemError::NotAnItemContainer, ItemKind::List, found),
+    }
+}
+
+// JSON allows a key to repeat; as with ItemHash the last value wins, in the
+// position of the first.
+fn dedup_members(members: &mut Vec<(KeyId, NodeId)>) {
+    let mut slots: Option<HashMap<KeyId, usize>> = (members.len() > SCAN_LIMIT).then(HashMap::new);
+    let mut kept = 0;
+    let mut next = 0;
+    while next < members.len() {
+        let (key, member) = members[next];
+        let existing = match &slots {
+            Some(slots) => slots.get(&key).copied(),
+            None => members[..kept].iter().position(|(k, _)| *k == key),
+        };
+        match existing {
+            Some(slot) => members[slot].1 = member,
+            None => {
+                if let Some(slots) = &mut slots {
+                    slots.insert(key, kept);
+                }
+                members[kept] = (key, member);
+                kept += 1;
+            },
+        }
+        next += 1;
+    }
+    members.truncate(kept);
+}
+
+
+enum Members<'a> {
+    List(std::iter::Enumerate<std::slice::Iter<'a, NodeId>>),
+    Hash(std::slice::Iter<'a, (KeyId, NodeId)>),
+    Empty,
+}
+
+pub struct ArenaIter<'a> {
+    arena: &'a ItemArena,
+    members: Members<'a>,
+}
+
+impl<'a> Iterator for ArenaIter<'a> {
+    type Item = (ContainerKey<'a>, NodeId);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        match &mut self.members {
+            Members::List(members) => members.next().map(|(i, member)| (ContainerKey::Idx(i), *member)),
+            Members::Hash(members) => members.next().map(|(key, member)| (ContainerKey::Key(self.arena.get_key_name(*key)), *member)),
+            Members::Empty => None,
+        }
+    }
+}
@@ -1,33 +1,42 @@
-use std::error::Error;
+use std::fmt;
 
 use super::items::*;
+use super::errors::DocError;
 use super::utility::*;
 
-#[derive(PartialEq)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct ItemBoolean {
     value: bool,
 }
 
 impl ItemBoolean {
 
+    #[allow(clippy::new_ret_no_self)]
     pub fn new(value: Option<bool>) -> ItemType {
-        let me = ItemType::TBoolean(ItemBoolean { 
+        ItemType::TBoolean(ItemBoolean { 
             value: value.unwrap_or(false)
-        });
-        me
+        })
     }
 
-    pub fn add_null<'a>(&mut self, _key: Option<&'a str>) -> Result<&mut Self, ItemError> {
-        Err(ItemError::NotAnItemContainer)
+    pub fn get_value(&self) -> bool {
+        self.value
     }
-    pub fn add_value<'a, V: Into<RustType>>(&mut self, _value: V, _key: Option<&'a str>) -> Result<(), ItemError> {
-        Err(ItemError::NotAnItemContainer)
+
+    pub fn set_value(&mut self, value: bool) {
+        self.value = value;
+    }
+
+    pub fn add_null(&mut self, _key: Option<&str>) -> Result<&mut Self, DocError> {
+        Err(ItemError::NotAnItemContainer.into())
+    }
+    pub fn add_value<V: Into<RustType>>(&mut self, _value: V, _key: Option<&str>) -> Result<(), DocError> {
+        Err(ItemError::NotAnItemContainer.into())
     }
-    pub fn add_list<'a>(&mut self, _key: Option<&'a str>) -> Result<&mut ItemType, ItemError> {
-        Err(ItemError::NotAnItemContainer)
+    pub fn add_list(&mut self, _key: Option<&str>) -> Result<&mut ItemType, DocError> {
+        Err(ItemError::NotAnItemContainer.into())
     }
-    pub fn add_hash<'a>(&mut self, _key: Option<&'a str>) -> Result<&mut ItemType, ItemError> {
-        Err(ItemError::NotAnItemContainer)
+    pub fn add_hash(&mut self, _key: Option<&str>) -> Result<&mut ItemType, DocError> {
+        Err(ItemError::NotAnItemContainer.into())
     }
 
     pub fn is_null(&self) -> bool { false }
@@ -43,21 +52,23 @@ impl ItemBoolean {
     pub fn has_key(&self, _key: &str) -> bool { false }
     pub fn get_keys(&self) -> Option<Box<dyn Iterator<Item = &String> + '_>> { None }
     pub fn has_item(&self, _item: &ItemType) -> bool { false }
-    pub fn index_of_item(&self, _item: &ItemType) -> Result<Option<usize>, Box<dyn Error>> { 
-        Err(Box::new(ItemError::NotAnItemList))
+    pub fn index_of_item(&self, _item: &ItemType) -> Result<Option<usize>, DocError> { 
+        Err(ItemError::NotAnItemList.into())
     }
-    pub fn key_of_item(&self, _item: &ItemType) -> Result<Option<&String>, Box<dyn Error>> { 
-        Err(Box::new(ItemError::NotAnItemHash))
+    pub fn key_of_item(&self, _item: &ItemType) -> Result<Option<&String>, DocError> { 
+        Err(ItemError::NotAnItemHash.into())
     }
-    pub fn item_by_index(&self, _index: usize) -> Result<Option<&ItemType>, Box<dyn Error>> {
-        Err(Box::new(ItemError::NotAnItemList))
+    pub fn item_by_index(&self, _index: usize) -> Result<Option<&ItemType>, DocError> {
+        Err(ItemError::NotAnItemList.into())
     }
-    pub fn item_by_key(&self, _key: &str) -> Result<Option<&ItemType>, Box<dyn Error>> {
-        Err(Box::new(ItemError::NotAnItemHash))
+    pub fn item_by_key(&self, _key: &str) -> Result<Option<&ItemType>, DocError> {
+        Err(ItemError::NotAnItemHash.into())
     }
 
-    pub fn to_string(&self) -> String {
-        if self.value { "true" } else { "false" }.to_string()
-    }
+}
 
+impl fmt::Display for ItemBoolean {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.value)
+    }
 }
\ No newline at end of file
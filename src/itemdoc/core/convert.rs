@@ -0,0 +1,303 @@
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+
+use super::items::*;
+use super::nulls::ItemNull;
+use super::lists::ItemList;
+use super::hashes::ItemHash;
+use super::utility::RustType;
+
+#[derive(Debug)]
+#[derive(Copy, Clone, PartialEq)]
+pub enum ConversionError {
+    WrongKind { expected: ItemKind, found: ItemKind },
+    OutOfRange { target: &'static str },
+    WrongLength { expected: usize, found: usize },
+}
+
+impl fmt::Display for ConversionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConversionError::WrongKind { expected, found } => write!(f, "Expected a {} item but found a {}!", expected, found),
+            ConversionError::OutOfRange { target } => write!(f, "Number does not fit in {}!", target),
+            ConversionError::WrongLength { expected, found } => write!(f, "Expected a list of {} members but found {}!", expected, found),
+        }
+    }
+}
+
+impl std::error::Error for ConversionError {}
+
+fn wrong_kind(expected: ItemKind, item: &ItemType) -> ConversionError {
+    ConversionError::WrongKind { expected, found: item.get_kind() }
+}
+
+
+// Into ItemType. Scalars go through RustType; collections become lists
+// and hashes of their converted members.
+
+impl<T: Into<RustType>> From<T> for ItemType {
+    fn from(value: T) -> Self {
+        value.into().into_item_type()
+    }
+}
+
+impl<T: Into<ItemType>> From<Option<T>> for ItemType {
+    fn from(value: Option<T>) -> Self {
+        match value {
+            Some(value) => value.into(),
+            None => ItemNull::new(),
+        }
+    }
+}
+
+impl<T: Into<ItemType>> From<Vec<T>> for ItemType {
+    fn from(values: Vec<T>) -> Self {
+        let mut list = ItemList::new();
+        for value in values {
+            list.add_item(value.into(), None).ok();
+        }
+        list
+    }
+}
+
+impl<T: Clone + Into<ItemType>> From<&[T]> for ItemType {
+    fn from(values: &[T]) -> Self {
+        ItemType::from(values.to_vec())
+    }
+}
+
+impl<T: Into<ItemType>, const N: usize> From<[T; N]> for ItemType {
+    fn from(values: [T; N]) -> Self {
+        ItemType::from(Vec::from(values))
+    }
+}
+
+// HashMap iteration order is arbitrary, so the hash keys are sorted to keep
+// output stable.
+impl<T: Into<ItemType>> From<HashMap<String, T>> for ItemType {
+    fn from(values: HashMap<String, T>) -> Self {
+        let mut entries: Vec<(String, T)> = values.into_iter().collect();
+        entries.sort_by(|a, b| a.0.cmp(&b.0));
+        from_entries(entries)
+    }
+}
+
+impl<T: Into<ItemType>> From<BTreeMap<String, T>> for ItemType {
+    fn from(values: BTreeMap<String, T>) -> Self {
+        from_entries(values)
+    }
+}
+
+fn from_entries<T: Into<ItemType>, I: IntoIterator<Item = (String, T)>>(entries: I) -> ItemType {
+    let mut hash = ItemHash::new();
+    for (key, value) in entries {
+        hash.add_item(value.into(), Some(&key)).ok();
+    }
+    hash
+}
+
+// Tuples become lists, one member per field.
+macro_rules! tuple_into_item {
+    ($($name:ident),+) => {
+        impl<$($name: Into<ItemType>),+> From<($($name,)+)> for ItemType {
+            #[allow(non_snake_case)]
+            fn from(($($name,)+): ($($name,)+)) -> Self {
+                let mut list = ItemList::new();
+                $(list.add_item($name.into(), None).ok();)+
+                list
+            }
+        }
+    };
+}
+
+tuple_into_item!(A);
+tuple_into_item!(A, B);
+tuple_into_item!(A, B, C);
+tuple_into_item!(A, B, C, D);
+tuple_into_item!(A, B, C, D, E);
+tuple_into_item!(A, B, C, D, E, F);
+
+
+// Out of ItemType. Numbers must fit the target exactly except for the
+// float types, which take the nearest value.
+
+impl TryFrom<&ItemType> for bool {
+    type Error = ConversionError;
+
+    fn try_from(item: &ItemType) -> Result<Self, Self::Error> {
+        match item {
+            ItemType::TBoolean(mapped) => Ok(mapped.get_value()),
+            _ => Err(wrong_kind(ItemKind::Boolean, item)),
+        }
+    }
+}
+
+impl TryFrom<&ItemType> for String {
+    type Error = ConversionError;
+
+    fn try_from(item: &ItemType) -> Result<Self, Self::Error> {
+        <&str>::try_from(item).map(String::from)
+    }
+}
+
+impl<'a> TryFrom<&'a ItemType> for &'a str {
+    type Error = ConversionError;
+
+    fn try_from(item: &'a ItemType) -> Result<Self, Self::Error> {
+        match item {
+            ItemType::TString(mapped) => Ok(mapped.get_value().as_str()),
+            _ => Err(wrong_kind(ItemKind::String, item)),
+        }
+    }
+}
+
+impl TryFrom<&ItemType> for f64 {
+    type Error = ConversionError;
+
+    fn try_from(item: &ItemType) -> Result<Self, Self::Error> {
+        match item {
+            ItemType::TNumber(mapped) => Ok(mapped.get_number().to_f64()),
+            _ => Err(wrong_kind(ItemKind::Number, item)),
+        }
+    }
+}
+
+impl TryFrom<&ItemType> for f32 {
+    type Error = ConversionError;
+
+    fn try_from(item: &ItemType) -> Result<Self, Self::Error> {
+        f64::try_from(item).map(|value| value as f32)
+    }
+}
+
+macro_rules! integer_from_item {
+    ($($target:ty),+) => {
+        $(
+        impl TryFrom<&ItemType> for $target {
+            type Error = ConversionError;
+
+            fn try_from(item: &ItemType) -> Result<Self, Self::Error> {
+                let out_of_range = ConversionError::OutOfRange { target: stringify!($target) };
+                match item {
+                    ItemType::TNumber(mapped) => mapped.as_i128()
+                        .and_then(|n| <$target>::try_from(n).ok())
+                        .ok_or(out_of_range),
+                    _ => Err(wrong_kind(ItemKind::Number, item)),
+                }
+            }
+        }
+        )+
+    };
+}
+
+integer_from_item!(i8, i16, i32, i64, i128, isize, u8, u16, u32, u64, usize);
+
+impl<'a, T> TryFrom<&'a ItemType> for Option<T>
+where
+    T: TryFrom<&'a ItemType, Error = ConversionError>,
+{
+    type Error = ConversionError;
+
+    fn try_from(item: &'a ItemType) -> Result<Self, Self::Error> {
+        match item {
+            ItemType::TNull(_) => Ok(None),
+            _ => T::try_from(item).map(Some),
+        }
+    }
+}
+
+impl<'a, T> TryFrom<&'a ItemType> for Vec<T>
+where
+    T: TryFrom<&'a ItemType, Error = ConversionError>,
+{
+    type Error = ConversionError;
+
+    fn try_from(item: &'a ItemType) -> Result<Self, Self::Error> {
+        match item {
+            ItemType::TList(list) => list.iter().map(|(_, member)| T::try_from(member)).collect(),
+            _ => Err(wrong_kind(ItemKind::List, item)),
+        }
+    }
+}
+
+impl<'a, T, const N: usize> TryFrom<&'a ItemType> for [T; N]
+where
+    T: TryFrom<&'a ItemType, Error = ConversionError>,
+{
+    type Error = ConversionError;
+
+    fn try_from(item: &'a ItemType) -> Result<Self, Self::Error> {
+        let values = list_members(item, N)?.into_iter().map(T::try_from).collect::<Result<Vec<T>, _>>()?;
+        values.try_into().map_err(|values: Vec<T>| ConversionError::WrongLength { expected: N, found: values.len() })
+    }
+}
+
+// Tuples are read from lists with exactly one member per field.
+macro_rules! tuple_from_item {
+    ($len:literal; $($name:ident $index:tt),+) => {
+        impl<'a, $($name),+> TryFrom<&'a ItemType> for ($($name,)+)
+        where
+            $($name: TryFrom<&'a ItemType, Error = ConversionError>),+
+        {
+            type Error = ConversionError;
+
+            fn try_from(item: &'a ItemType) -> Result<Self, Self::Error> {
+                let members = list_members(item, $len)?;
+                Ok(($($name::try_from(members[$index])?,)+))
+            }
+        }
+    };
+}
+
+tuple_from_item!(1; A 0);
+tuple_from_item!(2; A 0, B 1);
+tuple_from_item!(3; A 0, B 1, C 2);
+tuple_from_item!(4; A 0, B 1, C 2, D 3);
+tuple_from_item!(5; A 0, B 1, C 2, D 3, E 4);
+tuple_from_item!(6; A 0, B 1, C 2, D 3, E 4, F 5);
+
+fn list_members(item: &ItemType, len: usize) -> Result<Vec<&ItemType>, ConversionError> {
+    match item {
+        ItemType::TList(list) if list.count() == len => Ok(list.iter().map(|(_, member)| member).collect()),
+        ItemType::TList(list) => Err(ConversionError::WrongLength { expected: len, found: list.count() }),
+        _ => Err(wrong_kind(ItemKind::List, item)),
+    }
+}
+
+impl<'a, T> TryFrom<&'a ItemType> for HashMap<String, T>
+where
+    T: TryFrom<&'a ItemType, Error = ConversionError>,
+{
+    type Error = ConversionError;
+
+    fn try_from(item: &'a ItemType) -> Result<Self, Self::Error> {
+        hash_entries(item)
+    }
+}
+
+impl<'a, T> TryFrom<&'a ItemType> for BTreeMap<String, T>
+where
+    T: TryFrom<&'a ItemType, Error = ConversionError>,
+{
+    type Error = ConversionError;
+
+    fn try_from(item: &'a ItemType) -> Result<Self, Self::Error> {
+        hash_entries(item)
+    }
+}
+
+fn hash_entries<'a, T, C>(item: &'a ItemType) -> Result<C, ConversionError>
+where
+    T: TryFrom<&'a ItemType, Error = ConversionError>,
+    C: FromIterator<(String, T)>,
+{
+    match item {
+        ItemType::THash(hash) => hash.iter()
+            .filter_map(|(key, member)| match key {
+                ContainerKey::Key(name) => Some(T::try_from(member).map(|value| (name.to_string(), value))),
+                ContainerKey::Idx(_) => None,
+            })
+            .collect(),
+        _ => Err(wrong_kind(ItemKind::Hash, item)),
+    }
+}
@@ -0,0 +1,206 @@
+use std::error::Error;
+use std::fmt;
+
+use super::items::*;
+use super::pointers::ItemPointer;
+use super::convert::ConversionError;
+use crate::itemdoc::exports::core::ContextError;
+use crate::itemdoc::imports::core::{ParseError, ParseErrorKind, SourcePosition};
+use crate::itemdoc::query::jsonpath::{QueryError, QueryErrorKind};
+use crate::itemdoc::schema::validate::SchemaError;
+
+// What went wrong, without the context. Matching on this works the same way
+// as matching on the narrower error types it wraps.
+#[derive(Debug)]
+#[derive(Copy, Clone, PartialEq)]
+pub enum DocErrorKind {
+    Item(ItemError),
+    Conversion(ConversionError),
+    Context(ContextError),
+    Parse(ParseErrorKind),
+    Query(QueryErrorKind),
+    Schema(SchemaError),
+    Io(std::io::ErrorKind),
+}
+
+impl fmt::Display for DocErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DocErrorKind::Item(err) => write!(f, "{}", err),
+            DocErrorKind::Conversion(err) => write!(f, "{}", err),
+            DocErrorKind::Context(err) => write!(f, "{}", err),
+            DocErrorKind::Parse(err) => write!(f, "{}", err),
+            DocErrorKind::Query(err) => write!(f, "{}", err),
+            DocErrorKind::Schema(err) => write!(f, "{}", err),
+            DocErrorKind::Io(err) => write!(f, "{}", err),
+        }
+    }
+}
+
+// The error shared by the core, export and import APIs. Besides its kind it
+// may carry the document path it happened at, the item kinds involved, the
+// source position of a parse failure and the error that caused it. Pointer
+// resolution, export and import fill in the path as they descend.
+#[derive(Debug)]
+pub struct DocError {
+    kind: DocErrorKind,
+    path: Option<ItemPointer>,
+    expected: Option<ItemKind>,
+    found: Option<ItemKind>,
+    position: Option<SourcePosition>,
+    source: Option<Box<dyn Error + Send + Sync + 'static>>,
+}
+
+impl DocError {
+
+    pub fn new(kind: DocErrorKind) -> Self {
+        DocError { kind, path: None, expected: None, found: None, position: None, source: None }
+    }
+
+    // An error about `found` not being the kind of item an operation needed.
+    pub fn wrong_kind(error: ItemError, expected: ItemKind, found: ItemKind) -> Self {
+        DocError::new(DocErrorKind::Item(error)).with_kinds(expected, found)
+    }
+
+    // Locates an error raised while following `pointer` at the prefix where
+    // resolution stopped, which pointer errors give by segment number.
+    pub(crate) fn at_pointer(self, pointer: &ItemPointer) -> Self {
+        let depth = match self.kind {
+            DocErrorKind::Item(ItemError::PointerNotFound(n)) => n + 1,
+            DocErrorKind::Item(ItemError::PointerNotAContainer(n)) => n,
+            DocErrorKind::Item(ItemError::PointerInvalidIndex(n)) => n + 1,
+            _ => pointer.count(),
+        };
+        let segments = pointer.get_segments().iter().take(depth);
+        self.with_path(ItemPointer::from_segments(segments.cloned()))
+    }
+
+    pub fn with_path(mut self, path: ItemPointer) -> Self {
+        self.path = Some(path);
+        self
+    }
+    pub fn with_kinds(mut self, expected: ItemKind, found: ItemKind) -> Self {
+        self.expected = Some(expected);
+        self.found = Some(found);
+        self
+    }
+    pub fn with_position(mut self, position: SourcePosition) -> Self {
+        self.position = Some(position);
+        self
+    }
+    pub fn with_source<E: Error + Send + Sync + 'static>(mut self, source: E) -> Self {
+        self.source = Some(Box::new(source));
+        self
+    }
+
+    // Prefixes the path with the key of the member the error came from, as
+    // it travels back up to the caller.
+    pub(crate) fn within<'a, K: Into<ContainerKey<'a>>>(mut self, key: K) -> Self {
+        let mut path = ItemPointer::root();
+        path.push_key(&key.into());
+        for segment in self.path.iter().flat_map(|inner| inner.get_segments()) {
+            path.push(segment);
+        }
+        self.path = Some(path);
+        self
+    }
+
+
+    pub fn get_kind(&self) -> DocErrorKind {
+        self.kind
+    }
+    pub fn get_item_error(&self) -> Option<ItemError> {
+        match self.kind {
+            DocErrorKind::Item(err) => Some(err),
+            _ => None,
+        }
+    }
+    pub fn get_path(&self) -> Option<&ItemPointer> {
+        self.path.as_ref()
+    }
+    pub fn get_expected(&self) -> Option<ItemKind> {
+        self.expected
+    }
+    pub fn get_found(&self) -> Option<ItemKind> {
+        self.found
+    }
+    pub fn get_position(&self) -> Option<SourcePosition> {
+        self.position
+    }
+
+}
+
+impl fmt::Display for DocError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.path {
+            Some(path) if path.is_root() => write!(f, "At '/': ")?,
+            Some(path) => write!(f, "At '{}': ", path)?,
+            None => {},
+        }
+        write!(f, "{}", self.kind)?;
+        if let (Some(expected), Some(found)) = (self.expected, self.found) {
+            if !matches!(self.kind, DocErrorKind::Conversion(_)) {
+                write!(f, " Expected a {} item but found a {}!", expected, found)?;
+            }
+        }
+        if let Some(position) = &self.position {
+            write!(f, " (line {}, column {}, offset {})", position.line, position.column, position.offset)?;
+        }
+        Ok(())
+    }
+}
+
+impl Error for DocError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        self.source.as_ref().map(|source| source.as_ref() as &(dyn Error + 'static))
+    }
+}
+
+impl PartialEq<ItemError> for DocError {
+    fn eq(&self, other: &ItemError) -> bool {
+        self.kind == DocErrorKind::Item(*other)
+    }
+}
+
+
+impl From<ItemError> for DocError {
+    fn from(error: ItemError) -> Self {
+        DocError::new(DocErrorKind::Item(error))
+    }
+}
+
+impl From<ConversionError> for DocError {
+    fn from(error: ConversionError) -> Self {
+        let converted = DocError::new(DocErrorKind::Conversion(error));
+        match error {
+            ConversionError::WrongKind { expected, found } => converted.with_kinds(expected, found),
+            ConversionError::OutOfRange { .. } | ConversionError::WrongLength { .. } => converted,
+        }
+    }
+}
+
+impl From<ContextError> for DocError {
+    fn from(error: ContextError) -> Self {
+        DocError::new(DocErrorKind::Context(error))
+    }
+}
+
+impl From<ParseError> for DocError {
+    fn from(error: ParseError) -> Self {
+        let position = SourcePosition { offset: error.offset, line: error.line, column: error.column };
+        DocError::new(DocErrorKind::Parse(error.kind)).with_position(position).with_source(error)
+    }
+}
+
+impl From<QueryError> for DocError {
+    fn from(error: QueryError) -> Self {
+        let position = SourcePosition { offset: error.offset, line: error.line, column: error.column };
+        DocError::new(DocErrorKind::Query(error.kind)).with_position(position).with_source(error)
+    }
+}
+
+impl From<std::io::Error> for DocError {
+    fn from(error: std::io::Error) -> Self {
+        DocError::new(DocErrorKind::Io(error.kind())).with_source(error)
+    }
+}
@@ -1,21 +1,112 @@
+use std::cmp::Ordering;
 use std::collections::HashMap;
-use std::error::Error;
+use std::slice;
+use std::vec;
+use std::fmt;
 
 use super::items::*;
 use super::utility::*;
+use super::errors::DocError;
 
-#[derive(PartialEq)]
+// Decides what removing an entry does to the order of the rest. Shift moves
+// the later entries down, keeping insertion order at an O(n) cost; Swap moves
+// the last entry into the gap in O(1). There is no unordered mode: Swap is
+// the cheap option when order does not matter.
+#[derive(Debug)]
+#[derive(Copy, Clone, PartialEq)]
+pub enum RemovalOrder {
+    Shift,
+    Swap,
+}
+
+// Entries always keep a defined order, so export and iteration are
+// deterministic; the key index only speeds up lookups.
+#[derive(Debug, Clone)]
 pub struct ItemHash {
-    items: HashMap<String, ItemType>,
+    entries: Vec<(String, ItemType)>,
+    index: HashMap<String, usize>,
+    removal: RemovalOrder,
+}
+
+impl PartialEq for ItemHash {
+    fn eq(&self, other: &Self) -> bool {
+        self.entries.len() == other.entries.len()
+            && self.entries.iter().all(|(k, v)| other.get_entry(k) == Some(v))
+    }
 }
 
 impl ItemHash {
 
+    #[allow(clippy::new_ret_no_self)]
     pub fn new() -> ItemType {
-        let me = ItemType::THash(ItemHash { 
-            items: HashMap::new(),
-        });
-        me
+        ItemHash::with_removal_order(RemovalOrder::Shift)
+    }
+
+    pub fn with_removal_order(removal: RemovalOrder) -> ItemType {
+        ItemType::THash(ItemHash {
+            entries: Vec::new(),
+            index: HashMap::new(),
+            removal,
+        })
+    }
+
+
+    pub fn get_removal_order(&self) -> RemovalOrder {
+        self.removal
+    }
+    pub fn set_removal_order(&mut self, removal: RemovalOrder) {
+        self.removal = removal;
+    }
+
+
+    fn get_entry(&self, key: &str) -> Option<&ItemType> {
+        self.index.get(key).map(|i| &self.entries[*i].1)
+    }
+    fn get_entry_mut(&mut self, key: &str) -> Option<&mut ItemType> {
+        match self.index.get(key) {
+            Some(i) => Some(&mut self.entries[*i].1),
+            None => None,
+        }
+    }
+    fn insert_entry(&mut self, key: &str, item: ItemType) -> &mut ItemType {
+        let slot = match self.index.get(key) {
+            Some(i) => {
+                self.entries[*i].1 = item;
+                *i
+            },
+            None => {
+                self.entries.push((key.to_string(), item));
+                self.index.insert(key.to_string(), self.entries.len() - 1);
+                self.entries.len() - 1
+            },
+        };
+        &mut self.entries[slot].1
+    }
+    fn remove_entry(&mut self, key: &str) -> Option<ItemType> {
+        let slot = self.index.remove(key)?;
+        let (_, item) = match self.removal {
+            RemovalOrder::Shift => {
+                let removed = self.entries.remove(slot);
+                for (i, (k, _)) in self.entries.iter().enumerate().skip(slot) {
+                    self.index.insert(k.clone(), i);
+                }
+                removed
+            },
+            RemovalOrder::Swap => {
+                let removed = self.entries.swap_remove(slot);
+                if let Some((k, _)) = self.entries.get(slot) {
+                    self.index.insert(k.clone(), slot);
+                }
+                removed
+            },
+        };
+        Some(item)
+    }
+    fn rebuild_index(&mut self) {
+        self.index.clear();
+        for (i, (k, _)) in self.entries.iter().enumerate() {
+            self.index.insert(k.clone(), i);
+        }
     }
 
 
@@ -29,106 +120,377 @@ impl ItemHash {
 
 
     pub fn has_item(&self, item: &ItemType) -> bool {
-        self.items.values().any(|value| value == item)  
+        self.entries.iter().any(|(_, value)| value == item)
+    }
+    pub fn get_item<'a, L: Into<ContainerKey<'a>>>(&self, lookup: L) -> Result<Option<&ItemType>, DocError> {
+        match lookup.into() {
+            ContainerKey::Key(k) => Ok(self.get_entry(k)),
+            _ => Err(unkeyed()),
+        }
     }
-    pub fn get_item<'a, L: Into<ContainerKey<'a>>>(&'a self, lookup: L) -> Result<Option<&'a ItemType>, ItemError> {
+    pub fn get_item_mut<'a, L: Into<ContainerKey<'a>>>(&mut self, lookup: L) -> Result<Option<&mut ItemType>, DocError> {
         match lookup.into() {
-            ContainerKey::Key(k) => Ok(self.items.get(k)),
-            _ => Err(ItemError::NotAnItemList),
+            ContainerKey::Key(k) => Ok(self.get_entry_mut(k)),
+            _ => Err(unkeyed()),
+        }
+    }
+    pub fn entry(&mut self, key: &str) -> ItemEntry<'_> {
+        ItemEntry {
+            hash: self,
+            key: key.to_string(),
         }
     }
 
 
-    pub fn add_null<'a>(&mut self, key: Option<&'a str>) -> Result<&mut Self, ItemError> {
+    pub fn add_null(&mut self, key: Option<&str>) -> Result<&mut Self, DocError> {
         match key {
             Some(k) => {
-                self.items.insert(k.to_string(),super::nulls::ItemNull::new());
+                self.insert_entry(k, super::nulls::ItemNull::new());
                 Ok(self)
             }
-            None => Err(ItemError::NotAnItemList),
+            None => Err(unkeyed()),
         }
     }
-    pub fn add_value<'a, V: Into<RustType>>(
+    pub fn add_value<V: Into<RustType>>(
         &mut self,
         value: V,
-        key: Option<&'a str>,
-    ) -> Result<(), ItemError> {
+        key: Option<&str>,
+    ) -> Result<(), DocError> {
+        match key {
+            Some(k) => {
+                self.insert_entry(k, value.into().into_item_type());
+                Ok(())
+            }
+            None => Err(unkeyed()),
+        }
+    }
+    pub fn add_item(&mut self, item: ItemType, key: Option<&str>) -> Result<(), DocError> {
         match key {
             Some(k) => {
-                self.items.insert(k.to_string(), value.into().into_item_type());
+                self.insert_entry(k, item);
                 Ok(())
             }
-            None => Err(ItemError::NotAnItemList),
+            None => Err(unkeyed()),
         }
     }
-    pub fn add_list<'a>(&mut self, key: Option<&'a str>) -> Result<&mut ItemType, Box<dyn Error>> {
+    pub fn add_list(&mut self, key: Option<&str>) -> Result<&mut ItemType, DocError> {
         if let Some(k) = key {
             let list = super::lists::ItemList::new();
-            self.items.insert(k.to_string(),list);
-            self.items.get_mut(k)
-                .ok_or_else(|| Box::<dyn Error>::from(ItemError::ItemAdditionFailed))
+            Ok(self.insert_entry(k, list))
         } else {
-            Err(Box::new(ItemError::NotAnItemHash))
+            Err(unkeyed())
         }
     }
-    pub fn add_hash<'a>(&mut self, key: Option<&'a str>) -> Result<&mut ItemType, Box<dyn Error>> {
+    pub fn add_hash(&mut self, key: Option<&str>) -> Result<&mut ItemType, DocError> {
         if let Some(k) = key {
-            let hash = ItemHash::new();
-            self.items.insert(k.to_string(),hash);
-            self.items.get_mut(k)
-                .ok_or_else(|| Box::<dyn Error>::from(ItemError::ItemAdditionFailed))
+            let hash = ItemHash::with_removal_order(self.removal);
+            Ok(self.insert_entry(k, hash))
         } else {
-            Err(Box::new(ItemError::NotAnItemHash))
+            Err(unkeyed())
         }
     }
 
 
-    pub fn remove_item<'a>(&mut self, lookup: ContainerKey<'a>) -> Result<Option<ItemType>, ItemError> {
+    pub fn remove_item<'a>(&mut self, lookup: ContainerKey<'a>) -> Result<Option<ItemType>, DocError> {
         match lookup {
             ContainerKey::Key(k) => {
-                Ok(self.items.remove(k))
+                Ok(self.remove_entry(k))
             },
-            _ => Err(ItemError::NotAnItemList),
+            _ => Err(unkeyed()),
         }
     }
 
 
-    pub fn count(&self) -> usize { 
-        self.items.len() 
+    pub fn replace_item<'a>(&mut self, lookup: ContainerKey<'a>, item: ItemType) -> Result<ItemType, DocError> {
+        let slot = self.slot_of(lookup)?;
+        Ok(std::mem::replace(&mut self.entries[slot].1, item))
+    }
+    // Inserting a key that already exists moves it to the new position.
+    pub fn insert_at(&mut self, index: usize, item: ItemType, key: Option<&str>) -> Result<(), DocError> {
+        let k = key.ok_or_else(unkeyed)?;
+        let len = self.entries.len() - usize::from(self.index.contains_key(k));
+        if index > len {
+            return Err(out_of_range(index));
+        }
+        if let Some(slot) = self.index.get(k).copied() {
+            self.entries.remove(slot);
+        }
+        self.entries.insert(index, (k.to_string(), item));
+        self.rebuild_index();
+        Ok(())
+    }
+    pub fn add_or_replace<'a>(&mut self, lookup: ContainerKey<'a>, item: ItemType) -> Result<Option<ItemType>, DocError> {
+        match lookup {
+            ContainerKey::Key(k) => match self.get_entry_mut(k) {
+                Some(value) => Ok(Some(std::mem::replace(value, item))),
+                None => {
+                    self.insert_entry(k, item);
+                    Ok(None)
+                },
+            },
+            ContainerKey::Idx(_) => Err(unkeyed()),
+        }
+    }
+    pub fn swap<'a>(&mut self, a: ContainerKey<'a>, b: ContainerKey<'a>) -> Result<(), DocError> {
+        let a = self.slot_of(a)?;
+        let b = self.slot_of(b)?;
+        self.entries.swap(a, b);
+        self.rebuild_index();
+        Ok(())
+    }
+    pub fn move_item<'a>(&mut self, lookup: ContainerKey<'a>, index: usize) -> Result<(), DocError> {
+        match lookup {
+            ContainerKey::Key(k) => self.move_key(k, index),
+            ContainerKey::Idx(_) => Err(unkeyed()),
+        }
+    }
+    pub fn truncate(&mut self, len: usize) -> Result<(), DocError> {
+        for (key, _) in self.entries.drain(len.min(self.entries.len())..) {
+            self.index.remove(&key);
+        }
+        Ok(())
+    }
+    pub fn clear(&mut self) -> Result<(), DocError> {
+        self.entries.clear();
+        self.index.clear();
+        Ok(())
+    }
+
+    fn slot_of<'a>(&self, lookup: ContainerKey<'a>) -> Result<usize, DocError> {
+        match lookup {
+            ContainerKey::Key(k) => self.get_index_of(k).ok_or_else(|| missing(k)),
+            ContainerKey::Idx(_) => Err(unkeyed()),
+        }
+    }
+
+
+    pub fn count(&self) -> usize {
+        self.entries.len()
     }
 
 
     pub fn has_key<'a, K: Into<ContainerKey<'a>>>(&self, key: K) -> bool {
         match key.into() {
-            ContainerKey::Key(k) => self.items.contains_key(k),
+            ContainerKey::Key(k) => self.index.contains_key(k),
             ContainerKey::Idx(_) => false,
         }
     }
-    pub fn get_key<'a>(&'a self, item: &ItemType) -> Result<Option<ContainerKey<'a>>, ItemError> {
-        for (key, value) in &self.items {
+    pub fn get_key<'a>(&'a self, item: &ItemType) -> Result<Option<ContainerKey<'a>>, DocError> {
+        for (key, value) in &self.entries {
             if value == item {
                 return Ok(Some(ContainerKey::Key(key.as_str())));
             }
         }
         Ok(None)
     }
-    pub fn get_keys<'a>(&'a self) -> Result<Vec<ContainerKey<'a>>, ItemError> {
-        let keys = self.items
-            .keys()
-            .map(|k| ContainerKey::Key(k.as_str()))
+    pub fn get_keys<'a>(&'a self) -> Result<Vec<ContainerKey<'a>>, DocError> {
+        let keys = self.entries
+            .iter()
+            .map(|(k, _)| ContainerKey::Key(k.as_str()))
             .collect();
         Ok(keys)
     }
+    pub fn get_index_of(&self, key: &str) -> Option<usize> {
+        self.index.get(key).copied()
+    }
+
 
-    
-    pub fn last_mut(&mut self, key: &String) -> Option<&mut ItemType> {
-        self.items.get_mut(key)
+    pub fn iter(&self) -> HashIter<'_> {
+        HashIter { inner: self.entries.iter() }
+    }
+    pub fn iter_mut(&mut self) -> HashIterMut<'_> {
+        HashIterMut { inner: self.entries.iter_mut() }
     }
 
 
-    pub fn to_string(&self) -> String {
-        let elements: Vec<String> = self.items.iter().map(|(key, value)| format!("\"{}\":{}", key, value.to_string())).collect();
-        format!("{{{}}}", elements.join(","))
+    pub fn move_key(&mut self, key: &str, index: usize) -> Result<(), DocError> {
+        let from = self.get_index_of(key).ok_or_else(|| missing(key))?;
+        if index >= self.entries.len() {
+            return Err(out_of_range(index));
+        }
+        let entry = self.entries.remove(from);
+        self.entries.insert(index, entry);
+        self.rebuild_index();
+        Ok(())
+    }
+    // Keeps the entry in place; fails if another entry already uses the new key.
+    pub fn rename_key(&mut self, key: &str, to: &str) -> Result<(), DocError> {
+        let slot = self.get_index_of(key).ok_or_else(|| missing(key))?;
+        if key == to {
+            return Ok(());
+        }
+        if self.index.contains_key(to) {
+            return Err(DocError::from(ItemError::ItemAdditionFailed).within(to));
+        }
+        self.index.remove(key);
+        self.index.insert(to.to_string(), slot);
+        self.entries[slot].0 = to.to_string();
+        Ok(())
+    }
+    pub fn retain<F>(&mut self, mut keep: F)
+    where
+        F: FnMut(&str, &ItemType) -> bool,
+    {
+        self.entries.retain(|(key, item)| keep(key, item));
+        self.rebuild_index();
+    }
+    pub fn sort_keys(&mut self) {
+        self.entries.sort_by(|a, b| a.0.cmp(&b.0));
+        self.rebuild_index();
+    }
+    pub fn sort_by<F>(&mut self, mut compare: F)
+    where
+        F: FnMut(&str, &ItemType, &str, &ItemType) -> Ordering,
+    {
+        self.entries.sort_by(|a, b| compare(&a.0, &a.1, &b.0, &b.1));
+        self.rebuild_index();
     }
 
-}
\ No newline at end of file
+
+    pub fn last_mut(&mut self, key: &str) -> Option<&mut ItemType> {
+        self.get_entry_mut(key)
+    }
+
+
+}
+
+impl fmt::Display for ItemHash {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let elements: Vec<String> = self.entries.iter().map(|(key, value)| format!("\"{}\":{}", key, value)).collect();
+        write!(f, "{{{}}}", elements.join(","))
+    }
+}
+
+// Hash members are only addressed by key.
+fn unkeyed() -> DocError {
+    DocError::wrong_kind(ItemError::NotAnItemList, ItemKind::List, ItemKind::Hash)
+}
+
+fn missing(key: &str) -> DocError {
+    DocError::from(ItemError::ItemNotFound).within(key)
+}
+
+pub struct HashIter<'a> {
+    inner: slice::Iter<'a, (String, ItemType)>,
+}
+
+impl<'a> Iterator for HashIter<'a> {
+    type Item = (ContainerKey<'a>, &'a ItemType);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.inner.next().map(|(key, item)| (ContainerKey::Key(key.as_str()), item))
+    }
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.inner.size_hint()
+    }
+}
+
+impl<'a> DoubleEndedIterator for HashIter<'a> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.inner.next_back().map(|(key, item)| (ContainerKey::Key(key.as_str()), item))
+    }
+}
+
+impl<'a> ExactSizeIterator for HashIter<'a> {}
+
+// Only the values are handed out mutably; keys stay fixed so the index
+// remains valid.
+pub struct HashIterMut<'a> {
+    inner: slice::IterMut<'a, (String, ItemType)>,
+}
+
+impl<'a> Iterator for HashIterMut<'a> {
+    type Item = (ContainerKey<'a>, &'a mut ItemType);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.inner.next().map(|(key, item)| (ContainerKey::Key(key.as_str()), item))
+    }
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.inner.size_hint()
+    }
+}
+
+impl<'a> DoubleEndedIterator for HashIterMut<'a> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.inner.next_back().map(|(key, item)| (ContainerKey::Key(key.as_str()), item))
+    }
+}
+
+impl<'a> ExactSizeIterator for HashIterMut<'a> {}
+
+impl<'a> IntoIterator for &'a ItemHash {
+    type Item = (ContainerKey<'a>, &'a ItemType);
+    type IntoIter = HashIter<'a>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl<'a> IntoIterator for &'a mut ItemHash {
+    type Item = (ContainerKey<'a>, &'a mut ItemType);
+    type IntoIter = HashIterMut<'a>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter_mut()
+    }
+}
+
+// Owned iteration yields the keys as Strings since a ContainerKey cannot
+// outlive the hash it borrows from.
+impl IntoIterator for ItemHash {
+    type Item = (String, ItemType);
+    type IntoIter = vec::IntoIter<(String, ItemType)>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.entries.into_iter()
+    }
+}
+
+// A key of an ItemHash that may or may not be present yet, used to update
+// nested documents in place without looking the key up twice.
+pub struct ItemEntry<'a> {
+    hash: &'a mut ItemHash,
+    key: String,
+}
+
+impl<'a> ItemEntry<'a> {
+
+    pub fn key(&self) -> &str {
+        &self.key
+    }
+    pub fn is_occupied(&self) -> bool {
+        self.hash.index.contains_key(&self.key)
+    }
+
+
+    pub fn and_modify<F: FnOnce(&mut ItemType)>(self, modify: F) -> Self {
+        if let Some(item) = self.hash.get_entry_mut(&self.key) {
+            modify(item);
+        }
+        self
+    }
+    pub fn or_insert(self, default: ItemType) -> &'a mut ItemType {
+        self.or_insert_with(|| default)
+    }
+    pub fn or_insert_with<F: FnOnce() -> ItemType>(self, default: F) -> &'a mut ItemType {
+        match self.hash.index.get(&self.key) {
+            Some(i) => &mut self.hash.entries[*i].1,
+            None => self.hash.insert_entry(&self.key, default()),
+        }
+    }
+    pub fn or_insert_value<V: Into<RustType>>(self, value: V) -> &'a mut ItemType {
+        self.or_insert_with(|| value.into().into_item_type())
+    }
+    pub fn or_insert_null(self) -> &'a mut ItemType {
+        self.or_insert_with(super::nulls::ItemNull::new)
+    }
+    pub fn or_insert_list(self) -> &'a mut ItemType {
+        self.or_insert_with(super::lists::ItemList::new)
+    }
+    pub fn or_insert_hash(self) -> &'a mut ItemType {
+        let removal = self.hash.removal;
+        self.or_insert_with(|| ItemHash::with_removal_order(removal))
+    }
+
+}
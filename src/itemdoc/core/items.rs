@@ -1,17 +1,35 @@
 use std::result::Result;
-use std::error::Error;
 use std::fmt;
+use std::cmp::Ordering;
 
 use super::utility::*;
+use super::pointers::{self, ItemPointer};
+use super::lists::{ListIter, ListIterMut};
+use super::hashes::{HashIter, HashIterMut};
+use super::walk::{Walk, WalkOrder};
+use super::visitor::{ItemVisitor, ItemVisitorMut};
+use super::errors::DocError;
+use crate::itemdoc::exports::core::ItemOutput;
+use crate::itemdoc::query::jsonpath::{Query, QueryError, QueryMatch, QueryPath};
+use crate::itemdoc::patch::json_patch::{ItemPatch, PatchError};
+use crate::itemdoc::patch::merge_patch;
+use crate::itemdoc::patch::diff::{self, ItemDiff, DiffOptions};
+use crate::itemdoc::patch::deep_merge::{self, MergeOptions, MergeError};
+use crate::itemdoc::schema::validate::{Schema, Violation};
 
 #[derive(Debug)]
-#[derive(Copy, Clone)]
+#[derive(Copy, Clone, PartialEq)]
 pub enum ItemError {
     NotAnItemList,
     NotAnItemHash,
     NotAnItemContainer,
     ItemAdditionFailed,
     ItemNotFound,
+    IndexOutOfRange(usize),
+    InvalidPointer,
+    PointerNotFound(usize),
+    PointerNotAContainer(usize),
+    PointerInvalidIndex(usize),
 }
 
 impl fmt::Display for ItemError {
@@ -22,13 +40,18 @@ impl fmt::Display for ItemError {
             ItemError::NotAnItemContainer => write!(f, "Not an item container!"),
             ItemError::ItemAdditionFailed => write!(f, "Item addition failure!"),
             ItemError::ItemNotFound => write!(f, "Item not found!"),
+            ItemError::IndexOutOfRange(n) => write!(f, "Index {} is out of range!", n),
+            ItemError::InvalidPointer => write!(f, "Invalid item pointer!"),
+            ItemError::PointerNotFound(n) => write!(f, "Pointer segment {} not found!", n),
+            ItemError::PointerNotAContainer(n) => write!(f, "Pointer segment {} is not within a container!", n),
+            ItemError::PointerInvalidIndex(n) => write!(f, "Pointer segment {} is not a list index!", n),
         }
     }
 }
 
 impl std::error::Error for ItemError {}
 
-#[derive(PartialEq)]
+#[derive(Debug, Copy, Clone, PartialEq)]
 pub enum ContainerKey<'a> {
     Idx(usize),
     Key(&'a str),
@@ -46,8 +69,38 @@ impl<'a> From<&'a str> for ContainerKey<'a> {
     }
 }
 
+// The variant of an ItemType without its contents, for error reporting.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum ItemKind {
+    Null,
+    Boolean,
+    Number,
+    String,
+    List,
+    Hash,
+}
 
-#[derive(PartialEq)]
+impl ItemKind {
+    pub fn get_name(&self) -> &'static str {
+        match self {
+            ItemKind::Null => "null",
+            ItemKind::Boolean => "boolean",
+            ItemKind::Number => "number",
+            ItemKind::String => "string",
+            ItemKind::List => "list",
+            ItemKind::Hash => "hash",
+        }
+    }
+}
+
+impl fmt::Display for ItemKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.get_name())
+    }
+}
+
+
+#[derive(Debug, Clone, PartialEq)]
 pub enum ItemType {
     TNull(super::nulls::ItemNull),
     TBoolean(super::booleans::ItemBoolean),
@@ -60,47 +113,38 @@ pub enum ItemType {
 impl ItemType {
 
     pub fn is_null(&self) -> bool {
-        match self {
-            ItemType::TNull(_mapped) => true,
-            _ => false,
-        }        
+        matches!(self, ItemType::TNull(_))
     }
-    pub fn is_booleam(&self) -> bool { 
-        match self {
-            ItemType::TBoolean(_mapped) => true,
-            _ => false,
-        }        
+    pub fn is_booleam(&self) -> bool {
+        matches!(self, ItemType::TBoolean(_))
     }
-    pub fn is_number(&self) -> bool { 
-        match self {
-            ItemType::TNumber(_mapped) => true,
-            _ => false,
-        }        
+    pub fn is_number(&self) -> bool {
+        matches!(self, ItemType::TNumber(_))
     }
     pub fn is_string(&self) -> bool {
-        match self {
-            ItemType::TString(_mapped) => true,
-            _ => false,
-        }        
+        matches!(self, ItemType::TString(_))
     }
     pub fn is_container(&self) -> bool {
-        match self {
-            ItemType::TList(_mapped) => true,
-            ItemType::THash(_mapped) => true,
-            _ => false,
-        }        
+        matches!(self, ItemType::TList(_) | ItemType::THash(_))
     }
     pub fn is_list(&self) -> bool {
-        match self {
-            ItemType::TList(_mapped) => true,
-            _ => false,
-        }        
+        matches!(self, ItemType::TList(_))
     }
     pub fn is_hash(&self) -> bool {
+        matches!(self, ItemType::THash(_))
+    }
+    pub fn get_kind(&self) -> ItemKind {
         match self {
-            ItemType::THash(_mapped) => true,
-            _ => false,
-        }        
+            ItemType::TNull(_mapped) => ItemKind::Null,
+            ItemType::TBoolean(_mapped) => ItemKind::Boolean,
+            ItemType::TNumber(_mapped) => ItemKind::Number,
+            ItemType::TString(_mapped) => ItemKind::String,
+            ItemType::TList(_mapped) => ItemKind::List,
+            ItemType::THash(_mapped) => ItemKind::Hash,
+        }
+    }
+    pub fn get_type_name(&self) -> &'static str {
+        self.get_kind().get_name()
     }
 
 
@@ -111,50 +155,162 @@ impl ItemType {
             _ => false,
         }        
     }
-    pub fn get_item<'a, L: Into<ContainerKey<'a>>>(&'a self, lookup: L) -> Result<Option<&'a ItemType>, ItemError> {
+    pub fn get_item<'a, L: Into<ContainerKey<'a>>>(&self, lookup: L) -> Result<Option<&ItemType>, DocError> {
+        let lookup = lookup.into();
         match self {
             ItemType::TList(list) => list.get_item(lookup),
             ItemType::THash(hash) => hash.get_item(lookup),
-            _ => Err(ItemError::NotAnItemContainer),
+            _ => Err(not_a_container(lookup_container(lookup), self.get_kind())),
+        }
+    }
+    pub fn get_item_mut<'a, L: Into<ContainerKey<'a>>>(&mut self, lookup: L) -> Result<Option<&mut ItemType>, DocError> {
+        let lookup = lookup.into();
+        let found = self.get_kind();
+        match self {
+            ItemType::TList(list) => list.get_item_mut(lookup),
+            ItemType::THash(hash) => hash.get_item_mut(lookup),
+            _ => Err(not_a_container(lookup_container(lookup), found)),
+        }
+    }
+    pub fn iter(&self) -> ItemIter<'_> {
+        match self {
+            ItemType::TList(list) => ItemIter::List(list.iter()),
+            ItemType::THash(hash) => ItemIter::Hash(hash.iter()),
+            _ => ItemIter::Empty,
+        }
+    }
+    pub fn iter_mut(&mut self) -> ItemIterMut<'_> {
+        match self {
+            ItemType::TList(list) => ItemIterMut::List(list.iter_mut()),
+            ItemType::THash(hash) => ItemIterMut::Hash(hash.iter_mut()),
+            _ => ItemIterMut::Empty,
+        }
+    }
+    pub fn walk(&self) -> Walk<'_> {
+        Walk::new(self, WalkOrder::PreOrder)
+    }
+    pub fn walk_with(&self, order: WalkOrder) -> Walk<'_> {
+        Walk::new(self, order)
+    }
+    pub fn accept<V: ItemVisitor + ?Sized>(&self, visitor: &mut V) {
+        visitor.visit_item(self);
+    }
+    pub fn accept_mut<V: ItemVisitorMut + ?Sized>(&mut self, visitor: &mut V) {
+        visitor.visit_item(self);
+    }
+    pub fn entry(&mut self, key: &str) -> Result<super::hashes::ItemEntry<'_>, DocError> {
+        let found = self.get_kind();
+        match self {
+            ItemType::THash(hash) => Ok(hash.entry(key)),
+            _ => Err(not_a_hash(found)),
         }
     }
 
 
-    pub fn add_null<'a>(&mut self, key: Option<&'a str>) -> Result<&mut Self, Box<dyn Error>> {
+    pub fn add_null(&mut self, key: Option<&str>) -> Result<&mut Self, DocError> {
+        let found = self.get_kind();
         match self {
             ItemType::TList(me) => { me.add_null(key)?; Ok(self) },
             ItemType::THash(me) => { me.add_null(key)?; Ok(self) },
-            _ => Err(Box::new(ItemError::NotAnItemContainer)),
+            _ => Err(not_a_container(key_container(key), found)),
         }
     }
-    pub fn add_value<'a, V: Into<RustType>>(&mut self, value: V, key: Option<&'a str>) -> Result<(), ItemError> {
+    pub fn add_value<V: Into<RustType>>(&mut self, value: V, key: Option<&str>) -> Result<(), DocError> {
+        let found = self.get_kind();
         match self {
             ItemType::TList(list) => list.add_value(value, key),
             ItemType::THash(hash) => hash.add_value(value, key),
-            _ => Err(ItemError::NotAnItemContainer),
+            _ => Err(not_a_container(key_container(key), found)),
+        }
+    }
+    pub fn add_item(&mut self, item: ItemType, key: Option<&str>) -> Result<(), DocError> {
+        let found = self.get_kind();
+        match self {
+            ItemType::TList(list) => list.add_item(item, key),
+            ItemType::THash(hash) => hash.add_item(item, key),
+            _ => Err(not_a_container(key_container(key), found)),
         }
     }
-    pub fn add_list<'a>(&mut self, key: Option<&'a str>) -> Result<&mut ItemType, Box<dyn Error>> {
+    pub fn add_list(&mut self, key: Option<&str>) -> Result<&mut ItemType, DocError> {
+        let found = self.get_kind();
         match self {
             ItemType::TList(list) => list.add_list(key),
             ItemType::THash(hash) => hash.add_list(key),
-            _ => Err(Box::new(ItemError::NotAnItemContainer)),
+            _ => Err(not_a_container(key_container(key), found)),
         }
     }
-    pub fn add_hash<'a>(&mut self, key: Option<&'a str>) -> Result<&mut ItemType, Box<dyn Error>> {
+    pub fn add_hash(&mut self, key: Option<&str>) -> Result<&mut ItemType, DocError> {
+        let found = self.get_kind();
         match self {
             ItemType::TList(list) => list.add_hash(key),
             ItemType::THash(hash) => hash.add_hash(key),
-            _ => Err(Box::new(ItemError::NotAnItemContainer)),
+            _ => Err(not_a_container(key_container(key), found)),
         }
     }
 
 
-    pub fn remove_item<'a>(&mut self, lookup: ContainerKey<'a>) -> Result<Option<ItemType>, ItemError> {
+    pub fn remove_item<'a>(&mut self, lookup: ContainerKey<'a>) -> Result<Option<ItemType>, DocError> {
+        let found = self.get_kind();
         match self {
             ItemType::TList(list) => list.remove_item(lookup),
             ItemType::THash(hash) => hash.remove_item(lookup),
-            _ => Err(ItemError::NotAnItemContainer),
+            _ => Err(not_a_container(lookup_container(lookup), found)),
+        }
+    }
+
+
+    pub fn replace_item<'a>(&mut self, lookup: ContainerKey<'a>, item: ItemType) -> Result<ItemType, DocError> {
+        let found = self.get_kind();
+        match self {
+            ItemType::TList(list) => list.replace_item(lookup, item),
+            ItemType::THash(hash) => hash.replace_item(lookup, item),
+            _ => Err(not_a_container(lookup_container(lookup), found)),
+        }
+    }
+    pub fn insert_at(&mut self, index: usize, item: ItemType, key: Option<&str>) -> Result<(), DocError> {
+        let found = self.get_kind();
+        match self {
+            ItemType::TList(list) => list.insert_at(index, item, key),
+            ItemType::THash(hash) => hash.insert_at(index, item, key),
+            _ => Err(not_a_container(key_container(key), found)),
+        }
+    }
+    pub fn add_or_replace<'a>(&mut self, lookup: ContainerKey<'a>, item: ItemType) -> Result<Option<ItemType>, DocError> {
+        let found = self.get_kind();
+        match self {
+            ItemType::TList(list) => list.add_or_replace(lookup, item),
+            ItemType::THash(hash) => hash.add_or_replace(lookup, item),
+            _ => Err(not_a_container(lookup_container(lookup), found)),
+        }
+    }
+    pub fn swap<'a>(&mut self, a: ContainerKey<'a>, b: ContainerKey<'a>) -> Result<(), DocError> {
+        let found = self.get_kind();
+        match self {
+            ItemType::TList(list) => list.swap(a, b),
+            ItemType::THash(hash) => hash.swap(a, b),
+            _ => Err(not_a_container(lookup_container(a), found)),
+        }
+    }
+    pub fn move_item<'a>(&mut self, lookup: ContainerKey<'a>, index: usize) -> Result<(), DocError> {
+        let found = self.get_kind();
+        match self {
+            ItemType::TList(list) => list.move_item(lookup, index),
+            ItemType::THash(hash) => hash.move_item(lookup, index),
+            _ => Err(not_a_container(lookup_container(lookup), found)),
+        }
+    }
+    pub fn truncate(&mut self, len: usize) -> Result<(), DocError> {
+        match self {
+            ItemType::TList(list) => list.truncate(len),
+            ItemType::THash(hash) => hash.truncate(len),
+            _ => Err(DocError::from(ItemError::NotAnItemContainer)),
+        }
+    }
+    pub fn clear(&mut self) -> Result<(), DocError> {
+        match self {
+            ItemType::TList(list) => list.clear(),
+            ItemType::THash(hash) => hash.clear(),
+            _ => Err(DocError::from(ItemError::NotAnItemContainer)),
         }
     }
 
@@ -175,30 +331,166 @@ impl ItemType {
             _ => false,
         }        
     }
-    pub fn get_key<'a>(&'a self, item: &ItemType) -> Result<Option<ContainerKey<'a>>, ItemError> {
+    pub fn get_key<'a>(&'a self, item: &ItemType) -> Result<Option<ContainerKey<'a>>, DocError> {
         match self {
             ItemType::TList(mapped) => mapped.get_key(item),
             ItemType::THash(mapped) => mapped.get_key(item),
-            _ => Err(ItemError::NotAnItemContainer),
+            _ => Err(DocError::from(ItemError::NotAnItemContainer)),
         }        
     }
-    pub fn get_keys<'a>(&'a self) -> Result<Vec<ContainerKey<'a>>, ItemError> {
+    pub fn get_keys<'a>(&'a self) -> Result<Vec<ContainerKey<'a>>, DocError> {
         match self {
             ItemType::TList(mapped) => mapped.get_keys(),
             ItemType::THash(mapped) => mapped.get_keys(),
-            _ => Err(ItemError::NotAnItemContainer),
+            _ => Err(DocError::from(ItemError::NotAnItemContainer)),
         }        
     }
 
-    pub fn to_string(&self) -> String {
+    pub fn move_key(&mut self, key: &str, index: usize) -> Result<(), DocError> {
+        let found = self.get_kind();
         match self {
-            ItemType::TNull(mapped) => mapped.to_string(),
-            ItemType::TBoolean(mapped) => mapped.to_string(),
-            ItemType::TNumber(mapped) => mapped.to_string(),
-            ItemType::TString(mapped) => mapped.to_string(),
-            ItemType::TList(mapped) => mapped.to_string(),
-            ItemType::THash(mapped) => mapped.to_string(),
-        }        
+            ItemType::THash(mapped) => mapped.move_key(key, index),
+            _ => Err(not_a_hash(found)),
+        }
+    }
+    pub fn sort_keys(&mut self) -> Result<(), DocError> {
+        let found = self.get_kind();
+        match self {
+            ItemType::THash(mapped) => { mapped.sort_keys(); Ok(()) },
+            _ => Err(not_a_hash(found)),
+        }
+    }
+    pub fn sort_by<F>(&mut self, compare: F) -> Result<(), DocError>
+    where
+        F: FnMut(&str, &ItemType, &str, &ItemType) -> Ordering,
+    {
+        let found = self.get_kind();
+        match self {
+            ItemType::THash(mapped) => { mapped.sort_by(compare); Ok(()) },
+            _ => Err(not_a_hash(found)),
+        }
+    }
+
+    pub fn pointer(&self, pointer: &ItemPointer) -> Result<&ItemType, DocError> {
+        pointers::resolve(self, pointer)
+    }
+    pub fn pointer_mut(&mut self, pointer: &ItemPointer) -> Result<&mut ItemType, DocError> {
+        pointers::resolve_mut(self, pointer)
+    }
+    pub fn set_at_pointer(&mut self, pointer: &ItemPointer, item: ItemType, create: bool) -> Result<Option<ItemType>, DocError> {
+        pointers::set(self, pointer, item, create)
+    }
+    pub fn remove_at_pointer(&mut self, pointer: &ItemPointer) -> Result<ItemType, DocError> {
+        pointers::remove(self, pointer)
+    }
+
+    pub fn apply_patch(&mut self, patch: &ItemPatch) -> Result<(), PatchError> {
+        patch.apply(self)
+    }
+    pub fn merge_patch(&mut self, patch: &ItemType) {
+        merge_patch::merge_patch(self, patch)
+    }
+    pub fn deep_merge(&mut self, other: &ItemType, options: &MergeOptions) -> Result<(), MergeError> {
+        deep_merge::deep_merge(self, other, options)
+    }
+    pub fn diff(&self, other: &ItemType) -> ItemDiff {
+        diff::diff(self, other)
+    }
+    pub fn diff_with(&self, other: &ItemType, options: &DiffOptions) -> ItemDiff {
+        diff::diff_with(self, other, options)
+    }
+
+    pub fn validate(&self, schema: &Schema) -> Vec<Violation> {
+        schema.validate(self)
+    }
+
+    pub fn query(&self, path: &str) -> Result<Vec<QueryMatch<'_>>, QueryError> {
+        Ok(Query::compile(path)?.select(self))
+    }
+    pub fn query_mut<F>(&mut self, path: &str, update: F) -> Result<usize, QueryError>
+    where
+        F: FnMut(&QueryPath, &mut ItemType),
+    {
+        Ok(Query::compile(path)?.apply_mut(self, update))
+    }
+
+    pub fn export(&self, output: &mut dyn ItemOutput) -> Result<(), DocError> {
+        crate::itemdoc::exports::walker::export(self, output)
     }
 
-}
\ No newline at end of file
+}
+
+impl fmt::Display for ItemType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ItemType::TNull(mapped) => write!(f, "{}", mapped),
+            ItemType::TBoolean(mapped) => write!(f, "{}", mapped),
+            ItemType::TNumber(mapped) => write!(f, "{}", mapped),
+            ItemType::TString(mapped) => write!(f, "{}", mapped),
+            ItemType::TList(mapped) => write!(f, "{}", mapped),
+            ItemType::THash(mapped) => write!(f, "{}", mapped),
+        }
+    }
+}
+
+// Keyed access needs a hash and positional access a list.
+fn key_container(key: Option<&str>) -> ItemKind {
+    if key.is_some() { ItemKind::Hash } else { ItemKind::List }
+}
+
+fn lookup_container(lookup: ContainerKey<'_>) -> ItemKind {
+    match lookup {
+        ContainerKey::Key(_) => ItemKind::Hash,
+        ContainerKey::Idx(_) => ItemKind::List,
+    }
+}
+
+fn not_a_container(expected: ItemKind, found: ItemKind) -> DocError {
+    DocError::wrong_kind(ItemError::NotAnItemContainer, expected, found)
+}
+
+fn not_a_hash(found: ItemKind) -> DocError {
+    DocError::wrong_kind(ItemError::NotAnItemHash, ItemKind::Hash, found)
+}
+
+// A position past the end of a list or hash.
+pub(crate) fn out_of_range(index: usize) -> DocError {
+    ItemError::IndexOutOfRange(index).into()
+}
+
+// The children of an item; scalars have none.
+pub enum ItemIter<'a> {
+    List(ListIter<'a>),
+    Hash(HashIter<'a>),
+    Empty,
+}
+
+impl<'a> Iterator for ItemIter<'a> {
+    type Item = (ContainerKey<'a>, &'a ItemType);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        match self {
+            ItemIter::List(iter) => iter.next(),
+            ItemIter::Hash(iter) => iter.next(),
+            ItemIter::Empty => None,
+        }
+    }
+}
+
+pub enum ItemIterMut<'a> {
+    List(ListIterMut<'a>),
+    Hash(HashIterMut<'a>),
+    Empty,
+}
+
+impl<'a> Iterator for ItemIterMut<'a> {
+    type Item = (ContainerKey<'a>, &'a mut ItemType);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        match self {
+            ItemIterMut::List(iter) => iter.next(),
+            ItemIterMut::Hash(iter) => iter.next(),
+            ItemIterMut::Empty => None,
+        }
+    }
+}
@@ -1,20 +1,24 @@
-use std::error::Error;
+use std::iter::Enumerate;
+use std::slice;
+use std::vec;
+use std::fmt;
 
 use super::items::*;
 use super::utility::*;
+use super::errors::DocError;
 
-#[derive(PartialEq)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct ItemList {
     items: Vec<ItemType>,
 }
 
 impl ItemList {
 
+    #[allow(clippy::new_ret_no_self)]
     pub fn new() -> ItemType {
-        let me = ItemType::TList(ItemList { 
+        ItemType::TList(ItemList { 
             items: Vec::new(),
-        });
-        me
+        })
     }
 
     pub fn is_null(&self) -> bool { false }
@@ -29,52 +33,64 @@ impl ItemList {
         self.items.iter().any(|value| value == item) 
     }
 
-    pub fn get_item<'a, L: Into<ContainerKey<'a>>>(&'a self, lookup: L) -> Result<Option<&'a ItemType>, ItemError> {
+    pub fn get_item<'a, L: Into<ContainerKey<'a>>>(&self, lookup: L) -> Result<Option<&ItemType>, DocError> {
         match lookup.into() {
             ContainerKey::Idx(i) => Ok(self.items.get(i)),
-            _ => Err(ItemError::NotAnItemHash),
+            _ => Err(keyed()),
+        }
+    }
+    pub fn get_item_mut<'a, L: Into<ContainerKey<'a>>>(&mut self, lookup: L) -> Result<Option<&mut ItemType>, DocError> {
+        match lookup.into() {
+            ContainerKey::Idx(i) => Ok(self.items.get_mut(i)),
+            _ => Err(keyed()),
         }
     }
 
 
-    pub fn add_null<'a>(&mut self, key: Option<&'a str>) -> Result<&mut Self, ItemError> {
+    pub fn add_null(&mut self, key: Option<&str>) -> Result<&mut Self, DocError> {
         if key.is_some() {
-            Err(ItemError::NotAnItemHash)
+            Err(keyed())
         } else {
             self.items.push(super::nulls::ItemNull::new());
             Ok(self)
         }
     }
-    pub fn add_value<'a, V: Into<RustType>>(&mut self, value: V, key: Option<&'a str>) -> Result<(), ItemError> {
+    pub fn add_value<V: Into<RustType>>(&mut self, value: V, key: Option<&str>) -> Result<(), DocError> {
         if key.is_some() {
-            Err(ItemError::NotAnItemHash)
+            Err(keyed())
         } else {
             let item = value.into().into_item_type();
             self.items.push(item);
             Ok(())
         }
     }
-    pub fn add_list<'a>(&mut self, key: Option<&'a str>) -> Result<&mut ItemType, Box<dyn Error>> {
+    pub fn add_item(&mut self, item: ItemType, key: Option<&str>) -> Result<(), DocError> {
+        if key.is_some() {
+            Err(keyed())
+        } else {
+            self.items.push(item);
+            Ok(())
+        }
+    }
+    pub fn add_list(&mut self, key: Option<&str>) -> Result<&mut ItemType, DocError> {
         if key.is_some() {
-            return Err(Box::new(ItemError::NotAnItemHash));
+            return Err(keyed());
         }
         let list = ItemList::new();
         self.items.push(list);
-        self.items.last_mut()
-            .ok_or_else(|| Box::<dyn Error>::from(ItemError::ItemAdditionFailed))
+        self.items.last_mut().ok_or_else(|| ItemError::ItemAdditionFailed.into())
     }
-    pub fn add_hash<'a>(&mut self, key: Option<&'a str>) -> Result<&mut ItemType, Box<dyn Error>> {
+    pub fn add_hash(&mut self, key: Option<&str>) -> Result<&mut ItemType, DocError> {
         if key.is_some() {
-            return Err(Box::new(ItemError::NotAnItemHash));
+            return Err(keyed());
         }
         let hash = super::hashes::ItemHash::new();
         self.items.push(hash);
-        self.items.last_mut()
-            .ok_or_else(|| Box::<dyn Error>::from(ItemError::ItemAdditionFailed))
+        self.items.last_mut().ok_or_else(|| ItemError::ItemAdditionFailed.into())
     }
 
 
-    pub fn remove_item<'a>(&mut self, lookup: ContainerKey<'a>) -> Result<Option<ItemType>, ItemError> {
+    pub fn remove_item<'a>(&mut self, lookup: ContainerKey<'a>) -> Result<Option<ItemType>, DocError> {
         match lookup {
             ContainerKey::Idx(i) => {
                 if i < self.items.len() {
@@ -83,7 +99,63 @@ impl ItemList {
                     Ok(None)
                 }
             },
-            ContainerKey::Key(_) => Err(ItemError::NotAnItemHash),
+            ContainerKey::Key(_) => Err(keyed()),
+        }
+    }
+
+
+    pub fn replace_item<'a>(&mut self, lookup: ContainerKey<'a>, item: ItemType) -> Result<ItemType, DocError> {
+        let index = self.index_of(lookup)?;
+        Ok(std::mem::replace(&mut self.items[index], item))
+    }
+    pub fn insert_at(&mut self, index: usize, item: ItemType, key: Option<&str>) -> Result<(), DocError> {
+        if key.is_some() {
+            return Err(keyed());
+        }
+        if index > self.items.len() {
+            return Err(out_of_range(index));
+        }
+        self.items.insert(index, item);
+        Ok(())
+    }
+    pub fn add_or_replace<'a>(&mut self, lookup: ContainerKey<'a>, item: ItemType) -> Result<Option<ItemType>, DocError> {
+        match lookup {
+            ContainerKey::Idx(i) if i == self.items.len() => {
+                self.items.push(item);
+                Ok(None)
+            },
+            _ => self.replace_item(lookup, item).map(Some),
+        }
+    }
+    pub fn swap<'a>(&mut self, a: ContainerKey<'a>, b: ContainerKey<'a>) -> Result<(), DocError> {
+        let a = self.index_of(a)?;
+        let b = self.index_of(b)?;
+        self.items.swap(a, b);
+        Ok(())
+    }
+    pub fn move_item<'a>(&mut self, lookup: ContainerKey<'a>, index: usize) -> Result<(), DocError> {
+        let from = self.index_of(lookup)?;
+        if index >= self.items.len() {
+            return Err(out_of_range(index));
+        }
+        let item = self.items.remove(from);
+        self.items.insert(index, item);
+        Ok(())
+    }
+    pub fn truncate(&mut self, len: usize) -> Result<(), DocError> {
+        self.items.truncate(len);
+        Ok(())
+    }
+    pub fn clear(&mut self) -> Result<(), DocError> {
+        self.items.clear();
+        Ok(())
+    }
+
+    fn index_of<'a>(&self, lookup: ContainerKey<'a>) -> Result<usize, DocError> {
+        match lookup {
+            ContainerKey::Idx(i) if i < self.items.len() => Ok(i),
+            ContainerKey::Idx(i) => Err(missing(i)),
+            ContainerKey::Key(_) => Err(keyed()),
         }
     }
 
@@ -99,13 +171,13 @@ impl ItemList {
             ContainerKey::Key(_) => false,
         }
     }
-    pub fn get_key<'a>(&'a self, item: &ItemType) -> Result<Option<ContainerKey<'a>>, ItemError> {
+    pub fn get_key<'a>(&'a self, item: &ItemType) -> Result<Option<ContainerKey<'a>>, DocError> {
         match self.items.iter().position(|v| v == item) {
             Some(index) => Ok(Some(ContainerKey::Idx(index))),
             None => Ok(None),
         }
     }
-    pub fn get_keys<'a>(&'a self) -> Result<Vec<ContainerKey<'a>>, ItemError> {
+    pub fn get_keys<'a>(&'a self) -> Result<Vec<ContainerKey<'a>>, DocError> {
         let keys = self.items
         .iter()
         .enumerate()
@@ -115,14 +187,111 @@ impl ItemList {
     }
 
 
+    pub fn iter(&self) -> ListIter<'_> {
+        ListIter { inner: self.items.iter().enumerate() }
+    }
+    pub fn iter_mut(&mut self) -> ListIterMut<'_> {
+        ListIterMut { inner: self.items.iter_mut().enumerate() }
+    }
+
+
+    pub fn retain<F: FnMut(&ItemType) -> bool>(&mut self, keep: F) {
+        self.items.retain(keep);
+    }
     pub fn last_mut(&mut self) -> Option<&mut ItemType> {
         self.items.last_mut()
     }
 
 
-    pub fn to_string(&self) -> String {
+}
+
+impl fmt::Display for ItemList {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         let elements: Vec<String> = self.items.iter().map(|item| item.to_string()).collect();
-        format!("[{}]", elements.join(","))
+        write!(f, "[{}]", elements.join(","))
     }
+}
+
+// Keys only address hash members.
+fn keyed() -> DocError {
+    DocError::wrong_kind(ItemError::NotAnItemHash, ItemKind::Hash, ItemKind::List)
+}
+
+fn missing(index: usize) -> DocError {
+    DocError::from(ItemError::ItemNotFound).within(index)
+}
+
+pub struct ListIter<'a> {
+    inner: Enumerate<slice::Iter<'a, ItemType>>,
+}
+
+impl<'a> Iterator for ListIter<'a> {
+    type Item = (ContainerKey<'a>, &'a ItemType);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.inner.next().map(|(i, item)| (ContainerKey::Idx(i), item))
+    }
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.inner.size_hint()
+    }
+}
+
+impl<'a> DoubleEndedIterator for ListIter<'a> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.inner.next_back().map(|(i, item)| (ContainerKey::Idx(i), item))
+    }
+}
+
+impl<'a> ExactSizeIterator for ListIter<'a> {}
+
+pub struct ListIterMut<'a> {
+    inner: Enumerate<slice::IterMut<'a, ItemType>>,
+}
+
+impl<'a> Iterator for ListIterMut<'a> {
+    type Item = (ContainerKey<'a>, &'a mut ItemType);
 
+    fn next(&mut self) -> Option<Self::Item> {
+        self.inner.next().map(|(i, item)| (ContainerKey::Idx(i), item))
+    }
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.inner.size_hint()
+    }
+}
+
+impl<'a> DoubleEndedIterator for ListIterMut<'a> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.inner.next_back().map(|(i, item)| (ContainerKey::Idx(i), item))
+    }
+}
+
+impl<'a> ExactSizeIterator for ListIterMut<'a> {}
+
+impl<'a> IntoIterator for &'a ItemList {
+    type Item = (ContainerKey<'a>, &'a ItemType);
+    type IntoIter = ListIter<'a>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl<'a> IntoIterator for &'a mut ItemList {
+    type Item = (ContainerKey<'a>, &'a mut ItemType);
+    type IntoIter = ListIterMut<'a>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter_mut()
+    }
+}
+
+// Owned iteration yields the positions as plain indexes, matching the
+// String keys of an owned ItemHash.
+impl IntoIterator for ItemList {
+    type Item = (usize, ItemType);
+    type IntoIter = Enumerate<vec::IntoIter<ItemType>>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.items.into_iter().enumerate()
+    }
 }
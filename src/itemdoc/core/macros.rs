@@ -0,0 +1,79 @@
+// Builds an ItemType from JSON-like syntax:
+//
+//   let port = 8080;
+//   let doc = item!({ "name": "web", "ports": [80, port], "tls": null });
+//
+// Hash keys are string literals or any single token that derefs to a str,
+// such as a variable or a parenthesized expression. Values are nested
+// literals, `null`, or Rust expressions that convert Into<ItemType>, which
+// covers every Into<RustType> scalar as well as Vecs, maps and Options.
+// Each member costs a few levels of macro recursion, so very large literals
+// may need a higher #![recursion_limit].
+#[macro_export]
+macro_rules! item {
+    // lists: members are accumulated token by token until a comma
+    (@list $list:ident ()) => {};
+    (@list $list:ident () $member:tt) => {
+        $crate::item!(@push $list ($member));
+    };
+    (@list $list:ident () $member:tt , $($rest:tt)*) => {
+        $crate::item!(@push $list ($member));
+        $crate::item!(@list $list () $($rest)*);
+    };
+    (@list $list:ident ($($member:tt)+)) => {
+        $crate::item!(@push $list ($($member)+));
+    };
+    (@list $list:ident ($($member:tt)+) , $($rest:tt)*) => {
+        $crate::item!(@push $list ($($member)+));
+        $crate::item!(@list $list () $($rest)*);
+    };
+    (@list $list:ident ($($member:tt)*) $next:tt $($rest:tt)*) => {
+        $crate::item!(@list $list ($($member)* $next) $($rest)*);
+    };
+    (@push $list:ident ($($member:tt)+)) => {
+        let _ = $list.add_item($crate::item!($($member)+), None);
+    };
+
+    // hashes: the same, with a key and a colon in front of each value
+    (@hash $hash:ident ()) => {};
+    (@hash $hash:ident () $key:tt : $value:tt) => {
+        $crate::item!(@insert $hash $key ($value));
+    };
+    (@hash $hash:ident () $key:tt : $value:tt , $($rest:tt)*) => {
+        $crate::item!(@insert $hash $key ($value));
+        $crate::item!(@hash $hash () $($rest)*);
+    };
+    (@hash $hash:ident ($key:tt : $($value:tt)+)) => {
+        $crate::item!(@insert $hash $key ($($value)+));
+    };
+    (@hash $hash:ident ($key:tt : $($value:tt)+) , $($rest:tt)*) => {
+        $crate::item!(@insert $hash $key ($($value)+));
+        $crate::item!(@hash $hash () $($rest)*);
+    };
+    (@hash $hash:ident ($($member:tt)*) $next:tt $($rest:tt)*) => {
+        $crate::item!(@hash $hash ($($member)* $next) $($rest)*);
+    };
+    (@insert $hash:ident $key:tt ($($value:tt)+)) => {
+        let key: &str = &$key;
+        let _ = $hash.add_item($crate::item!($($value)+), Some(key));
+    };
+
+    (null) => {
+        $crate::itemdoc::core::nulls::ItemNull::new()
+    };
+    ([ $($members:tt)* ]) => {{
+        #[allow(unused_mut)]
+        let mut list = $crate::itemdoc::core::lists::ItemList::new();
+        $crate::item!(@list list () $($members)*);
+        list
+    }};
+    ({ $($members:tt)* }) => {{
+        #[allow(unused_mut)]
+        let mut hash = $crate::itemdoc::core::hashes::ItemHash::new();
+        $crate::item!(@hash hash () $($members)*);
+        hash
+    }};
+    ($value:expr) => {
+        $crate::itemdoc::core::utility::IntoItemType::into_item_type($value)
+    };
+}
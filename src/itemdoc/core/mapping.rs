@@ -0,0 +1,344 @@
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+
+use super::items::*;
+use super::nulls::ItemNull;
+use super::lists::ItemList;
+use super::hashes::ItemHash;
+use super::pointers::ItemPointer;
+use super::convert::ConversionError;
+
+// Conversion of Rust values into documents, implemented by
+// #[derive(IntoItem)] and for the standard scalar and collection types.
+pub trait IntoItem {
+    #[allow(clippy::wrong_self_convention)]
+    fn into_item(&self) -> ItemType;
+}
+
+// Types whose item is always a hash, so their members can be merged into
+// an enclosing one: flattened fields and the newtype variants of
+// internally tagged enums require it. #[derive(IntoItem)] implements it for
+// structs with named fields and for internally or adjacently tagged enums.
+#[diagnostic::on_unimplemented(message = "`{Self}` cannot be flattened because its item is not always a hash")]
+pub trait IntoHash: IntoItem {}
+
+// Extraction of Rust values from documents, implemented by
+// #[derive(FromItem)] and for the standard scalar and collection types.
+pub trait FromItem: Sized {
+    fn from_item(item: &ItemType) -> Result<Self, FromItemError>;
+
+    // The value used when a hash key is absent; None makes the key required.
+    fn from_missing() -> Option<Self> {
+        None
+    }
+}
+
+// Every variant carries the path of the item that failed, relative to the
+// item extraction started from.
+#[derive(Debug)]
+#[derive(Clone, PartialEq)]
+pub enum FromItemError {
+    Conversion(ItemPointer, ConversionError),
+    MissingKey(ItemPointer),
+    UnknownVariant(ItemPointer, String),
+    NoVariantMatched(ItemPointer, ItemKind),
+}
+
+impl FromItemError {
+
+    pub fn wrong_kind(expected: ItemKind, found: &ItemType) -> Self {
+        FromItemError::Conversion(ItemPointer::root(), ConversionError::WrongKind { expected, found: found.get_kind() })
+    }
+
+    pub fn get_path(&self) -> &ItemPointer {
+        match self {
+            FromItemError::Conversion(path, _) => path,
+            FromItemError::MissingKey(path) => path,
+            FromItemError::UnknownVariant(path, _) => path,
+            FromItemError::NoVariantMatched(path, _) => path,
+        }
+    }
+
+    // Prefixes the path with the key of the member that failed, as the error
+    // travels back up to the caller.
+    pub fn within<'a, K: Into<ContainerKey<'a>>>(mut self, key: K) -> Self {
+        let mut path = ItemPointer::root();
+        path.push_key(&key.into());
+        let inner = match &mut self {
+            FromItemError::Conversion(inner, _) => inner,
+            FromItemError::MissingKey(inner) => inner,
+            FromItemError::UnknownVariant(inner, _) => inner,
+            FromItemError::NoVariantMatched(inner, _) => inner,
+        };
+        for segment in inner.get_segments() {
+            path.push(segment);
+        }
+        *inner = path;
+        self
+    }
+
+}
+
+impl fmt::Display for FromItemError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let path = match self.get_path() {
+            path if path.is_root() => "/".to_string(),
+            path => path.to_string(),
+        };
+        match self {
+            FromItemError::Conversion(_, error) => write!(f, "At '{}': {}", path, error),
+            FromItemError::MissingKey(_) => write!(f, "Missing key at '{}'!", path),
+            FromItemError::UnknownVariant(_, name) => write!(f, "Unknown variant '{}' at '{}'!", name, path),
+            FromItemError::NoVariantMatched(_, kind) => write!(f, "No variant matches the {} item at '{}'!", kind, path),
+        }
+    }
+}
+
+impl std::error::Error for FromItemError {}
+
+impl From<ConversionError> for FromItemError {
+    fn from(error: ConversionError) -> Self {
+        FromItemError::Conversion(ItemPointer::root(), error)
+    }
+}
+
+
+// Helpers called by the derived implementations.
+
+pub fn hash_field<T: FromItem>(hash: &ItemType, key: &str) -> Result<T, FromItemError> {
+    match hash.get_item(key) {
+        Ok(Some(item)) => T::from_item(item).map_err(|e| e.within(key)),
+        _ => T::from_missing().ok_or_else(|| FromItemError::MissingKey(ItemPointer::root().join(key))),
+    }
+}
+
+pub fn hash_field_or_else<T: FromItem, F: FnOnce() -> T>(hash: &ItemType, key: &str, default: F) -> Result<T, FromItemError> {
+    match hash.get_item(key) {
+        Ok(Some(item)) => T::from_item(item).map_err(|e| e.within(key)),
+        _ => Ok(default()),
+    }
+}
+
+pub fn hash_member<'a>(hash: &'a ItemType, key: &str) -> Result<&'a ItemType, FromItemError> {
+    match hash.get_item(key) {
+        Ok(Some(item)) => Ok(item),
+        _ => Err(FromItemError::MissingKey(ItemPointer::root().join(key))),
+    }
+}
+
+// The key and value of a hash holding exactly one member.
+pub fn single_entry(item: &ItemType) -> Option<(&str, &ItemType)> {
+    match item {
+        ItemType::THash(hash) if hash.count() == 1 => match hash.iter().next() {
+            Some((ContainerKey::Key(name), value)) => Some((name, value)),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+pub fn list_member<T: FromItem>(list: &ItemType, index: usize) -> Result<T, FromItemError> {
+    match list.get_item(index) {
+        Ok(Some(item)) => T::from_item(item).map_err(|e| e.within(index)),
+        _ => T::from_missing().ok_or_else(|| FromItemError::MissingKey(ItemPointer::root().join(&index.to_string()))),
+    }
+}
+
+// Runs an extraction whose errors belong under `key`.
+pub fn nested<'a, T, K, F>(key: K, extract: F) -> Result<T, FromItemError>
+where
+    K: Into<ContainerKey<'a>>,
+    F: FnOnce() -> Result<T, FromItemError>,
+{
+    extract().map_err(|e| e.within(key))
+}
+
+// Runs one candidate extraction of an untagged enum.
+pub fn attempt<T, F: FnOnce() -> Result<T, FromItemError>>(extract: F) -> Result<T, FromItemError> {
+    extract()
+}
+
+pub fn expect_kind(item: &ItemType, expected: ItemKind) -> Result<(), FromItemError> {
+    if item.get_kind() == expected {
+        Ok(())
+    } else {
+        Err(FromItemError::wrong_kind(expected, item))
+    }
+}
+
+// Copies the members of a flattened hash into the hash being built.
+//
+// # Panics
+//
+// If `item` is not a hash; the IntoHash bound on derived code rules that
+// out unless an impl breaks its promise.
+pub fn flatten_into(hash: &mut ItemType, item: ItemType) {
+    match item {
+        ItemType::THash(members) => {
+            for (key, value) in members {
+                hash.add_item(value, Some(&key)).ok();
+            }
+        },
+        other => panic!("cannot flatten {} into a hash", other.get_kind()),
+    }
+}
+
+
+impl IntoItem for ItemType {
+    fn into_item(&self) -> ItemType {
+        self.clone()
+    }
+}
+
+impl FromItem for ItemType {
+    fn from_item(item: &ItemType) -> Result<Self, FromItemError> {
+        Ok(item.clone())
+    }
+}
+
+macro_rules! scalar_mapping {
+    ($($target:ty),+) => {
+        $(
+        impl IntoItem for $target {
+            fn into_item(&self) -> ItemType {
+                ItemType::from(self.clone())
+            }
+        }
+
+        impl FromItem for $target {
+            fn from_item(item: &ItemType) -> Result<Self, FromItemError> {
+                Ok(<$target>::try_from(item)?)
+            }
+        }
+        )+
+    };
+}
+
+scalar_mapping!(bool, i8, i16, i32, i64, i128, u8, u16, u32, u64, usize, f32, f64, String);
+
+impl IntoItem for str {
+    fn into_item(&self) -> ItemType {
+        ItemType::from(self)
+    }
+}
+
+impl<T: IntoItem + ?Sized> IntoItem for &T {
+    fn into_item(&self) -> ItemType {
+        (**self).into_item()
+    }
+}
+
+impl<T: IntoHash + ?Sized> IntoHash for &T {}
+
+impl<T: IntoItem> IntoItem for Box<T> {
+    fn into_item(&self) -> ItemType {
+        (**self).into_item()
+    }
+}
+
+impl<T: IntoHash> IntoHash for Box<T> {}
+
+impl<T: FromItem> FromItem for Box<T> {
+    fn from_item(item: &ItemType) -> Result<Self, FromItemError> {
+        T::from_item(item).map(Box::new)
+    }
+}
+
+impl<T: IntoItem> IntoItem for Option<T> {
+    fn into_item(&self) -> ItemType {
+        match self {
+            Some(value) => value.into_item(),
+            None => ItemNull::new(),
+        }
+    }
+}
+
+impl<T: FromItem> FromItem for Option<T> {
+    fn from_item(item: &ItemType) -> Result<Self, FromItemError> {
+        match item {
+            ItemType::TNull(_) => Ok(None),
+            _ => T::from_item(item).map(Some),
+        }
+    }
+    fn from_missing() -> Option<Self> {
+        Some(None)
+    }
+}
+
+impl<T: IntoItem> IntoItem for [T] {
+    fn into_item(&self) -> ItemType {
+        let mut list = ItemList::new();
+        for value in self {
+            list.add_item(value.into_item(), None).ok();
+        }
+        list
+    }
+}
+
+impl<T: IntoItem> IntoItem for Vec<T> {
+    fn into_item(&self) -> ItemType {
+        self.as_slice().into_item()
+    }
+}
+
+impl<T: FromItem> FromItem for Vec<T> {
+    fn from_item(item: &ItemType) -> Result<Self, FromItemError> {
+        match item {
+            ItemType::TList(list) => list.iter()
+                .map(|(key, member)| T::from_item(member).map_err(|e| e.within(key)))
+                .collect(),
+            _ => Err(FromItemError::wrong_kind(ItemKind::List, item)),
+        }
+    }
+}
+
+impl<T: IntoItem> IntoItem for BTreeMap<String, T> {
+    fn into_item(&self) -> ItemType {
+        let mut hash = ItemHash::new();
+        for (key, value) in self {
+            hash.add_item(value.into_item(), Some(key)).ok();
+        }
+        hash
+    }
+}
+
+impl<T: IntoItem> IntoHash for BTreeMap<String, T> {}
+
+impl<T: FromItem> FromItem for BTreeMap<String, T> {
+    fn from_item(item: &ItemType) -> Result<Self, FromItemError> {
+        hash_members(item)
+    }
+}
+
+// Keys are sorted so that output does not depend on HashMap ordering.
+impl<T: IntoItem> IntoItem for HashMap<String, T> {
+    fn into_item(&self) -> ItemType {
+        let mut keys: Vec<&String> = self.keys().collect();
+        keys.sort();
+        let mut hash = ItemHash::new();
+        for key in keys {
+            hash.add_item(self[key].into_item(), Some(key)).ok();
+        }
+        hash
+    }
+}
+
+impl<T: IntoItem> IntoHash for HashMap<String, T> {}
+
+impl<T: FromItem> FromItem for HashMap<String, T> {
+    fn from_item(item: &ItemType) -> Result<Self, FromItemError> {
+        hash_members(item)
+    }
+}
+
+fn hash_members<T: FromItem, C: FromIterator<(String, T)>>(item: &ItemType) -> Result<C, FromItemError> {
+    match item {
+        ItemType::THash(hash) => hash.iter()
+            .filter_map(|(key, member)| match key {
+                ContainerKey::Key(name) => Some(T::from_item(member).map(|value| (name.to_string(), value)).map_err(|e| e.within(name))),
+                ContainerKey::Idx(_) => None,
+            })
+            .collect(),
+        _ => Err(FromItemError::wrong_kind(ItemKind::Hash, item)),
+    }
+}
@@ -1,16 +1,19 @@
+use std::fmt;
+
 use super::items::*;
+use super::errors::DocError;
 use super::utility::*;
 
-#[derive(PartialEq)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct ItemNull {
 }
 
 impl ItemNull {
 
+    #[allow(clippy::new_ret_no_self)]
     pub fn new() -> ItemType {
-        let me = ItemType::TNull(ItemNull { 
-        });
-        me
+        ItemType::TNull(ItemNull { 
+        })
     }
 
 
@@ -23,27 +26,30 @@ impl ItemNull {
 
 
     pub fn has_item(&self, _item: &ItemType) -> bool { false }
-    pub fn get_item<'a, L: Into<ContainerKey<'a>>>(&'a self, _lookup: L) -> Result<Option<&'a ItemType>, ItemError> {
-        Err(ItemError::NotAnItemContainer)
+    pub fn get_item<'a, L: Into<ContainerKey<'a>>>(&self, _lookup: L) -> Result<Option<&ItemType>, DocError> {
+        Err(ItemError::NotAnItemContainer.into())
+    }
+    pub fn get_item_mut<'a, L: Into<ContainerKey<'a>>>(&mut self, _lookup: L) -> Result<Option<&mut ItemType>, DocError> {
+        Err(ItemError::NotAnItemContainer.into())
     }
 
 
-    pub fn add_null<'a>(&mut self, _key: Option<&'a str>) -> Result<&mut Self, ItemError> {
-        Err(ItemError::NotAnItemContainer)
+    pub fn add_null(&mut self, _key: Option<&str>) -> Result<&mut Self, DocError> {
+        Err(ItemError::NotAnItemContainer.into())
     }
-    pub fn add_value<'a, V: Into<RustType>>(&mut self, _value: V, _key: Option<&'a str>) -> Result<(), ItemError> {
-        Err(ItemError::NotAnItemContainer)
+    pub fn add_value<V: Into<RustType>>(&mut self, _value: V, _key: Option<&str>) -> Result<(), DocError> {
+        Err(ItemError::NotAnItemContainer.into())
     }
-    pub fn add_list<'a>(&mut self, _key: Option<&'a str>) -> Result<&mut ItemType, ItemError> {
-        Err(ItemError::NotAnItemContainer)
+    pub fn add_list(&mut self, _key: Option<&str>) -> Result<&mut ItemType, DocError> {
+        Err(ItemError::NotAnItemContainer.into())
     }
-    pub fn add_hash<'a>(&mut self, _key: Option<&'a str>) -> Result<&mut ItemType, ItemError> {
-        Err(ItemError::NotAnItemContainer)
+    pub fn add_hash(&mut self, _key: Option<&str>) -> Result<&mut ItemType, DocError> {
+        Err(ItemError::NotAnItemContainer.into())
     }
 
 
-    pub fn remove_item<'a>(&mut self, _key: ContainerKey<'a>) -> Result<Option<ItemType>, ItemError> {
-        Err(ItemError::NotAnItemContainer)
+    pub fn remove_item<'a>(&mut self, _key: ContainerKey<'a>) -> Result<Option<ItemType>, DocError> {
+        Err(ItemError::NotAnItemContainer.into())
     }
 
 
@@ -53,16 +59,18 @@ impl ItemNull {
     pub fn has_key<'a, K: Into<ContainerKey<'a>>>(&self, _key: K) -> bool {
         false
     }
-    pub fn get_key<'a>(&'a self, _item: &ItemType) -> Result<Option<ContainerKey<'a>>, ItemError> {
-        Err(ItemError::NotAnItemContainer)
+    pub fn get_key<'a>(&'a self, _item: &ItemType) -> Result<Option<ContainerKey<'a>>, DocError> {
+        Err(ItemError::NotAnItemContainer.into())
     }
-    pub fn get_keys<'a>(&'a self) -> Result<Vec<ContainerKey<'a>>, ItemError> {
-        Err(ItemError::NotAnItemContainer)
+    pub fn get_keys<'a>(&'a self) -> Result<Vec<ContainerKey<'a>>, DocError> {
+        Err(ItemError::NotAnItemContainer.into())
     }
 
 
-    pub fn to_string(&self) -> String {
-        "null".to_string()
-    }
+}
 
+impl fmt::Display for ItemNull {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "null")
+    }
 }
\ No newline at end of file
@@ -1,20 +1,297 @@
+use std::cmp::Ordering;
+use std::fmt;
+
 use super::items::*;
+use super::errors::DocError;
 use super::utility::*;
 
-#[derive(PartialEq)]
+#[derive(Debug, Clone)]
+pub enum NumberValue {
+    Int(i64),
+    UInt(u64),
+    Int128(i128),
+    Float(f64),
+    Decimal(String),
+}
+
+impl NumberValue {
+
+    // Picks the narrowest exact representation for a JSON/YAML number
+    // literal: integers stay integers and a float that would lose digits
+    // is kept as a decimal. Exponents beyond the i64 range become zero when
+    // negative and make the literal unrepresentable when positive.
+    pub fn parse(text: &str) -> Option<NumberValue> {
+        let parts = decimal_parts(text)?;
+        if !text.contains(['.', 'e', 'E']) {
+            if let Ok(n) = text.parse::<i64>() {
+                return Some(NumberValue::Int(n));
+            }
+            if let Ok(n) = text.trim_start_matches('+').parse::<u64>() {
+                return Some(NumberValue::UInt(n));
+            }
+            if let Ok(n) = text.parse::<i128>() {
+                return Some(NumberValue::Int128(n));
+            }
+        } else if let Ok(f) = text.parse::<f64>() {
+            if f.is_finite() && decimal_parts(&format!("{:e}", f)) == Some(parts.clone()) {
+                return Some(NumberValue::Float(f));
+            }
+        }
+        Some(NumberValue::Decimal(format_decimal(&parts)))
+    }
+
+    pub fn from_decimal_str(text: &str) -> Option<NumberValue> {
+        decimal_parts(text).map(|parts| NumberValue::Decimal(format_decimal(&parts)))
+    }
+
+    pub fn is_integer(&self) -> bool {
+        matches!(self, NumberValue::Int(_) | NumberValue::UInt(_) | NumberValue::Int128(_))
+    }
+    pub fn is_float(&self) -> bool {
+        matches!(self, NumberValue::Float(_))
+    }
+    pub fn is_decimal(&self) -> bool {
+        matches!(self, NumberValue::Decimal(_))
+    }
+
+    pub fn as_i128(&self) -> Option<i128> {
+        match self {
+            NumberValue::Int(n) => Some(*n as i128),
+            NumberValue::UInt(n) => Some(*n as i128),
+            NumberValue::Int128(n) => Some(*n),
+            NumberValue::Float(f) => {
+                if f.is_finite() && f.fract() == 0.0 && f.abs() < 1.7e38 {
+                    Some(*f as i128)
+                } else {
+                    None
+                }
+            },
+            NumberValue::Decimal(text) => {
+                let (negative, digits, exponent) = decimal_parts(text)?;
+                if exponent < 0 || digits.len() as i64 + exponent > 39 {
+                    return None;
+                }
+                let whole = format!("{}{}{}", if negative { "-" } else { "" }, digits, "0".repeat(exponent as usize));
+                whole.parse::<i128>().ok()
+            },
+        }
+    }
+    pub fn as_i64(&self) -> Option<i64> {
+        self.as_i128().and_then(|n| i64::try_from(n).ok())
+    }
+    pub fn as_u64(&self) -> Option<u64> {
+        self.as_i128().and_then(|n| u64::try_from(n).ok())
+    }
+    pub fn as_f64(&self) -> Option<f64> {
+        let value = self.to_f64();
+        match self {
+            NumberValue::Float(f) => Some(*f),
+            _ if value.is_finite() && NumberValue::Float(value) == *self => Some(value),
+            _ => None,
+        }
+    }
+
+    // Nearest f64, which may round large integers and long decimals.
+    pub fn to_f64(&self) -> f64 {
+        match self {
+            NumberValue::Int(n) => *n as f64,
+            NumberValue::UInt(n) => *n as f64,
+            NumberValue::Int128(n) => *n as f64,
+            NumberValue::Float(f) => *f,
+            NumberValue::Decimal(text) => text.parse::<f64>().unwrap_or(f64::NAN),
+        }
+    }
+
+    fn parts(&self) -> Option<(bool, String, i64)> {
+        match self {
+            NumberValue::Int(n) => decimal_parts(&n.to_string()),
+            NumberValue::UInt(n) => decimal_parts(&n.to_string()),
+            NumberValue::Int128(n) => decimal_parts(&n.to_string()),
+            NumberValue::Float(f) if f.is_finite() => decimal_parts(&format!("{:e}", f)),
+            NumberValue::Float(_) => None,
+            NumberValue::Decimal(text) => decimal_parts(text),
+        }
+    }
+}
+
+impl PartialEq for NumberValue {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (NumberValue::Float(a), NumberValue::Float(b)) => a == b,
+            (a, b) if a.is_integer() && b.is_integer() => a.as_i128() == b.as_i128(),
+            (a, b) => match (a.parts(), b.parts()) {
+                (Some(x), Some(y)) => x == y,
+                _ => false,
+            },
+        }
+    }
+}
+
+// Orders numbers by their exact value, so integers beyond 2^53 and long
+// decimals compare correctly against each other and against floats. NaN
+// is unordered and infinities sort beyond every finite number.
+impl PartialOrd for NumberValue {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        match (self, other) {
+            (NumberValue::Float(a), NumberValue::Float(b)) => a.partial_cmp(b),
+            (a, b) if a.is_integer() && b.is_integer() => a.as_i128().partial_cmp(&b.as_i128()),
+            (NumberValue::Float(f), _) if f.is_nan() => None,
+            (_, NumberValue::Float(f)) if f.is_nan() => None,
+            (NumberValue::Float(f), _) if f.is_infinite() => Some(if *f > 0.0 { Ordering::Greater } else { Ordering::Less }),
+            (_, NumberValue::Float(f)) if f.is_infinite() => Some(if *f > 0.0 { Ordering::Less } else { Ordering::Greater }),
+            (a, b) => Some(compare_parts(&a.parts()?, &b.parts()?)),
+        }
+    }
+}
+
+// Compares by sign, then by the position of the leading digit, then digit
+// by digit; without trailing zeros the digit strings compare as text.
+fn compare_parts(a: &(bool, String, i64), b: &(bool, String, i64)) -> Ordering {
+    let sign = |(negative, digits, _): &(bool, String, i64)| match (digits.as_str(), negative) {
+        ("0", _) => 0,
+        (_, true) => -1,
+        (_, false) => 1,
+    };
+    let (sa, sb) = (sign(a), sign(b));
+    if sa != sb || sa == 0 {
+        return sa.cmp(&sb);
+    }
+    let lead = |(_, digits, exponent): &(bool, String, i64)| digits.len() as i128 + *exponent as i128;
+    let magnitude = lead(a).cmp(&lead(b)).then_with(|| a.1.cmp(&b.1));
+    if sa < 0 { magnitude.reverse() } else { magnitude }
+}
+
+// Writes the number so that reading it back yields the same representation:
+// floats use the shortest digits that round-trip, an integral float keeps a
+// trailing ".0" and magnitudes outside [1e-6, 1e21) switch to exponent form.
+impl fmt::Display for NumberValue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            NumberValue::Int(n) => write!(f, "{}", n),
+            NumberValue::UInt(n) => write!(f, "{}", n),
+            NumberValue::Int128(n) => write!(f, "{}", n),
+            NumberValue::Float(n) if n.is_finite() && *n != 0.0 && !(1e-6..1e21).contains(&n.abs()) => write!(f, "{:e}", n),
+            NumberValue::Float(n) if n.is_finite() && n.fract() == 0.0 => write!(f, "{:.1}", n),
+            NumberValue::Float(n) => write!(f, "{}", n),
+            NumberValue::Decimal(text) => write!(f, "{}", text),
+        }
+    }
+}
+
+// Splits a decimal literal into sign, significant digits and a power of ten
+// with leading and trailing zeros removed, so equal values compare equal.
+fn decimal_parts(text: &str) -> Option<(bool, String, i64)> {
+    let (negative, rest) = match text.as_bytes().first()? {
+        b'-' => (true, &text[1..]),
+        b'+' => (false, &text[1..]),
+        _ => (false, text),
+    };
+    let (mantissa, mut exponent) = match rest.find(['e', 'E']) {
+        Some(i) => (&rest[..i], parse_exponent(&rest[i + 1..])?),
+        None => (rest, 0),
+    };
+    let (whole, fraction) = match mantissa.find('.') {
+        Some(i) => (&mantissa[..i], &mantissa[i + 1..]),
+        None => (mantissa, ""),
+    };
+    if whole.is_empty() && fraction.is_empty() {
+        return None;
+    }
+    if !whole.bytes().chain(fraction.bytes()).all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    exponent -= fraction.len() as i128;
+    let digits = format!("{}{}", whole, fraction);
+    let digits = digits.trim_start_matches('0');
+    let significant = digits.trim_end_matches('0');
+    exponent += (digits.len() - significant.len()) as i128;
+    // A power of ten too small for an i64 underflows to zero, the way it
+    // would for a float; one too large has no representation at all.
+    if digits.is_empty() || exponent < i64::MIN as i128 {
+        return Some((false, "0".to_string(), 0));
+    }
+    Some((negative, significant.to_string(), i64::try_from(exponent).ok()?))
+}
+
+// Exponents far outside the i64 range saturate instead of failing, so that
+// decimal_parts can tell underflow from overflow.
+fn parse_exponent(text: &str) -> Option<i128> {
+    let (negative, digits) = match text.as_bytes().first()? {
+        b'-' => (true, &text[1..]),
+        b'+' => (false, &text[1..]),
+        _ => (false, text),
+    };
+    if digits.is_empty() || !digits.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    let digits = digits.trim_start_matches('0');
+    let magnitude = match digits.len() {
+        0 => 0,
+        1..=30 => digits.parse::<i128>().ok()?,
+        _ => i128::MAX / 2,
+    };
+    Some(if negative { -magnitude } else { magnitude })
+}
+
+fn format_decimal(parts: &(bool, String, i64)) -> String {
+    let (negative, digits, exponent) = parts;
+    let sign = if *negative { "-" } else { "" };
+    let scale = -exponent;
+    if *exponent == 0 {
+        format!("{}{}", sign, digits)
+    } else if scale > 0 && scale < digits.len() as i64 {
+        let point = digits.len() - scale as usize;
+        format!("{}{}.{}", sign, &digits[..point], &digits[point..])
+    } else if scale > 0 && scale <= digits.len() as i64 + 6 {
+        format!("{}0.{}{}", sign, "0".repeat(scale as usize - digits.len()), digits)
+    } else {
+        format!("{}{}e{}", sign, digits, exponent)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
 pub struct ItemNumber {
-    value: f64,
+    value: NumberValue,
 }
 
 impl ItemNumber {
 
+    #[allow(clippy::new_ret_no_self)]
     pub fn new(value: Option<f64>) -> ItemType {
-        let me = ItemType::TNumber(ItemNumber { 
-            value: value.unwrap_or(0.0)
-        });
-        me
+        ItemNumber::with_value(NumberValue::Float(value.unwrap_or(0.0)))
     }
 
+    pub fn with_value(value: NumberValue) -> ItemType {
+        ItemType::TNumber(ItemNumber {
+            value
+        })
+    }
+
+    pub fn get_value(&self) -> f64 {
+        self.value.to_f64()
+    }
+
+    pub fn get_number(&self) -> &NumberValue {
+        &self.value
+    }
+
+    pub fn set_value(&mut self, value: f64) {
+        self.value = NumberValue::Float(value);
+    }
+
+    pub fn set_number(&mut self, value: NumberValue) {
+        self.value = value;
+    }
+
+    pub fn is_integer(&self) -> bool { self.value.is_integer() }
+    pub fn is_float(&self) -> bool { self.value.is_float() }
+    pub fn is_decimal(&self) -> bool { self.value.is_decimal() }
+
+    pub fn as_i64(&self) -> Option<i64> { self.value.as_i64() }
+    pub fn as_u64(&self) -> Option<u64> { self.value.as_u64() }
+    pub fn as_i128(&self) -> Option<i128> { self.value.as_i128() }
+    pub fn as_f64(&self) -> Option<f64> { self.value.as_f64() }
+
 
     pub fn is_null(&self) -> bool { false }
     pub fn is_boolean(&self) -> bool { false }
@@ -26,27 +303,30 @@ impl ItemNumber {
 
 
     pub fn has_item(&self, _item: &ItemType) -> bool { false }
-    pub fn get_item<'a, L: Into<ContainerKey<'a>>>(&'a self, _lookup: L) -> Result<Option<&'a ItemType>, ItemError> {
-        Err(ItemError::NotAnItemContainer)
+    pub fn get_item<'a, L: Into<ContainerKey<'a>>>(&self, _lookup: L) -> Result<Option<&ItemType>, DocError> {
+        Err(ItemError::NotAnItemContainer.into())
+    }
+    pub fn get_item_mut<'a, L: Into<ContainerKey<'a>>>(&mut self, _lookup: L) -> Result<Option<&mut ItemType>, DocError> {
+        Err(ItemError::NotAnItemContainer.into())
     }
 
 
-    pub fn add_null<'a>(&mut self, _key: Option<&'a str>) -> Result<&mut Self, ItemError> {
-        Err(ItemError::NotAnItemContainer)
+    pub fn add_null(&mut self, _key: Option<&str>) -> Result<&mut Self, DocError> {
+        Err(ItemError::NotAnItemContainer.into())
     }
-    pub fn add_value<'a, V: Into<RustType>>(&mut self, _value: V, _key: Option<&'a str>) -> Result<(), ItemError> {
-        Err(ItemError::NotAnItemContainer)
+    pub fn add_value<V: Into<RustType>>(&mut self, _value: V, _key: Option<&str>) -> Result<(), DocError> {
+        Err(ItemError::NotAnItemContainer.into())
     }
-    pub fn add_list<'a>(&mut self, _key: Option<&'a str>) -> Result<&mut ItemType, ItemError> {
-        Err(ItemError::NotAnItemContainer)
+    pub fn add_list(&mut self, _key: Option<&str>) -> Result<&mut ItemType, DocError> {
+        Err(ItemError::NotAnItemContainer.into())
     }
-    pub fn add_hash<'a>(&mut self, _key: Option<&'a str>) -> Result<&mut ItemType, ItemError> {
-        Err(ItemError::NotAnItemContainer)
+    pub fn add_hash(&mut self, _key: Option<&str>) -> Result<&mut ItemType, DocError> {
+        Err(ItemError::NotAnItemContainer.into())
     }
 
 
-    pub fn remove_item<'a>(&mut self, _key: ContainerKey<'a>) -> Result<Option<ItemType>, ItemError> {
-        Err(ItemError::NotAnItemContainer)
+    pub fn remove_item<'a>(&mut self, _key: ContainerKey<'a>) -> Result<Option<ItemType>, DocError> {
+        Err(ItemError::NotAnItemContainer.into())
     }
 
 
@@ -56,16 +336,18 @@ impl ItemNumber {
     pub fn has_key<'a, K: Into<ContainerKey<'a>>>(&self, _key: K) -> bool {
         false
     }
-    pub fn get_key<'a>(&'a self, _item: &ItemType) -> Result<Option<ContainerKey<'a>>, ItemError> {
-        Err(ItemError::NotAnItemContainer)
+    pub fn get_key<'a>(&'a self, _item: &ItemType) -> Result<Option<ContainerKey<'a>>, DocError> {
+        Err(ItemError::NotAnItemContainer.into())
     }
-    pub fn get_keys<'a>(&'a self) -> Result<Vec<ContainerKey<'a>>, ItemError> {
-        Err(ItemError::NotAnItemContainer)
+    pub fn get_keys<'a>(&'a self) -> Result<Vec<ContainerKey<'a>>, DocError> {
+        Err(ItemError::NotAnItemContainer.into())
     }
 
 
-    pub fn to_string(&self) -> String {
-        self.value.to_string()
-    }
+}
 
-}
\ No newline at end of file
+impl fmt::Display for ItemNumber {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.value)
+    }
+}
@@ -0,0 +1,239 @@
+use std::fmt;
+use std::str::FromStr;
+
+use super::items::*;
+use super::errors::DocError;
+use super::lists::ItemList;
+use super::hashes::ItemHash;
+
+// An RFC 6901 JSON Pointer such as "/servers/0/ports". Segments are kept
+// unescaped; "~0" and "~1" only appear in the printed form.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Default)]
+pub struct ItemPointer {
+    segments: Vec<String>,
+}
+
+impl ItemPointer {
+
+    pub fn root() -> Self {
+        ItemPointer { segments: Vec::new() }
+    }
+
+    pub fn parse(text: &str) -> Result<Self, DocError> {
+        if text.is_empty() {
+            return Ok(ItemPointer::root());
+        }
+        let rest = text.strip_prefix('/').ok_or(ItemError::InvalidPointer)?;
+        let mut segments = Vec::new();
+        for raw in rest.split('/') {
+            segments.push(unescape_segment(raw)?);
+        }
+        Ok(ItemPointer { segments })
+    }
+
+    pub fn from_segments<I, S>(segments: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        ItemPointer { segments: segments.into_iter().map(Into::into).collect() }
+    }
+
+
+    pub fn is_root(&self) -> bool {
+        self.segments.is_empty()
+    }
+    pub fn count(&self) -> usize {
+        self.segments.len()
+    }
+    pub fn get_segments(&self) -> &[String] {
+        &self.segments
+    }
+    pub fn get_segment(&self, position: usize) -> Option<&str> {
+        self.segments.get(position).map(|s| s.as_str())
+    }
+    pub fn last(&self) -> Option<&str> {
+        self.segments.last().map(|s| s.as_str())
+    }
+    pub fn starts_with(&self, prefix: &ItemPointer) -> bool {
+        self.segments.starts_with(&prefix.segments)
+    }
+    pub fn parent(&self) -> Option<ItemPointer> {
+        let (_, parents) = self.segments.split_last()?;
+        Some(ItemPointer { segments: parents.to_vec() })
+    }
+
+
+    pub fn push(&mut self, segment: &str) {
+        self.segments.push(segment.to_string());
+    }
+    pub fn push_key<'a>(&mut self, key: &ContainerKey<'a>) {
+        match key {
+            ContainerKey::Idx(i) => self.segments.push(i.to_string()),
+            ContainerKey::Key(k) => self.segments.push(k.to_string()),
+        }
+    }
+    pub fn pop(&mut self) -> Option<String> {
+        self.segments.pop()
+    }
+    pub fn join(&self, segment: &str) -> ItemPointer {
+        let mut pointer = self.clone();
+        pointer.push(segment);
+        pointer
+    }
+
+}
+
+impl fmt::Display for ItemPointer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for segment in &self.segments {
+            write!(f, "/{}", segment.replace('~', "~0").replace('/', "~1"))?;
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for ItemPointer {
+    type Err = DocError;
+
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        ItemPointer::parse(text)
+    }
+}
+
+impl TryFrom<&str> for ItemPointer {
+    type Error = DocError;
+
+    fn try_from(text: &str) -> Result<Self, Self::Error> {
+        ItemPointer::parse(text)
+    }
+}
+
+fn unescape_segment(raw: &str) -> Result<String, ItemError> {
+    let mut segment = String::with_capacity(raw.len());
+    let mut chars = raw.chars();
+    while let Some(c) = chars.next() {
+        if c == '~' {
+            match chars.next() {
+                Some('0') => segment.push('~'),
+                Some('1') => segment.push('/'),
+                _ => return Err(ItemError::InvalidPointer),
+            }
+        } else {
+            segment.push(c);
+        }
+    }
+    Ok(segment)
+}
+
+// Array indexes are plain decimal without leading zeros; "-" names the
+// position just past the last element.
+pub(crate) fn parse_index(segment: &str, len: usize) -> Option<usize> {
+    if segment == "-" {
+        return Some(len);
+    }
+    if segment.is_empty() || (segment.len() > 1 && segment.starts_with('0')) {
+        return None;
+    }
+    if !segment.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    segment.parse::<usize>().ok()
+}
+
+fn child_key<'a>(item: &ItemType, segment: &'a str, position: usize) -> Result<ContainerKey<'a>, DocError> {
+    match item {
+        ItemType::TList(list) => parse_index(segment, list.count())
+            .map(ContainerKey::Idx)
+            .ok_or_else(|| ItemError::PointerInvalidIndex(position).into()),
+        ItemType::THash(_) => Ok(ContainerKey::Key(segment)),
+        _ => Err(ItemError::PointerNotAContainer(position).into()),
+    }
+}
+
+pub(crate) fn not_found(error: DocError, position: usize) -> DocError {
+    match error.get_item_error() {
+        Some(ItemError::ItemNotFound) => ItemError::PointerNotFound(position).into(),
+        _ => error,
+    }
+}
+
+// Errors come back located at the part of the pointer that resolved.
+pub(crate) fn resolve<'a>(root: &'a ItemType, pointer: &ItemPointer) -> Result<&'a ItemType, DocError> {
+    let mut current = root;
+    for (position, segment) in pointer.segments.iter().enumerate() {
+        let key = child_key(current, segment, position).map_err(|e| e.at_pointer(pointer))?;
+        current = current.get_item(key)?.ok_or_else(|| DocError::from(ItemError::PointerNotFound(position)).at_pointer(pointer))?;
+    }
+    Ok(current)
+}
+
+pub(crate) fn resolve_mut<'a>(root: &'a mut ItemType, pointer: &ItemPointer) -> Result<&'a mut ItemType, DocError> {
+    let mut current = root;
+    for (position, segment) in pointer.segments.iter().enumerate() {
+        let key = child_key(current, segment, position).map_err(|e| e.at_pointer(pointer))?;
+        current = current.get_item_mut(key)?.ok_or_else(|| DocError::from(ItemError::PointerNotFound(position)).at_pointer(pointer))?;
+    }
+    Ok(current)
+}
+
+// Missing intermediates become lists when the following segment looks like
+// an index and hashes otherwise.
+pub(crate) fn set(root: &mut ItemType, pointer: &ItemPointer, item: ItemType, create: bool) -> Result<Option<ItemType>, DocError> {
+    set_within(root, pointer, item, create).map_err(|e| e.at_pointer(pointer))
+}
+
+fn set_within(root: &mut ItemType, pointer: &ItemPointer, item: ItemType, create: bool) -> Result<Option<ItemType>, DocError> {
+    let (last, parents) = match pointer.segments.split_last() {
+        Some(split) => split,
+        None => return Ok(Some(std::mem::replace(root, item))),
+    };
+    let mut current = root;
+    for (position, segment) in parents.iter().enumerate() {
+        if !current.has_key(child_key(current, segment, position)?) {
+            if !create {
+                return Err(ItemError::PointerNotFound(position).into());
+            }
+            let next = &pointer.segments[position + 1];
+            let container = match parse_index(next, 0) {
+                Some(_) => ItemList::new(),
+                None => ItemHash::new(),
+            };
+            let key = child_key(current, segment, position)?;
+            current.add_or_replace(key, container).map_err(|e| not_found(e, position))?;
+        }
+        let key = child_key(current, segment, position)?;
+        current = current.get_item_mut(key)?.ok_or(ItemError::PointerNotFound(position))?;
+    }
+    let position = parents.len();
+    let key = child_key(current, last, position)?;
+    current.add_or_replace(key, item).map_err(|e| not_found(e, position))
+}
+
+pub(crate) fn remove(root: &mut ItemType, pointer: &ItemPointer) -> Result<ItemType, DocError> {
+    let parent = pointer.parent().ok_or(ItemError::InvalidPointer)?;
+    let position = parent.count();
+    let current = resolve_mut(root, &parent)?;
+    let key = child_key(current, &pointer.segments[position], position).map_err(|e| e.at_pointer(pointer))?;
+    current.remove_item(key)?.ok_or_else(|| DocError::from(ItemError::PointerNotFound(position)).at_pointer(pointer))
+}
+
+// Adds with RFC 6902 semantics: the parent must exist, list members are
+// inserted rather than overwritten and the root is replaced outright.
+pub(crate) fn insert(root: &mut ItemType, pointer: &ItemPointer, item: ItemType) -> Result<(), DocError> {
+    let parent = match pointer.parent() {
+        Some(parent) => parent,
+        None => {
+            *root = item;
+            return Ok(());
+        },
+    };
+    let position = parent.count();
+    let current = resolve_mut(root, &parent)?;
+    let result = match child_key(current, &pointer.segments[position], position) {
+        Ok(ContainerKey::Idx(i)) => current.insert_at(i, item, None).map_err(|e| not_found(e, position)),
+        Ok(key) => current.add_or_replace(key, item).map(|_| ()),
+        Err(e) => Err(e),
+    };
+    result.map_err(|e| e.at_pointer(pointer))
+}
@@ -1,12 +1,12 @@
 use super::items::ItemType;
 use super::booleans::ItemBoolean;
-use super::numbers::ItemNumber;
+use super::numbers::{ItemNumber, NumberValue};
 use super::strings::ItemString;
 
 #[derive(Debug, Clone, PartialEq)]
 pub enum RustType {
     Bool(bool),
-    Number(f64),
+    Number(NumberValue),
     String(String),
 }
 
@@ -18,60 +18,71 @@ impl From<bool> for RustType {
 
 impl From<i8> for RustType {
     fn from(n: i8) -> Self {
-        RustType::Number(n as f64)
+        RustType::Number(NumberValue::Int(n as i64))
     }
 }
 impl From<i16> for RustType {
     fn from(n: i16) -> Self {
-        RustType::Number(n as f64)
+        RustType::Number(NumberValue::Int(n as i64))
     }
 }
 impl From<i32> for RustType {
     fn from(n: i32) -> Self {
-        RustType::Number(n as f64)
+        RustType::Number(NumberValue::Int(n as i64))
     }
 }
 impl From<i64> for RustType {
     fn from(n: i64) -> Self {
-        RustType::Number(n as f64)
+        RustType::Number(NumberValue::Int(n))
     }
 }
 
 impl From<u8> for RustType {
     fn from(n: u8) -> Self {
-        RustType::Number(n as f64)
+        RustType::Number(NumberValue::UInt(n as u64))
     }
 }
 impl From<u16> for RustType {
     fn from(n: u16) -> Self {
-        RustType::Number(n as f64)
+        RustType::Number(NumberValue::UInt(n as u64))
     }
 }
 impl From<u32> for RustType {
     fn from(n: u32) -> Self {
-        RustType::Number(n as f64)
+        RustType::Number(NumberValue::UInt(n as u64))
     }
 }
 impl From<u64> for RustType {
     fn from(n: u64) -> Self {
-        RustType::Number(n as f64)
+        RustType::Number(NumberValue::UInt(n))
     }
 }
 
 impl From<f32> for RustType {
     fn from(n: f32) -> Self {
-        RustType::Number(n as f64)
+        RustType::Number(NumberValue::Float(n as f64))
     }
 }
 impl From<f64> for RustType {
     fn from(n: f64) -> Self {
+        RustType::Number(NumberValue::Float(n))
+    }
+}
+
+impl From<i128> for RustType {
+    fn from(n: i128) -> Self {
+        RustType::Number(NumberValue::Int128(n))
+    }
+}
+impl From<NumberValue> for RustType {
+    fn from(n: NumberValue) -> Self {
         RustType::Number(n)
     }
 }
 
 impl From<usize> for RustType {
     fn from(n: usize) -> Self {
-        RustType::Number(n as f64)
+        RustType::Number(NumberValue::UInt(n as u64))
     }
 }
 
@@ -91,9 +102,20 @@ impl RustType {
     pub fn into_item_type(self) -> ItemType {
         match self {
             RustType::Bool(b) => ItemBoolean::new(Some(b)),
-            RustType::Number(n) => ItemNumber::new(Some(n)),
+            RustType::Number(n) => ItemNumber::with_value(n),
             RustType::String(s) => ItemString::new(Some(s)),
         }
     }
 }
 
+
+// Lets item! interpolate scalar values, collections and ready-made items.
+pub trait IntoItemType {
+    fn into_item_type(self) -> ItemType;
+}
+
+impl<T: Into<ItemType>> IntoItemType for T {
+    fn into_item_type(self) -> ItemType {
+        self.into()
+    }
+}
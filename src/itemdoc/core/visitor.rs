@@ -0,0 +1,120 @@
+use super::items::*;
+use super::nulls::ItemNull;
+use super::booleans::ItemBoolean;
+use super::numbers::ItemNumber;
+use super::strings::ItemString;
+use super::lists::ItemList;
+use super::hashes::ItemHash;
+
+// Read-only traversal of a document. Every callback has a default, so a
+// visitor only overrides the ones it cares about. Overriding visit_list or
+// visit_hash without calling walk_list or walk_hash skips the members.
+pub trait ItemVisitor {
+    fn visit_item(&mut self, item: &ItemType) {
+        walk_item(self, item);
+    }
+    fn visit_member(&mut self, _key: ContainerKey<'_>, item: &ItemType) {
+        self.visit_item(item);
+    }
+
+    fn visit_null(&mut self, _null: &ItemNull) {}
+    fn visit_boolean(&mut self, _boolean: &ItemBoolean) {}
+    fn visit_number(&mut self, _number: &ItemNumber) {}
+    fn visit_string(&mut self, _string: &ItemString) {}
+
+    fn visit_list(&mut self, list: &ItemList) {
+        walk_list(self, list);
+    }
+    fn enter_list(&mut self, _list: &ItemList) {}
+    fn leave_list(&mut self, _list: &ItemList) {}
+
+    fn visit_hash(&mut self, hash: &ItemHash) {
+        walk_hash(self, hash);
+    }
+    fn enter_hash(&mut self, _hash: &ItemHash) {}
+    fn leave_hash(&mut self, _hash: &ItemHash) {}
+}
+
+pub fn walk_item<V: ItemVisitor + ?Sized>(visitor: &mut V, item: &ItemType) {
+    match item {
+        ItemType::TNull(mapped) => visitor.visit_null(mapped),
+        ItemType::TBoolean(mapped) => visitor.visit_boolean(mapped),
+        ItemType::TNumber(mapped) => visitor.visit_number(mapped),
+        ItemType::TString(mapped) => visitor.visit_string(mapped),
+        ItemType::TList(mapped) => visitor.visit_list(mapped),
+        ItemType::THash(mapped) => visitor.visit_hash(mapped),
+    }
+}
+
+pub fn walk_list<V: ItemVisitor + ?Sized>(visitor: &mut V, list: &ItemList) {
+    visitor.enter_list(list);
+    for (key, item) in list.iter() {
+        visitor.visit_member(key, item);
+    }
+    visitor.leave_list(list);
+}
+
+pub fn walk_hash<V: ItemVisitor + ?Sized>(visitor: &mut V, hash: &ItemHash) {
+    visitor.enter_hash(hash);
+    for (key, item) in hash.iter() {
+        visitor.visit_member(key, item);
+    }
+    visitor.leave_hash(hash);
+}
+
+// In-place transformation of a document. Containers are handed to
+// leave_list and leave_hash after their members, which is the place to
+// drop or rename members. Override visit_item to replace an item with one
+// of a different kind.
+pub trait ItemVisitorMut {
+    fn visit_item(&mut self, item: &mut ItemType) {
+        walk_item_mut(self, item);
+    }
+    fn visit_member(&mut self, _key: ContainerKey<'_>, item: &mut ItemType) {
+        self.visit_item(item);
+    }
+
+    fn visit_null(&mut self, _null: &mut ItemNull) {}
+    fn visit_boolean(&mut self, _boolean: &mut ItemBoolean) {}
+    fn visit_number(&mut self, _number: &mut ItemNumber) {}
+    fn visit_string(&mut self, _string: &mut ItemString) {}
+
+    fn visit_list(&mut self, list: &mut ItemList) {
+        walk_list_mut(self, list);
+    }
+    fn enter_list(&mut self, _list: &mut ItemList) {}
+    fn leave_list(&mut self, _list: &mut ItemList) {}
+
+    fn visit_hash(&mut self, hash: &mut ItemHash) {
+        walk_hash_mut(self, hash);
+    }
+    fn enter_hash(&mut self, _hash: &mut ItemHash) {}
+    fn leave_hash(&mut self, _hash: &mut ItemHash) {}
+}
+
+pub fn walk_item_mut<V: ItemVisitorMut + ?Sized>(visitor: &mut V, item: &mut ItemType) {
+    match item {
+        ItemType::TNull(mapped) => visitor.visit_null(mapped),
+        ItemType::TBoolean(mapped) => visitor.visit_boolean(mapped),
+        ItemType::TNumber(mapped) => visitor.visit_number(mapped),
+        ItemType::TString(mapped) => visitor.visit_string(mapped),
+        ItemType::TList(mapped) => visitor.visit_list(mapped),
+        ItemType::THash(mapped) => visitor.visit_hash(mapped),
+    }
+}
+
+pub fn walk_list_mut<V: ItemVisitorMut + ?Sized>(visitor: &mut V, list: &mut ItemList) {
+    visitor.enter_list(list);
+    for (key, item) in list.iter_mut() {
+        visitor.visit_member(key, item);
+    }
+    visitor.leave_list(list);
+}
+
+pub fn walk_hash_mut<V: ItemVisitorMut + ?Sized>(visitor: &mut V, hash: &mut ItemHash) {
+    visitor.enter_hash(hash);
+    for (key, item) in hash.iter_mut() {
+        visitor.visit_member(key, item);
+    }
+    visitor.leave_hash(hash);
+}
@@ -0,0 +1,82 @@
+use super::items::*;
+use super::pointers::ItemPointer;
+
+#[derive(Debug, Copy, Clone, PartialEq, Default)]
+pub enum WalkOrder {
+    // Parents are yielded before their children.
+    #[default]
+    PreOrder,
+    // Children are yielded before their parents.
+    PostOrder,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct WalkEntry<'a> {
+    pub path: ItemPointer,
+    pub depth: usize,
+    pub item: &'a ItemType,
+}
+
+// A depth-first traversal of every item in a document, the root included.
+// Members are visited in container order.
+pub struct Walk<'a> {
+    order: WalkOrder,
+    stack: Vec<(WalkEntry<'a>, bool)>,
+    expand: Option<WalkEntry<'a>>,
+}
+
+impl<'a> Walk<'a> {
+
+    pub fn new(root: &'a ItemType, order: WalkOrder) -> Self {
+        let entry = WalkEntry { path: ItemPointer::root(), depth: 0, item: root };
+        Walk { order, stack: vec![(entry, false)], expand: None }
+    }
+
+    pub fn get_order(&self) -> WalkOrder {
+        self.order
+    }
+
+    // Skips the children of the entry most recently returned. In post-order
+    // the children have already been visited, so this does nothing.
+    pub fn prune(&mut self) {
+        self.expand = None;
+    }
+
+    fn push_children(&mut self, entry: &WalkEntry<'a>) {
+        let children: Vec<(ContainerKey<'a>, &'a ItemType)> = entry.item.iter().collect();
+        for (key, item) in children.into_iter().rev() {
+            let mut path = entry.path.clone();
+            path.push_key(&key);
+            self.stack.push((WalkEntry { path, depth: entry.depth + 1, item }, false));
+        }
+    }
+
+}
+
+impl<'a> Iterator for Walk<'a> {
+    type Item = WalkEntry<'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        match self.order {
+            WalkOrder::PreOrder => {
+                // children are pushed lazily so prune() can still cancel them
+                if let Some(parent) = self.expand.take() {
+                    self.push_children(&parent);
+                }
+                let (entry, _) = self.stack.pop()?;
+                if entry.item.is_container() {
+                    self.expand = Some(entry.clone());
+                }
+                Some(entry)
+            },
+            WalkOrder::PostOrder => loop {
+                let (entry, expanded) = self.stack.pop()?;
+                if expanded || !entry.item.is_container() {
+                    return Some(entry);
+                }
+                self.stack.push((entry.clone(), true));
+                self.push_children(&entry);
+            },
+        }
+    }
+}
@@ -3,8 +3,11 @@ use std::io::Write;
 use std::rc::Rc;
 use std::fmt;
 
+use crate::itemdoc::core::errors::DocError;
+use crate::itemdoc::core::numbers::NumberValue;
+
 #[derive(Debug)]
-#[derive(Copy, Clone)]
+#[derive(Copy, Clone, PartialEq)]
 pub enum ContextError {
     NotAnItemList,
     NotAnItemHash,
@@ -12,24 +15,6 @@ pub enum ContextError {
 
 impl std::error::Error for ContextError {}
 
-#[derive(Debug)]
-pub enum ExportError {
-    Io(std::io::Error),
-    Context(ContextError),
-}
-
-impl From<std::io::Error> for ExportError {
-    fn from(err: std::io::Error) -> Self {
-        ExportError::Io(err)
-    }
-}
-
-impl From<ContextError> for ExportError {
-    fn from(err: ContextError) -> Self {
-        ExportError::Context(err)
-    }
-}
-
 impl fmt::Display for ContextError {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
@@ -39,6 +24,7 @@ impl fmt::Display for ContextError {
     }
 }
 
+
 pub mod chardefs {
 
     // static values
@@ -61,6 +47,7 @@ pub mod chardefs {
     pub static CHRB_COLON_C: &[u8] = b":";
     pub static CHRB_COLON_S: &[u8] = b": ";
     pub static CHRB_DASH_S: &[u8] = b"- ";
+    pub static CHRB_SPACE: &[u8] = b" ";
 
     pub static CHRB_CRLF: &[u8] = b"\r\n";
     pub static CHRB_TAB: &[u8] = b"\t";
@@ -82,6 +69,12 @@ pub struct SimpleExportContext {
     stk_count: Vec<usize>
 }
 
+impl Default for SimpleExportContext {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl SimpleExportContext {
     pub fn new() -> Self {
         Self {
@@ -115,12 +108,11 @@ impl StructuredExportContext {
     }
 
     fn adjust_indent(&mut self) {
-        // if the len of the stk_indent vector is less than the len of the
-        // stk_type vector then we need to push a new indent value, if it's
-        // the same or larger then we already have value built
+        // stk_indent[n] holds the indent for stack level n+1; levels are
+        // built once on the way down and reused when the stack shrinks
         let level = self.context.get_stack_level();
 
-        if self.stk_indent.len() < level {
+        while self.stk_indent.len() < level {
             match self.stk_indent.len() {
                 0 => self.stk_indent.push(self.str_indent.clone()),
                 n => { 
@@ -136,33 +128,31 @@ impl StructuredExportContext {
 
     pub fn get_indent_vec(&self) -> &Rc<[u8]> {
         let level = self.context.get_stack_level();
-        let result = match level {
+        match level {
             0 => &self.vec_empty,
-            _ => &self.stk_indent[level],
-        };
-        result
+            _ => &self.stk_indent[level-1],
+        }
     }
 
     pub fn get_outdent_vec(&self) -> &Rc<[u8]> {
         let level = self.context.get_stack_level();
-        let result = match level {
+        match level {
             0 | 1 => &self.vec_empty,
-            _ => &self.stk_indent[level-1]
-        };
-        result
+            _ => &self.stk_indent[level-2]
+        }
     }
 
-    pub fn write_outdent(&self, writer: &mut Box<dyn Write, >) -> Result<(), ExportError> {
-        let outdent = self.get_outdent_vec().clone();
-        if outdent.len() > 0 {
-            writer.write(&outdent.clone())?;
+    pub fn write_outdent(&self, writer: &mut Box<dyn Write, >) -> Result<(), DocError> {
+        let outdent = self.get_outdent_vec();
+        if !outdent.is_empty() {
+            writer.write_all(outdent)?;
         }
         Ok(())
     }
     
-    pub fn write_indent(&self, writer: &mut Box<dyn Write>) -> Result<(), ExportError> {
-        let indent = self.get_outdent_vec();
-        if indent.len() > 0 {
+    pub fn write_indent(&self, writer: &mut Box<dyn Write>) -> Result<(), DocError> {
+        let indent = self.get_indent_vec();
+        if !indent.is_empty() {
             writer.write_all(indent)?;
         }
         Ok(())
@@ -201,7 +191,7 @@ impl ExportContext for SimpleExportContext {
     }
 
     fn incr_item_count(&mut self) {
-        if self.stk_count.len() > 0 {
+        if !self.stk_count.is_empty() {
             let tos = self.stk_count.len()-1;
             let count = self.stk_count[tos];
             self.stk_count[tos] = count + 1;
@@ -265,7 +255,7 @@ impl ExportContext for SimpleExportContext {
     fn is_first(&mut self) -> bool {
         match self.stk_count.len() {
             0 => true,
-            n => self.stk_count[n] == 0,
+            n => self.stk_count[n-1] == 0,
         }
     }
 
@@ -325,31 +315,31 @@ impl ExportContext for StructuredExportContext {
 }
 
 pub trait ItemOutput {
-    fn list_open(&mut self) -> Result<usize, ExportError>;
-    fn list_begin_next(&mut self) -> Result<(), ExportError>;
-    fn list_write_null(&mut self) -> Result<(), ExportError>;
-    fn list_write_bool(&mut self, value: bool) -> Result<(), ExportError>;
-    fn list_write_number(&mut self, value: f64) -> Result<(), ExportError>;
-    fn list_write_string(&mut self, value: &String) -> Result<(), ExportError>;
-    fn list_write_empty_list(&mut self) -> Result<(), ExportError>;
-    fn list_write_empty_hash(&mut self) -> Result<(), ExportError>;
-    fn list_close(&mut self) -> Result<usize, ExportError>;
-
-    fn hash_open(&mut self) -> Result<usize, ExportError>;
-    fn hash_begin_next(&mut self, key: &String) -> Result<(), ExportError>;
-    fn hash_write_key(&mut self, key: &String) -> Result<(), ExportError>;
-    fn hash_write_null(&mut self, key: &String) -> Result<(), ExportError>;
-    fn hash_write_bool(&mut self, key: &String, value: bool) -> Result<(), ExportError>;
-    fn hash_write_string(&mut self, key: &String, value: &String) -> Result<(), ExportError>;
-    fn hash_write_number(&mut self, key: &String, value: f64) -> Result<(), ExportError>;
-    fn hash_write_empty_list(&mut self, key: &String) -> Result<(), ExportError>;
-    fn hash_write_empty_hash(&mut self, key: &String) -> Result<(), ExportError>;
-    fn hash_close(&mut self) -> Result<usize, ExportError>;
+    fn list_open(&mut self) -> Result<usize, DocError>;
+    fn list_begin_next(&mut self) -> Result<(), DocError>;
+    fn list_write_null(&mut self) -> Result<(), DocError>;
+    fn list_write_bool(&mut self, value: bool) -> Result<(), DocError>;
+    fn list_write_number(&mut self, value: &NumberValue) -> Result<(), DocError>;
+    fn list_write_string(&mut self, value: &str) -> Result<(), DocError>;
+    fn list_write_empty_list(&mut self) -> Result<(), DocError>;
+    fn list_write_empty_hash(&mut self) -> Result<(), DocError>;
+    fn list_close(&mut self) -> Result<usize, DocError>;
+
+    fn hash_open(&mut self) -> Result<usize, DocError>;
+    fn hash_begin_next(&mut self, key: &str) -> Result<(), DocError>;
+    fn hash_write_key(&mut self, key: &str) -> Result<(), DocError>;
+    fn hash_write_null(&mut self, key: &str) -> Result<(), DocError>;
+    fn hash_write_bool(&mut self, key: &str, value: bool) -> Result<(), DocError>;
+    fn hash_write_string(&mut self, key: &str, value: &str) -> Result<(), DocError>;
+    fn hash_write_number(&mut self, key: &str, value: &NumberValue) -> Result<(), DocError>;
+    fn hash_write_empty_list(&mut self, key: &str) -> Result<(), DocError>;
+    fn hash_write_empty_hash(&mut self, key: &str) -> Result<(), DocError>;
+    fn hash_close(&mut self) -> Result<usize, DocError>;
 }
 
 pub mod stringhelp {
 
-    pub fn make_quoted_string(value: &String) -> String {
+    pub fn make_quoted_string(value: &str) -> String {
         let mut quoted = String::with_capacity(value.len() + 2);
         quoted.push('"');
         quoted.push_str(value);
@@ -357,7 +347,7 @@ pub mod stringhelp {
         quoted
     }
     
-    pub fn make_escaped_string(value: &String) -> String {
+    pub fn make_escaped_string(value: &str) -> String {
         let mut escaped = String::with_capacity(value.len()); // Initial capacity: content + 2 quotes
     
         for c in value.chars() {
@@ -380,7 +370,7 @@ pub mod stringhelp {
         escaped
     }
     
-    pub fn make_quoted_escaped_string(value: &String) -> String {
+    pub fn make_quoted_escaped_string(value: &str) -> String {
         let mut escaped = String::with_capacity(value.len() + 2); // Initial capacity: content + 2 quotes
         
         escaped.push('"');
@@ -1,10 +1,12 @@
-use super::core::{ItemOutput, ExportError};
+use crate::itemdoc::core::errors::DocError;
+use super::core::ItemOutput;
 use super::json::allman::*;
 use super::json::compact::*;
 use super::json::knr::*;
 use super::json::linear::*;
 use super::json::whitesmith::*;
 use super::yaml::yaml::*;
+use crate::itemdoc::core::numbers::NumberValue;
 
 pub enum OutputFormats {
     Compact(JSONFormatCompact),
@@ -17,7 +19,7 @@ pub enum OutputFormats {
 
 impl ItemOutput for OutputFormats {
 
-    fn list_open(&mut self) -> Result<usize, ExportError> { 
+    fn list_open(&mut self) -> Result<usize, DocError> { 
         match self {
             OutputFormats::Compact(fmt) => fmt.list_open(),
             OutputFormats::Linear(fmt) => fmt.list_open(),
@@ -27,7 +29,7 @@ impl ItemOutput for OutputFormats {
             OutputFormats::YAML(fmt) => fmt.list_open(),
         }
     }
-    fn list_begin_next(&mut self) -> Result<(), ExportError> { 
+    fn list_begin_next(&mut self) -> Result<(), DocError> { 
         match self {
             OutputFormats::Compact(fmt) => fmt.list_begin_next(),
             OutputFormats::Linear(fmt) => fmt.list_begin_next(),
@@ -37,7 +39,7 @@ impl ItemOutput for OutputFormats {
             OutputFormats::YAML(fmt) => fmt.list_begin_next(),
         }
     }
-    fn list_write_null(&mut self) -> Result<(), ExportError> { 
+    fn list_write_null(&mut self) -> Result<(), DocError> { 
         match self {
             OutputFormats::Compact(fmt) => fmt.list_write_null(),
             OutputFormats::Linear(fmt) => fmt.list_write_null(),
@@ -47,7 +49,7 @@ impl ItemOutput for OutputFormats {
             OutputFormats::YAML(fmt) => fmt.list_write_null(),
         }
     }
-    fn list_write_bool(&mut self, value: bool) -> Result<(), ExportError> { 
+    fn list_write_bool(&mut self, value: bool) -> Result<(), DocError> { 
         match self {
             OutputFormats::Compact(fmt) => fmt.list_write_bool(value),
             OutputFormats::Linear(fmt) => fmt.list_write_bool(value),
@@ -57,7 +59,7 @@ impl ItemOutput for OutputFormats {
             OutputFormats::YAML(fmt) => fmt.list_write_bool(value),
         }
     }
-    fn list_write_number(&mut self, value: f64) -> Result<(), ExportError> { 
+    fn list_write_number(&mut self, value: &NumberValue) -> Result<(), DocError> { 
         match self {
             OutputFormats::Compact(fmt) => fmt.list_write_number(value),
             OutputFormats::Linear(fmt) => fmt.list_write_number(value),
@@ -67,7 +69,7 @@ impl ItemOutput for OutputFormats {
             OutputFormats::YAML(fmt) => fmt.list_write_number(value),
         }
     }
-    fn list_write_string(&mut self, value: &String) -> Result<(), ExportError> { 
+    fn list_write_string(&mut self, value: &str) -> Result<(), DocError> { 
         match self {
             OutputFormats::Compact(fmt) => fmt.list_write_string(value),
             OutputFormats::Linear(fmt) => fmt.list_write_string(value),
@@ -77,7 +79,7 @@ impl ItemOutput for OutputFormats {
             OutputFormats::YAML(fmt) => fmt.list_write_string(value),
         }
     }
-    fn list_write_empty_list(&mut self) -> Result<(), ExportError> { 
+    fn list_write_empty_list(&mut self) -> Result<(), DocError> { 
         match self {
             OutputFormats::Compact(fmt) => fmt.list_write_empty_list(),
             OutputFormats::Linear(fmt) => fmt.list_write_empty_list(),
@@ -87,7 +89,7 @@ impl ItemOutput for OutputFormats {
             OutputFormats::YAML(fmt) => fmt.list_write_empty_list(),
         }
     }
-    fn list_write_empty_hash(&mut self) -> Result<(), ExportError> { 
+    fn list_write_empty_hash(&mut self) -> Result<(), DocError> { 
         match self {
             OutputFormats::Compact(fmt) => fmt.list_write_empty_hash(),
             OutputFormats::Linear(fmt) => fmt.list_write_empty_hash(),
@@ -97,7 +99,7 @@ impl ItemOutput for OutputFormats {
             OutputFormats::YAML(fmt) => fmt.list_write_empty_hash(),
         }
     }
-    fn list_close(&mut self) -> Result<usize, ExportError> { 
+    fn list_close(&mut self) -> Result<usize, DocError> { 
         match self {
             OutputFormats::Compact(fmt) => fmt.list_close(),
             OutputFormats::Linear(fmt) => fmt.list_close(),
@@ -108,7 +110,7 @@ impl ItemOutput for OutputFormats {
         }   
     }
 
-    fn hash_open(&mut self) -> Result<usize, ExportError> {
+    fn hash_open(&mut self) -> Result<usize, DocError> {
         match self {
             OutputFormats::Compact(fmt) => fmt.hash_open(),
             OutputFormats::Linear(fmt) => fmt.hash_open(),
@@ -118,7 +120,7 @@ impl ItemOutput for OutputFormats {
             OutputFormats::YAML(fmt) => fmt.hash_open(),
         }   
     }
-    fn hash_begin_next(&mut self, key: &String) -> Result<(), ExportError> {
+    fn hash_begin_next(&mut self, key: &str) -> Result<(), DocError> {
         match self {
             OutputFormats::Compact(fmt) => fmt.hash_begin_next(key),
             OutputFormats::Linear(fmt) => fmt.hash_begin_next(key),
@@ -128,7 +130,7 @@ impl ItemOutput for OutputFormats {
             OutputFormats::YAML(fmt) => fmt.hash_begin_next(key),
         }   
     }
-    fn hash_write_key(&mut self, key: &String) -> Result<(), ExportError> { 
+    fn hash_write_key(&mut self, key: &str) -> Result<(), DocError> { 
         match self {
             OutputFormats::Compact(fmt) => fmt.hash_write_key(key),
             OutputFormats::Linear(fmt) => fmt.hash_write_key(key),
@@ -138,7 +140,7 @@ impl ItemOutput for OutputFormats {
             OutputFormats::YAML(fmt) => fmt.hash_write_key(key),
         }   
     }
-    fn hash_write_null(&mut self, key: &String) -> Result<(), ExportError> { 
+    fn hash_write_null(&mut self, key: &str) -> Result<(), DocError> { 
         match self {
             OutputFormats::Compact(fmt) => fmt.hash_write_null(key),
             OutputFormats::Linear(fmt) => fmt.hash_write_null(key),
@@ -148,7 +150,7 @@ impl ItemOutput for OutputFormats {
             OutputFormats::YAML(fmt) => fmt.hash_write_null(key),
         }   
     }
-    fn hash_write_bool(&mut self, key: &String, value: bool) -> Result<(), ExportError> { 
+    fn hash_write_bool(&mut self, key: &str, value: bool) -> Result<(), DocError> { 
         match self {
             OutputFormats::Compact(fmt) => fmt.hash_write_bool(key, value),
             OutputFormats::Linear(fmt) => fmt.hash_write_bool(key, value),
@@ -158,7 +160,7 @@ impl ItemOutput for OutputFormats {
             OutputFormats::YAML(fmt) => fmt.hash_write_bool(key, value),
         }   
     }
-    fn hash_write_number(&mut self, key: &String, value: f64) -> Result<(), ExportError> { 
+    fn hash_write_number(&mut self, key: &str, value: &NumberValue) -> Result<(), DocError> { 
         match self {
             OutputFormats::Compact(fmt) => fmt.hash_write_number(key, value),
             OutputFormats::Linear(fmt) => fmt.hash_write_number(key, value),
@@ -168,7 +170,7 @@ impl ItemOutput for OutputFormats {
             OutputFormats::YAML(fmt) => fmt.hash_write_number(key, value),
         }   
     }
-    fn hash_write_string(&mut self, key: &String, value: &String) -> Result<(), ExportError> { 
+    fn hash_write_string(&mut self, key: &str, value: &str) -> Result<(), DocError> { 
         match self {
             OutputFormats::Compact(fmt) => fmt.hash_write_string(key, value),
             OutputFormats::Linear(fmt) => fmt.hash_write_string(key, value),
@@ -178,7 +180,7 @@ impl ItemOutput for OutputFormats {
             OutputFormats::YAML(fmt) => fmt.hash_write_string(key, value),
         }   
     }
-    fn hash_write_empty_list(&mut self, key: &String) -> Result<(), ExportError> {
+    fn hash_write_empty_list(&mut self, key: &str) -> Result<(), DocError> {
         match self {
             OutputFormats::Compact(fmt) => fmt.hash_write_empty_list(key),
             OutputFormats::Linear(fmt) => fmt.hash_write_empty_list(key),
@@ -188,7 +190,7 @@ impl ItemOutput for OutputFormats {
             OutputFormats::YAML(fmt) => fmt.hash_write_empty_list(key),
         }   
     }
-    fn hash_write_empty_hash(&mut self, key: &String) -> Result<(), ExportError> {
+    fn hash_write_empty_hash(&mut self, key: &str) -> Result<(), DocError> {
         match self {
             OutputFormats::Compact(fmt) => fmt.hash_write_empty_hash(key),
             OutputFormats::Linear(fmt) => fmt.hash_write_empty_hash(key),
@@ -198,7 +200,7 @@ impl ItemOutput for OutputFormats {
             OutputFormats::YAML(fmt) => fmt.hash_write_empty_hash(key),
         }   
     }
-    fn hash_close(&mut self) -> Result<usize, ExportError> {
+    fn hash_close(&mut self) -> Result<usize, DocError> {
         match self {
             OutputFormats::Compact(fmt) => fmt.hash_close(),
             OutputFormats::Linear(fmt) => fmt.hash_close(),
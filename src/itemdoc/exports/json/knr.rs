@@ -1,6 +1,9 @@
 use std::io::Write;
 use std::rc::Rc;
 
+use crate::itemdoc::core::errors::DocError;
+use crate::itemdoc::core::numbers::NumberValue;
+
 use super::super::core::*;
 use super::super::core::chardefs::*;
 use super::super::core::stringhelp::*;
@@ -26,17 +29,17 @@ impl JSONFormatKNR {
         self.context.get_outdent_vec()
     }
 
-    pub fn write_outdent(&mut self) -> Result<(), ExportError> {
+    pub fn write_outdent(&mut self) -> Result<(), DocError> {
         let outdent = self.get_outdent_vec().clone();
-        if outdent.len() > 0 {
-            self.writer.write_all(&outdent)?
+        if !outdent.is_empty() {
+            self.writer.write_all(&outdent)?;
         }
         Ok(())
     }
     
-    pub fn write_indent(&mut self) -> Result<(), ExportError> {
+    pub fn write_indent(&mut self) -> Result<(), DocError> {
         let indent = self.get_indent_vec().clone();
-        if indent.len() > 0 {
+        if !indent.is_empty() {
             self.writer.write_all(&indent)?;
         }
         Ok(())
@@ -45,158 +48,141 @@ impl JSONFormatKNR {
 
 impl ItemOutput for JSONFormatKNR {
 
-    fn list_open(&mut self) -> Result<usize, ExportError> { 
-        if self.context.is_top_level() {
-            self.writer.write_all(CHRB_ARR_OPEN_C)?;
-        } else {
-            if self.context.is_first() {
-                self.context.write_indent(self.writer.by_ref())?;
-            } else {
-                if  self.context.is_within_list() {
-                    self.writer.write_all(CHRB_COMMA_S)?;
-                }
-            }
-            let _ =self.writer.write_all(CHRB_ARR_OPEN_C)?;
-        }
+    fn list_open(&mut self) -> Result<usize, DocError> { 
+        self.list_begin_next()?;
+        self.writer.write_all(CHRB_ARR_OPEN_C)?;
         Ok(self.context.list_begin())
     }
-    fn list_begin_next(&mut self) -> Result<(), ExportError> { 
-        if !self.context.is_first() {
-            self.writer.write_all(CHRB_COMMA_S)?; 
+    fn list_begin_next(&mut self) -> Result<(), DocError> { 
+        if self.context.is_list() {
+            if !self.context.is_first() {
+                self.writer.write_all(CHRB_COMMA_C)?; 
+            }
+            self.writer.write_all(CHRB_CRLF)?; 
+            self.write_indent()?;
         }
-        self.writer.write_all(CHRB_CRLF)?; 
-        self.context.write_indent(self.writer.by_ref())?;
         Ok(())
     }
-    fn list_write_null(&mut self) -> Result<(), ExportError> { 
+    fn list_write_null(&mut self) -> Result<(), DocError> { 
         self.list_begin_next()?;
         self.writer.write_all(CHRB_NULL)?; 
         self.context.incr_item_count();
         Ok(())
     }
-    fn list_write_bool(&mut self, value: bool) -> Result<(), ExportError> { 
+    fn list_write_bool(&mut self, value: bool) -> Result<(), DocError> { 
         self.list_begin_next()?;
-        self.writer.write_all(value.to_string().as_bytes())?; 
+        let bool_vec = if value { CHRB_TRUE } else { CHRB_FALSE };
+        self.writer.write_all(bool_vec)?; 
         self.context.incr_item_count();
         Ok(())
     }
-    fn list_write_number(&mut self, value: f64) -> Result<(), ExportError> { 
+    fn list_write_number(&mut self, value: &NumberValue) -> Result<(), DocError> { 
         self.list_begin_next()?;
         self.writer.write_all(value.to_string().as_bytes())?; 
         self.context.incr_item_count();
         Ok(())
     }
-    fn list_write_string(&mut self, value: &String) -> Result<(), ExportError> { 
+    fn list_write_string(&mut self, value: &str) -> Result<(), DocError> { 
         self.list_begin_next()?;
         let escaped = make_quoted_escaped_string(value);
         self.writer.write_all(escaped.as_bytes())?; 
         self.context.incr_item_count();
         Ok(())
     }
-    fn list_write_empty_list(&mut self) -> Result<(), ExportError> { 
+    fn list_write_empty_list(&mut self) -> Result<(), DocError> { 
         self.list_begin_next()?;
-        self.writer.write_all(CHRB_ARR_EMPTY_S)?; 
+        self.writer.write_all(CHRB_ARR_EMPTY_C)?; 
         self.context.incr_item_count();
         Ok(())
     }
-    fn list_write_empty_hash(&mut self) -> Result<(), ExportError> { 
+    fn list_write_empty_hash(&mut self) -> Result<(), DocError> { 
         self.list_begin_next()?;
-        self.writer.write_all(CHRB_OBJ_EMPTY_S)?; 
+        self.writer.write_all(CHRB_OBJ_EMPTY_C)?; 
         self.context.incr_item_count();
         Ok(())
     }
-    fn list_close(&mut self) -> Result<usize, ExportError>  { 
-
-        let is_top_level = self.context.is_top_level();
+    fn list_close(&mut self) -> Result<usize, DocError>  { 
         let item_count = self.context.get_item_count();
-        let indent_vec = self.context.get_indent_vec().clone();
+        let outdent_vec = self.context.get_outdent_vec().clone();
 
         let result = self.context.list_end()?;
 
-        if item_count == 0 {
-            self.writer.write_all(CHRB_ARR_CLOSE_S)?;
-        } else if is_top_level {
-            self.writer.write_all(CHRB_ARR_CLOSE_C)?;            
-        } else {
+        if item_count > 0 {
             self.writer.write_all(CHRB_CRLF)?;
-            self.writer.write_all(&indent_vec)?;
-            self.writer.write_all(CHRB_ARR_CLOSE_S)?;    
-        }                
-
+            self.writer.write_all(&outdent_vec)?;
+        }
+        self.writer.write_all(CHRB_ARR_CLOSE_C)?;
         Ok(result)
     }
 
-    fn hash_open(&mut self) -> Result<usize, ExportError> {
-        if self.context.is_top_level() {
-            self.writer.write_all(CHRB_OBJ_OPEN_C)?;
-        } else {
-            if self.context.is_first() {
-                self.context.write_indent(self.writer.by_ref())?;
-            } else {
-                if  self.context.is_within_list() {
-                    self.writer.write_all(CHRB_COMMA_S)?;
-                }
-            }
-            let _ =self.writer.write_all(CHRB_OBJ_OPEN_C);
-        }
+    fn hash_open(&mut self) -> Result<usize, DocError> {
+        self.list_begin_next()?;
+        self.writer.write_all(CHRB_OBJ_OPEN_C)?;
         Ok(self.context.hash_begin())
     }
-    fn hash_begin_next(&mut self, key: &String) -> Result<(), ExportError> {
+    fn hash_begin_next(&mut self, key: &str) -> Result<(), DocError> {
+        self.hash_write_key(key)
+    }
+    fn hash_write_key(&mut self, key: &str) -> Result<(), DocError> { 
         if !self.context.is_first() {
-            self.writer.write_all(CHRB_COMMA_S)?; 
+            self.writer.write_all(CHRB_COMMA_C)?; 
         }
-        self.hash_write_key(key)?;
-        self.writer.write_all(CHRB_COLON_S)?;
-        Ok(())
-    }
-    fn hash_write_key(&mut self, key: &String) -> Result<(), ExportError> { 
-        let enclosed = make_quoted_string(key);
+        self.writer.write_all(CHRB_CRLF)?;
+        self.write_indent()?;
+        let enclosed = make_quoted_escaped_string(key);
         self.writer.write_all(enclosed.as_bytes())?;
-        self.writer.write_all(CHRB_COLON_C)?;
+        self.writer.write_all(CHRB_COLON_S)?;
         Ok(())
     }
-    fn hash_write_null(&mut self, key: &String) -> Result<(), ExportError> { 
+    fn hash_write_null(&mut self, key: &str) -> Result<(), DocError> { 
         self.hash_begin_next(key)?;
         self.writer.write_all(CHRB_NULL)?;
         self.context.incr_item_count();
         Ok(())
     }
-    fn hash_write_bool(&mut self, key: &String, value: bool) -> Result<(), ExportError> { 
+    fn hash_write_bool(&mut self, key: &str, value: bool) -> Result<(), DocError> { 
         self.hash_begin_next(key)?;
-        let bool_vec = if value { CHRB_TRUE } else {CHRB_FALSE };
-        self.writer.write_all(bool_vec )?;
+        let bool_vec = if value { CHRB_TRUE } else { CHRB_FALSE };
+        self.writer.write_all(bool_vec)?;
         self.context.incr_item_count();
         Ok(())
     }
-    fn hash_write_number(&mut self, key: &String, value: f64) -> Result<(), ExportError> { 
+    fn hash_write_number(&mut self, key: &str, value: &NumberValue) -> Result<(), DocError> { 
         self.hash_begin_next(key)?;
         self.writer.write_all(value.to_string().as_bytes())?;
         self.context.incr_item_count();
         Ok(())
     }
-    fn hash_write_string(&mut self, key: &String, value: &String) -> Result<(), ExportError> { 
+    fn hash_write_string(&mut self, key: &str, value: &str) -> Result<(), DocError> { 
         self.hash_begin_next(key)?;
         let escaped = make_quoted_escaped_string(value);
         self.writer.write_all(escaped.as_bytes())?; 
         self.context.incr_item_count();
         Ok(())
     }
-    fn hash_write_empty_list(&mut self, key: &String) -> Result<(), ExportError> {
+    fn hash_write_empty_list(&mut self, key: &str) -> Result<(), DocError> {
         self.hash_begin_next(key)?;
-        self.writer.write_all(CHRB_ARR_EMPTY_S)?;
+        self.writer.write_all(CHRB_ARR_EMPTY_C)?;
         self.context.incr_item_count();
         Ok(())
     }
-    fn hash_write_empty_hash(&mut self, key: &String) -> Result<(), ExportError> {
+    fn hash_write_empty_hash(&mut self, key: &str) -> Result<(), DocError> {
         self.hash_begin_next(key)?;
-        self.writer.write_all(CHRB_OBJ_EMPTY_S )?;
+        self.writer.write_all(CHRB_OBJ_EMPTY_C)?;
         self.context.incr_item_count();
         Ok(())
     }
-    fn hash_close(&mut self) -> Result<usize, ExportError> {
+    fn hash_close(&mut self) -> Result<usize, DocError> {
+        let item_count = self.context.get_item_count();
+        let outdent_vec = self.context.get_outdent_vec().clone();
+
         let result = self.context.hash_end()?;
-        self.writer.write_all(CHRB_OBJ_CLOSE_S )?;
-        self.context.incr_item_count();
+
+        if item_count > 0 {
+            self.writer.write_all(CHRB_CRLF)?;
+            self.writer.write_all(&outdent_vec)?;
+        }
+        self.writer.write_all(CHRB_OBJ_CLOSE_C)?;
         Ok(result)
     }
 
@@ -1,5 +1,8 @@
 use std::io::Write;
 
+use crate::itemdoc::core::errors::DocError;
+use crate::itemdoc::core::numbers::NumberValue;
+
 use super::super::core::*;
 use super::super::core::chardefs::*;
 use super::super::core::stringhelp::*;
@@ -20,121 +23,119 @@ impl JSONFormatLinear {
 
 impl ItemOutput for JSONFormatLinear {
 
-    fn list_open(&mut self) -> Result<usize, ExportError> {
+    fn list_open(&mut self) -> Result<usize, DocError> {
         self.list_begin_next()?;
         let level = self.context.list_begin();
-        self.writer.write_all(CHRB_ARR_OPEN_C)?;
+        self.writer.write_all(CHRB_ARR_OPEN_S)?;
         Ok(level)
-     }
-    fn list_begin_next(&mut self) -> Result<(), ExportError> { 
-        if !self.context.is_first() {
+    }
+    fn list_begin_next(&mut self) -> Result<(), DocError> { 
+        if self.context.is_list() && !self.context.is_first() {
             self.writer.write_all(CHRB_COMMA_S)?; 
         }
         Ok(())
     }
-    fn list_write_null(&mut self) -> Result<(), ExportError> { 
+    fn list_write_null(&mut self) -> Result<(), DocError> { 
         self.list_begin_next()?;
         self.writer.write_all(CHRB_NULL)?; 
         self.context.incr_item_count();
         Ok(())
     }
-    fn list_write_bool(&mut self, value: bool) -> Result<(), ExportError> { 
+    fn list_write_bool(&mut self, value: bool) -> Result<(), DocError> { 
         self.list_begin_next()?;
         self.writer.write_all(value.to_string().as_bytes())?; 
         self.context.incr_item_count();
         Ok(())
     }
-    fn list_write_number(&mut self, value: f64) -> Result<(), ExportError> { 
+    fn list_write_number(&mut self, value: &NumberValue) -> Result<(), DocError> { 
         self.list_begin_next()?;
         self.writer.write_all(value.to_string().as_bytes())?; 
         self.context.incr_item_count();
         Ok(())
     }
-    fn list_write_string(&mut self, value: &String) -> Result<(), ExportError> { 
+    fn list_write_string(&mut self, value: &str) -> Result<(), DocError> { 
         self.list_begin_next()?;
         let escaped = make_quoted_escaped_string(value);
         self.writer.write_all(escaped.as_bytes())?; 
         self.context.incr_item_count();
         Ok(())
     }
-    fn list_write_empty_list(&mut self) -> Result<(), ExportError> { 
+    fn list_write_empty_list(&mut self) -> Result<(), DocError> { 
         self.list_begin_next()?;
         self.writer.write_all(CHRB_ARR_EMPTY_S)?; 
         self.context.incr_item_count();
         Ok(())
     }
-    fn list_write_empty_hash(&mut self) -> Result<(), ExportError> { 
+    fn list_write_empty_hash(&mut self) -> Result<(), DocError> { 
         self.list_begin_next()?;
         self.writer.write_all(CHRB_OBJ_EMPTY_S)?; 
         self.context.incr_item_count();
         Ok(())
     }
-    fn list_close(&mut self) -> Result<usize, ExportError>  { 
+    fn list_close(&mut self) -> Result<usize, DocError>  { 
         let result = self.context.list_end()?;
         self.writer.write_all(CHRB_ARR_CLOSE_S)?; 
         Ok(result)
     }
 
-    fn hash_open(&mut self) -> Result<usize, ExportError> {
+    fn hash_open(&mut self) -> Result<usize, DocError> {
+        self.list_begin_next()?;
         let level = self.context.hash_begin();
         self.writer.write_all(CHRB_OBJ_OPEN_S)?;
         Ok(level)
     }
-    fn hash_begin_next(&mut self, key: &String) -> Result<(), ExportError> {
+    fn hash_begin_next(&mut self, key: &str) -> Result<(), DocError> {
+        self.hash_write_key(key)
+    }
+    fn hash_write_key(&mut self, key: &str) -> Result<(), DocError> { 
         if !self.context.is_first() {
-             self.writer.write_all(CHRB_COMMA_S)?; 
+            self.writer.write_all(CHRB_COMMA_S)?; 
         }
-        self.hash_write_key(key)?;
-        self.writer.write_all(CHRB_COLON_S)?;
-        Ok(())
-    }
-    fn hash_write_key(&mut self, key: &String) -> Result<(), ExportError> { 
-        let enclosed = make_quoted_string(key);
+        let enclosed = make_quoted_escaped_string(key);
         self.writer.write_all(enclosed.as_bytes())?;
-        self.writer.write_all(CHRB_COLON_C)?;
+        self.writer.write_all(CHRB_COLON_S)?;
         Ok(())
     }
-    fn hash_write_null(&mut self, key: &String) -> Result<(), ExportError> { 
+    fn hash_write_null(&mut self, key: &str) -> Result<(), DocError> { 
         self.hash_begin_next(key)?;
         self.writer.write_all(CHRB_NULL)?;
         self.context.incr_item_count();
         Ok(())
     }
-    fn hash_write_bool(&mut self, key: &String, value: bool) -> Result<(), ExportError> { 
+    fn hash_write_bool(&mut self, key: &str, value: bool) -> Result<(), DocError> { 
         self.hash_begin_next(key)?;
         self.writer.write_all(value.to_string().as_bytes())?;
         self.context.incr_item_count();
         Ok(())
     }
-    fn hash_write_number(&mut self, key: &String, value: f64) -> Result<(), ExportError> { 
+    fn hash_write_number(&mut self, key: &str, value: &NumberValue) -> Result<(), DocError> { 
         self.hash_begin_next(key)?;
         self.writer.write_all(value.to_string().as_bytes())?;
         self.context.incr_item_count();
         Ok(())
     }
-    fn hash_write_string(&mut self, key: &String, value: &String) -> Result<(), ExportError> { 
+    fn hash_write_string(&mut self, key: &str, value: &str) -> Result<(), DocError> { 
         self.hash_begin_next(key)?;
         let escaped = make_quoted_escaped_string(value);
         self.writer.write_all(escaped.as_bytes())?; 
         self.context.incr_item_count();
         Ok(())
     }
-    fn hash_write_empty_list(&mut self, key: &String) -> Result<(), ExportError> {
+    fn hash_write_empty_list(&mut self, key: &str) -> Result<(), DocError> {
         self.hash_begin_next(key)?;
         self.writer.write_all(CHRB_ARR_EMPTY_S)?;
         self.context.incr_item_count();
         Ok(())
     }
-    fn hash_write_empty_hash(&mut self, key: &String) -> Result<(), ExportError> {
+    fn hash_write_empty_hash(&mut self, key: &str) -> Result<(), DocError> {
         self.hash_begin_next(key)?;
-        self.writer.write_all(CHRB_OBJ_EMPTY_S )?;
+        self.writer.write_all(CHRB_OBJ_EMPTY_S)?;
         self.context.incr_item_count();
         Ok(())
     }
-    fn hash_close(&mut self) -> Result<usize, ExportError> {
+    fn hash_close(&mut self) -> Result<usize, DocError> {
         let result = self.context.hash_end()?;
-        self.writer.write_all(CHRB_OBJ_CLOSE_S )?;
-        self.context.incr_item_count();
+        self.writer.write_all(CHRB_OBJ_CLOSE_S)?;
         Ok(result)
     }
 
@@ -0,0 +1,69 @@
+use std::result::Result;
+
+use crate::itemdoc::core::items::*;
+use crate::itemdoc::core::lists::ItemList;
+use crate::itemdoc::core::hashes::ItemHash;
+
+use crate::itemdoc::core::errors::DocError;
+
+use super::core::ItemOutput;
+
+// Replays an item tree as ItemOutput events. Empty containers are written
+// with the *_write_empty_* calls, a nested container inside a hash is
+// preceded by hash_write_key, and a scalar root is written as a list
+// element at level zero. Errors carry the path of the member being written.
+pub fn export(item: &ItemType, output: &mut dyn ItemOutput) -> Result<(), DocError> {
+    export_list_item(item, output)
+}
+
+fn export_list(list: &ItemList, output: &mut dyn ItemOutput) -> Result<(), DocError> {
+    output.list_open()?;
+    for (key, item) in list.iter() {
+        export_list_item(item, output).map_err(|e| e.within(key))?;
+    }
+    output.list_close()?;
+    Ok(())
+}
+
+fn export_hash(hash: &ItemHash, output: &mut dyn ItemOutput) -> Result<(), DocError> {
+    output.hash_open()?;
+    for (key, item) in hash.iter() {
+        if let ContainerKey::Key(k) = key {
+            export_hash_item(k, item, output).map_err(|e| e.within(k))?;
+        }
+    }
+    output.hash_close()?;
+    Ok(())
+}
+
+fn export_list_item(item: &ItemType, output: &mut dyn ItemOutput) -> Result<(), DocError> {
+    match item {
+        ItemType::TNull(_) => output.list_write_null(),
+        ItemType::TBoolean(b) => output.list_write_bool(b.get_value()),
+        ItemType::TNumber(n) => output.list_write_number(n.get_number()),
+        ItemType::TString(s) => output.list_write_string(s.get_value()),
+        ItemType::TList(l) if l.count() == 0 => output.list_write_empty_list(),
+        ItemType::TList(l) => export_list(l, output),
+        ItemType::THash(h) if h.count() == 0 => output.list_write_empty_hash(),
+        ItemType::THash(h) => export_hash(h, output),
+    }
+}
+
+fn export_hash_item(key: &str, item: &ItemType, output: &mut dyn ItemOutput) -> Result<(), DocError> {
+    match item {
+        ItemType::TNull(_) => output.hash_write_null(key),
+        ItemType::TBoolean(b) => output.hash_write_bool(key, b.get_value()),
+        ItemType::TNumber(n) => output.hash_write_number(key, n.get_number()),
+        ItemType::TString(s) => output.hash_write_string(key, s.get_value()),
+        ItemType::TList(l) if l.count() == 0 => output.hash_write_empty_list(key),
+        ItemType::TList(l) => {
+            output.hash_write_key(key)?;
+            export_list(l, output)
+        },
+        ItemType::THash(h) if h.count() == 0 => output.hash_write_empty_hash(key),
+        ItemType::THash(h) => {
+            output.hash_write_key(key)?;
+            export_hash(h, output)
+        },
+    }
+}
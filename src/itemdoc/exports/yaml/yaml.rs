@@ -1,204 +1,239 @@
 use std::io::Write;
 use std::rc::Rc;
 
+use crate::itemdoc::core::errors::DocError;
+use crate::itemdoc::core::numbers::NumberValue;
+
 use super::super::core::*;
 use super::super::core::chardefs::*;
 use super::super::core::stringhelp::*;
 
 pub struct YAMLFormat {
     writer: Box<dyn Write>,
-    context: Box<StructuredExportContext>,
+    context: Box<SimpleExportContext>,
+    str_indent: Rc<[u8]>,
+    stk_indent: Vec<Rc<[u8]>>,
+    inline: bool,
 }
 
-impl YAMLFormat { // FIXME: Currently Not Ready for Prime Time
-
+impl YAMLFormat {
     pub fn new(output: Box<dyn Write>, indent: Option<String>) -> Self {
+        // tabs are not allowed as YAML indentation, so default to spaces
+        let indent_bytes = match indent {
+            Some(s) => Rc::from(s.into_bytes()),
+            None => Rc::from(CHRB_SPACES),
+        };
         Self {
-            context: Box::new(StructuredExportContext::new(indent)),
+            context: Box::new(SimpleExportContext::new()),
             writer: output,
+            str_indent: indent_bytes,
+            stk_indent: Vec::new(),
+            inline: true,
         }
     }
 
-    pub fn get_indent_vec(&self) -> &Rc<[u8]> {
-        self.context.get_indent_vec()
-    }
-
-    pub fn get_outdent_vec(&self) -> &Rc<[u8]> {
-        self.context.get_outdent_vec()
+    pub fn get_indent_vec(&self) -> Rc<[u8]> {
+        match self.stk_indent.last() {
+            Some(indent) => indent.clone(),
+            None => Rc::from(&b""[..]),
+        }
     }
 
-    pub fn write_outdent(&mut self) -> Result<(), ExportError> {
-        let outdent = self.get_outdent_vec().clone();
-        if outdent.len() > 0 {
-            self.writer.write_all(&outdent.clone())?;
+    pub fn write_indent(&mut self) -> Result<(), DocError> {
+        let indent = self.get_indent_vec();
+        if !indent.is_empty() {
+            self.writer.write_all(&indent)?;
         }
         Ok(())
     }
-    
-    pub fn write_indent(&mut self) -> Result<(), ExportError> {
-        let indent = self.get_indent_vec().clone();
-        if indent.len() > 0 {
-            self.writer.write_all(&indent.clone())?;
+
+    // Moves to the start of the next item, unless the cursor is still on
+    // the line of a "- " that introduced the enclosing container.
+    fn begin_item(&mut self) -> Result<(), DocError> {
+        if self.inline {
+            self.inline = false;
+        } else {
+            self.writer.write_all(CHRB_CRLF)?;
+            self.write_indent()?;
         }
         Ok(())
     }
-}
 
-impl ItemOutput for YAMLFormat {
+    fn push_indent(&mut self, extra: &[u8]) {
+        let mut indent = self.get_indent_vec().to_vec();
+        indent.extend_from_slice(extra);
+        self.stk_indent.push(Rc::from(indent));
+    }
 
-    fn list_open(&mut self) -> Result<usize, ExportError> { 
-        if self.context.is_top_level() {
-            self.writer.write_all(CHRB_ARR_OPEN_C)?;
+    fn open_container(&mut self) -> Result<(), DocError> {
+        if self.context.is_list() {
+            // compact notation: the first child shares the line of the dash
+            self.begin_item()?;
+            self.writer.write_all(CHRB_DASH_S)?;
+            self.push_indent(CHRB_SPACES);
+            self.inline = true;
+        } else if self.context.is_hash() {
+            let unit = self.str_indent.clone();
+            self.push_indent(&unit);
+            self.inline = false;
         } else {
-            if self.context.is_first() {
-                self.context.write_indent(self.writer.by_ref())?;
-            } else {
-                if  self.context.is_within_list() {
-                    self.writer.write_all(CHRB_COMMA_S)?;
-                }
-            }
-            let _ =self.writer.write_all(CHRB_ARR_OPEN_C)?;
-        }
-        Ok(self.context.list_begin())
-    }
-    fn list_begin_next(&mut self) -> Result<(), ExportError> { 
-        if !self.context.is_first() {
-            self.writer.write_all(CHRB_COMMA_S)?; 
+            self.stk_indent.push(Rc::from(&b""[..]));
         }
-        self.writer.write_all(CHRB_CRLF)?; 
-        self.context.write_indent(self.writer.by_ref())?;
         Ok(())
     }
-    fn list_write_null(&mut self) -> Result<(), ExportError> { 
-        self.list_begin_next()?;
-        self.writer.write_all(CHRB_NULL)?; 
+
+    fn write_list_value(&mut self, value: &[u8]) -> Result<(), DocError> {
+        if self.context.is_list() {
+            self.begin_item()?;
+            self.writer.write_all(CHRB_DASH_S)?;
+        }
+        self.writer.write_all(value)?;
         self.context.incr_item_count();
         Ok(())
     }
-    fn list_write_bool(&mut self, value: bool) -> Result<(), ExportError> { 
-        self.list_begin_next()?;
-        self.writer.write_all(value.to_string().as_bytes())?; 
+
+    fn write_hash_value(&mut self, key: &str, value: &[u8]) -> Result<(), DocError> {
+        self.hash_write_key(key)?;
+        self.writer.write_all(CHRB_SPACE)?;
+        self.writer.write_all(value)?;
         self.context.incr_item_count();
         Ok(())
     }
-    fn list_write_number(&mut self, value: f64) -> Result<(), ExportError> { 
-        self.list_begin_next()?;
-        self.writer.write_all(value.to_string().as_bytes())?; 
-        self.context.incr_item_count();
-        Ok(())
+}
+
+pub fn make_yaml_number(value: &NumberValue) -> String {
+    match value {
+        NumberValue::Float(f) if f.is_nan() => ".nan".to_string(),
+        NumberValue::Float(f) if f.is_infinite() => {
+            if *f > 0.0 { ".inf" } else { "-.inf" }.to_string()
+        },
+        _ => value.to_string(),
     }
-    fn list_write_string(&mut self, value: &String) -> Result<(), ExportError> { 
-        self.list_begin_next()?;
-        let escaped = make_quoted_escaped_string(value);
-        self.writer.write_all(escaped.as_bytes())?; 
-        self.context.incr_item_count();
-        Ok(())
+}
+
+// Writes a string as a plain scalar when it would read back as the same
+// string, and double quoted otherwise.
+pub fn make_yaml_string(value: &str) -> String {
+    if is_plain_safe(value) {
+        value.to_string()
+    } else {
+        make_quoted_escaped_string(value)
     }
-    fn list_write_empty_list(&mut self) -> Result<(), ExportError> { 
-        self.list_begin_next()?;
-        self.writer.write_all(CHRB_ARR_EMPTY_S)?; 
-        self.context.incr_item_count();
-        Ok(())
+}
+
+fn is_plain_safe(value: &str) -> bool {
+    let first = match value.chars().next() {
+        Some(c) => c,
+        None => return false,
+    };
+    if "-?:,[]{}#&*!|>'\"%@`".contains(first) || value.starts_with("...") {
+        return false;
     }
-    fn list_write_empty_hash(&mut self) -> Result<(), ExportError> { 
-        self.list_begin_next()?;
-        self.writer.write_all(CHRB_OBJ_EMPTY_S)?; 
-        self.context.incr_item_count();
-        Ok(())
+    if value.starts_with(' ') || value.ends_with(' ') || value.ends_with(':') {
+        return false;
     }
-    fn list_close(&mut self) -> Result<usize, ExportError> { 
-
-        let is_top_level = self.context.is_top_level();
-        let item_count = self.context.get_item_count();
-        let indent_vec = self.context.get_indent_vec().clone();
+    if value.contains(": ") || value.contains(" #") {
+        return false;
+    }
+    if value.chars().any(|c| c.is_control() || c == '\u{FEFF}') {
+        return false;
+    }
+    !resolves_to_non_string(value)
+}
 
-        let result = self.context.list_end()?;
+fn resolves_to_non_string(value: &str) -> bool {
+    match value {
+        "null" | "Null" | "NULL" | "~" => return true,
+        "true" | "True" | "TRUE" | "false" | "False" | "FALSE" => return true,
+        // YAML 1.1 booleans are still resolved by many readers
+        "y" | "Y" | "yes" | "Yes" | "YES" | "n" | "N" | "no" | "No" | "NO" => return true,
+        "on" | "On" | "ON" | "off" | "Off" | "OFF" => return true,
+        _ => {},
+    }
+    let unsigned = value.trim_start_matches(['-', '+']);
+    let lower = unsigned.to_ascii_lowercase();
+    if lower == ".inf" || lower == ".nan" || lower.starts_with("0x") || lower.starts_with("0o") {
+        return true;
+    }
+    unsigned.starts_with(|c: char| c.is_ascii_digit() || c == '.') && value.parse::<f64>().is_ok()
+}
 
-        if item_count == 0 {
-            self.writer.write_all(CHRB_ARR_CLOSE_S)?;
-        } else if is_top_level {
-            self.writer.write_all(CHRB_ARR_CLOSE_C)?;            
-        } else {
-            self.writer.write_all(CHRB_CRLF)?;
-            self.writer.write_all(&indent_vec)?;
-            self.writer.write_all(CHRB_ARR_CLOSE_S)?;    
-        }                
+impl ItemOutput for YAMLFormat {
 
-        Ok(result)
+    fn list_open(&mut self) -> Result<usize, DocError> {
+        self.open_container()?;
+        Ok(self.context.list_begin())
     }
-
-    fn hash_open(&mut self) -> Result<usize, ExportError> {
-        if self.context.is_top_level() {
-            self.writer.write_all(CHRB_OBJ_OPEN_C)?;
-        } else {
-            if self.context.is_first() {
-                self.context.write_indent(self.writer.by_ref())?;
-            } else {
-                if  self.context.is_within_list() {
-                    self.writer.write_all(CHRB_COMMA_S)?;
-                }
-            }
-            let _ =self.writer.write_all(CHRB_OBJ_OPEN_C)?;
+    fn list_begin_next(&mut self) -> Result<(), DocError> {
+        if self.context.is_list() {
+            self.begin_item()?;
+            self.writer.write_all(CHRB_DASH_S)?;
         }
+        Ok(())
+    }
+    fn list_write_null(&mut self) -> Result<(), DocError> {
+        self.write_list_value(CHRB_NULL)
+    }
+    fn list_write_bool(&mut self, value: bool) -> Result<(), DocError> {
+        let bool_vec = if value { CHRB_TRUE } else { CHRB_FALSE };
+        self.write_list_value(bool_vec)
+    }
+    fn list_write_number(&mut self, value: &NumberValue) -> Result<(), DocError> {
+        self.write_list_value(make_yaml_number(value).as_bytes())
+    }
+    fn list_write_string(&mut self, value: &str) -> Result<(), DocError> {
+        self.write_list_value(make_yaml_string(value).as_bytes())
+    }
+    fn list_write_empty_list(&mut self) -> Result<(), DocError> {
+        self.write_list_value(CHRB_ARR_EMPTY_C)
+    }
+    fn list_write_empty_hash(&mut self) -> Result<(), DocError> {
+        self.write_list_value(CHRB_OBJ_EMPTY_C)
+    }
+    fn list_close(&mut self) -> Result<usize, DocError> {
+        self.stk_indent.pop();
+        self.inline = false;
+        Ok(self.context.list_end()?)
+    }
+
+    fn hash_open(&mut self) -> Result<usize, DocError> {
+        self.open_container()?;
         Ok(self.context.hash_begin())
     }
-    fn hash_begin_next(&mut self, key: &String) -> Result<(), ExportError> {
-        if !self.context.is_first() {
-            self.writer.write_all(CHRB_COMMA_S)?; 
-        }
+    fn hash_begin_next(&mut self, key: &str) -> Result<(), DocError> {
         self.hash_write_key(key)?;
-        self.writer.write_all(CHRB_COLON_S)?;
+        self.writer.write_all(CHRB_SPACE)?;
         Ok(())
     }
-    fn hash_write_key(&mut self, key: &String) -> Result<(), ExportError> { 
-        let enclosed = make_quoted_string(key);
-        self.writer.write_all(enclosed.as_bytes())?;
+    fn hash_write_key(&mut self, key: &str) -> Result<(), DocError> {
+        self.begin_item()?;
+        self.writer.write_all(make_yaml_string(key).as_bytes())?;
         self.writer.write_all(CHRB_COLON_C)?;
         Ok(())
     }
-    fn hash_write_null(&mut self, key: &String) -> Result<(), ExportError> { 
-        self.hash_begin_next(key)?;
-        self.writer.write_all(CHRB_NULL)?;
-        self.context.incr_item_count();
-        Ok(())
+    fn hash_write_null(&mut self, key: &str) -> Result<(), DocError> {
+        self.write_hash_value(key, CHRB_NULL)
     }
-    fn hash_write_bool(&mut self, key: &String, value: bool) -> Result<(), ExportError> { 
-        self.hash_begin_next(key)?;
-        let bool_vec = if value { CHRB_TRUE } else {CHRB_FALSE };
-        self.writer.write_all(bool_vec )?;
-        self.context.incr_item_count();
-        Ok(())
+    fn hash_write_bool(&mut self, key: &str, value: bool) -> Result<(), DocError> {
+        let bool_vec = if value { CHRB_TRUE } else { CHRB_FALSE };
+        self.write_hash_value(key, bool_vec)
     }
-    fn hash_write_number(&mut self, key: &String, value: f64) -> Result<(), ExportError> { 
-        self.hash_begin_next(key)?;
-        self.writer.write_all(value.to_string().as_bytes())?;
-        self.context.incr_item_count();
-        Ok(())
+    fn hash_write_number(&mut self, key: &str, value: &NumberValue) -> Result<(), DocError> {
+        self.write_hash_value(key, make_yaml_number(value).as_bytes())
     }
-    fn hash_write_string(&mut self, key: &String, value: &String) -> Result<(), ExportError> { 
-        self.hash_begin_next(key)?;
-        let escaped = make_quoted_escaped_string(value);
-        self.writer.write_all(escaped.as_bytes())?; 
-        self.context.incr_item_count();
-        Ok(())
+    fn hash_write_string(&mut self, key: &str, value: &str) -> Result<(), DocError> {
+        self.write_hash_value(key, make_yaml_string(value).as_bytes())
     }
-    fn hash_write_empty_list(&mut self, key: &String) -> Result<(), ExportError> {
-        self.hash_begin_next(key)?;
-        self.writer.write_all(CHRB_ARR_EMPTY_S)?;
-        self.context.incr_item_count();
-        Ok(())
+    fn hash_write_empty_list(&mut self, key: &str) -> Result<(), DocError> {
+        self.write_hash_value(key, CHRB_ARR_EMPTY_C)
     }
-    fn hash_write_empty_hash(&mut self, key: &String) -> Result<(), ExportError> {
-        self.hash_begin_next(key)?;
-        self.writer.write_all(CHRB_OBJ_EMPTY_S )?;
-        self.context.incr_item_count();
-        Ok(())
+    fn hash_write_empty_hash(&mut self, key: &str) -> Result<(), DocError> {
+        self.write_hash_value(key, CHRB_OBJ_EMPTY_C)
     }
-    fn hash_close(&mut self) -> Result<usize, ExportError> {
-        let result = self.context.hash_end()?;
-        self.writer.write_all(CHRB_OBJ_CLOSE_S )?;
-        self.context.incr_item_count();
-        Ok(result)
+    fn hash_close(&mut self) -> Result<usize, DocError> {
+        self.stk_indent.pop();
+        self.inline = false;
+        Ok(self.context.hash_end()?)
     }
 
 }
@@ -0,0 +1,148 @@
+use std::result::Result;
+use std::io::{BufReader, Bytes, Read};
+use std::fmt;
+
+use crate::itemdoc::core::errors::DocError;
+
+#[derive(Debug)]
+#[derive(Copy, Clone, PartialEq)]
+pub enum ParseErrorKind {
+    UnexpectedEndOfInput,
+    UnexpectedCharacter(char),
+    InvalidNumber,
+    NumberOutOfRange,
+    InvalidEscape,
+    InvalidUnicode,
+    InvalidUtf8,
+    ControlCharacter,
+    TrailingCharacters,
+    DepthLimitExceeded,
+    InvalidIndentation,
+    UnsupportedFeature,
+}
+
+#[derive(Debug)]
+#[derive(Copy, Clone, PartialEq)]
+pub struct ParseError {
+    pub kind: ParseErrorKind,
+    pub offset: usize,
+    pub line: usize,
+    pub column: usize,
+}
+
+impl fmt::Display for ParseErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ParseErrorKind::UnexpectedEndOfInput => write!(f, "Unexpected end of input!"),
+            ParseErrorKind::UnexpectedCharacter(c) => write!(f, "Unexpected character {:?}!", c),
+            ParseErrorKind::InvalidNumber => write!(f, "Invalid number!"),
+            ParseErrorKind::NumberOutOfRange => write!(f, "Number out of range!"),
+            ParseErrorKind::InvalidEscape => write!(f, "Invalid escape sequence!"),
+            ParseErrorKind::InvalidUnicode => write!(f, "Invalid unicode escape!"),
+            ParseErrorKind::InvalidUtf8 => write!(f, "Invalid UTF-8 sequence!"),
+            ParseErrorKind::ControlCharacter => write!(f, "Unescaped control character!"),
+            ParseErrorKind::TrailingCharacters => write!(f, "Trailing characters after document!"),
+            ParseErrorKind::DepthLimitExceeded => write!(f, "Nesting depth limit exceeded!"),
+            ParseErrorKind::InvalidIndentation => write!(f, "Invalid indentation!"),
+            ParseErrorKind::UnsupportedFeature => write!(f, "Unsupported feature!"),
+        }
+    }
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} (line {}, column {}, offset {})", self.kind, self.line, self.column, self.offset)
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+#[derive(Debug)]
+#[derive(Copy, Clone, PartialEq)]
+pub struct SourcePosition {
+    pub offset: usize,
+    pub line: usize,
+    pub column: usize,
+}
+
+impl Default for SourcePosition {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl SourcePosition {
+    pub fn new() -> Self {
+        Self { offset: 0, line: 1, column: 1 }
+    }
+
+    pub fn error(&self, kind: ParseErrorKind) -> ParseError {
+        ParseError { kind, offset: self.offset, line: self.line, column: self.column }
+    }
+
+    // Locates a byte offset within text that is already in memory.
+    pub fn from_offset(text: &str, offset: usize) -> Self {
+        let before = &text.as_bytes()[..offset];
+        let line_start = before.iter().rposition(|b| *b == b'\n').map_or(0, |i| i + 1);
+        let column = before[line_start..].iter().filter(|b| **b & 0xC0 != 0x80).count() + 1;
+        let line = before.iter().filter(|b| **b == b'\n').count() + 1;
+        Self { offset, line, column }
+    }
+}
+
+pub struct ByteReader<R: Read> {
+    bytes: Bytes<BufReader<R>>,
+    peeked: Option<u8>,
+    position: SourcePosition,
+}
+
+impl<R: Read> ByteReader<R> {
+    pub fn new(reader: R) -> Self {
+        Self {
+            bytes: BufReader::new(reader).bytes(),
+            peeked: None,
+            position: SourcePosition::new(),
+        }
+    }
+
+    pub fn position(&self) -> SourcePosition {
+        self.position
+    }
+
+    pub fn error(&self, kind: ParseErrorKind) -> DocError {
+        DocError::from(self.position.error(kind))
+    }
+
+    pub fn peek(&mut self) -> Result<Option<u8>, DocError> {
+        if self.peeked.is_none() {
+            self.peeked = match self.bytes.next() {
+                Some(b) => Some(b?),
+                None => None,
+            };
+        }
+        Ok(self.peeked)
+    }
+
+    pub fn next_byte(&mut self) -> Result<Option<u8>, DocError> {
+        let result = self.peek()?;
+        self.peeked = None;
+        if let Some(b) = result {
+            self.position.offset += 1;
+            if b == b'\n' {
+                self.position.line += 1;
+                self.position.column = 1;
+            } else if b & 0xC0 != 0x80 {
+                // continuation bytes belong to the character already counted
+                self.position.column += 1;
+            }
+        }
+        Ok(result)
+    }
+
+    pub fn expect_byte(&mut self) -> Result<u8, DocError> {
+        match self.next_byte()? {
+            Some(b) => Ok(b),
+            None => Err(self.error(ParseErrorKind::UnexpectedEndOfInput)),
+        }
+    }
+}
@@ -0,0 +1,256 @@
+use std::result::Result;
+use std::io::Read;
+
+use crate::itemdoc::core::items::ItemType;
+use crate::itemdoc::core::nulls::ItemNull;
+use crate::itemdoc::core::booleans::ItemBoolean;
+use crate::itemdoc::core::numbers::{ItemNumber, NumberValue};
+use crate::itemdoc::core::strings::ItemString;
+use crate::itemdoc::core::lists::ItemList;
+use crate::itemdoc::core::hashes::ItemHash;
+use crate::itemdoc::core::errors::DocError;
+
+use super::core::*;
+
+pub const MAX_DEPTH: usize = 512;
+
+pub fn parse_str(input: &str) -> Result<ItemType, DocError> {
+    parse_reader(input.as_bytes())
+}
+
+pub fn parse_reader<R: Read>(reader: R) -> Result<ItemType, DocError> {
+    let mut source = ByteReader::new(reader);
+    skip_whitespace(&mut source)?;
+    let item = parse_value(&mut source, 0)?;
+    skip_whitespace(&mut source)?;
+    if source.peek()?.is_some() {
+        return Err(source.error(ParseErrorKind::TrailingCharacters));
+    }
+    Ok(item)
+}
+
+fn parse_value<R: Read>(source: &mut ByteReader<R>, depth: usize) -> Result<ItemType, DocError> {
+    match source.peek()? {
+        Some(b'[') => parse_list(source, depth + 1),
+        Some(b'{') => parse_hash(source, depth + 1),
+        Some(b'"') => Ok(ItemString::new(Some(read_string(source)?))),
+        Some(b'n') => { read_literal(source, b"null")?; Ok(ItemNull::new()) },
+        Some(b't') => { read_literal(source, b"true")?; Ok(ItemBoolean::new(Some(true))) },
+        Some(b'f') => { read_literal(source, b"false")?; Ok(ItemBoolean::new(Some(false))) },
+        Some(b'-' | b'0'..=b'9') => Ok(ItemNumber::with_value(read_number(source)?)),
+        Some(b) => Err(source.error(ParseErrorKind::UnexpectedCharacter(byte_char(b)))),
+        None => Err(source.error(ParseErrorKind::UnexpectedEndOfInput)),
+    }
+}
+
+fn parse_list<R: Read>(source: &mut ByteReader<R>, depth: usize) -> Result<ItemType, DocError> {
+    if depth > MAX_DEPTH {
+        return Err(source.error(ParseErrorKind::DepthLimitExceeded));
+    }
+    source.next_byte()?;
+    let mut list = ItemList::new();
+    skip_whitespace(source)?;
+    if source.peek()? == Some(b']') {
+        source.next_byte()?;
+        return Ok(list);
+    }
+    loop {
+        skip_whitespace(source)?;
+        let item = parse_value(source, depth).map_err(|e| e.within(list.count()))?;
+        list.add_item(item, None)?;
+        skip_whitespace(source)?;
+        match source.peek()? {
+            Some(b',') => { source.next_byte()?; },
+            Some(b']') => { source.next_byte()?; return Ok(list); },
+            Some(b) => return Err(source.error(ParseErrorKind::UnexpectedCharacter(byte_char(b)))),
+            None => return Err(source.error(ParseErrorKind::UnexpectedEndOfInput)),
+        }
+    }
+}
+
+fn parse_hash<R: Read>(source: &mut ByteReader<R>, depth: usize) -> Result<ItemType, DocError> {
+    if depth > MAX_DEPTH {
+        return Err(source.error(ParseErrorKind::DepthLimitExceeded));
+    }
+    source.next_byte()?;
+    let mut hash = ItemHash::new();
+    skip_whitespace(source)?;
+    if source.peek()? == Some(b'}') {
+        source.next_byte()?;
+        return Ok(hash);
+    }
+    loop {
+        skip_whitespace(source)?;
+        let key = read_key(source)?;
+        skip_whitespace(source)?;
+        let item = parse_value(source, depth).map_err(|e| e.within(key.as_str()))?;
+        hash.add_item(item, Some(&key))?;
+        skip_whitespace(source)?;
+        match source.peek()? {
+            Some(b',') => { source.next_byte()?; },
+            Some(b'}') => { source.next_byte()?; return Ok(hash); },
+            Some(b) => return Err(source.error(ParseErrorKind::UnexpectedCharacter(byte_char(b)))),
+            None => return Err(source.error(ParseErrorKind::UnexpectedEndOfInput)),
+        }
+    }
+}
+
+// Reads a member name and the colon that follows it.
+pub(crate) fn read_key<R: Read>(source: &mut ByteReader<R>) -> Result<String, DocError> {
+    match source.peek()? {
+        Some(b'"') => {},
+        Some(b) => return Err(source.error(ParseErrorKind::UnexpectedCharacter(byte_char(b)))),
+        None => return Err(source.error(ParseErrorKind::UnexpectedEndOfInput)),
+    }
+    let key = read_string(source)?;
+    skip_whitespace(source)?;
+    match source.peek()? {
+        Some(b':') => { source.next_byte()?; },
+        Some(b) => return Err(source.error(ParseErrorKind::UnexpectedCharacter(byte_char(b)))),
+        None => return Err(source.error(ParseErrorKind::UnexpectedEndOfInput)),
+    }
+    Ok(key)
+}
+
+pub(crate) fn skip_whitespace<R: Read>(source: &mut ByteReader<R>) -> Result<(), DocError> {
+    while let Some(b' ' | b'\t' | b'\n' | b'\r') = source.peek()? {
+        source.next_byte()?;
+    }
+    Ok(())
+}
+
+pub(crate) fn read_literal<R: Read>(source: &mut ByteReader<R>, literal: &[u8]) -> Result<(), DocError> {
+    for expected in literal {
+        match source.peek()? {
+            Some(b) if b == *expected => { source.next_byte()?; },
+            Some(b) => return Err(source.error(ParseErrorKind::UnexpectedCharacter(byte_char(b)))),
+            None => return Err(source.error(ParseErrorKind::UnexpectedEndOfInput)),
+        }
+    }
+    Ok(())
+}
+
+pub(crate) fn read_number<R: Read>(source: &mut ByteReader<R>) -> Result<NumberValue, DocError> {
+    let start = source.position();
+    let mut text = String::new();
+
+    if source.peek()? == Some(b'-') {
+        text.push('-');
+        source.next_byte()?;
+    }
+    match source.peek()? {
+        Some(b'0') => {
+            text.push('0');
+            source.next_byte()?;
+        },
+        Some(b'1'..=b'9') => read_digits(source, &mut text)?,
+        _ => return Err(DocError::from(start.error(ParseErrorKind::InvalidNumber))),
+    }
+    if source.peek()? == Some(b'.') {
+        text.push('.');
+        source.next_byte()?;
+        if !matches!(source.peek()?, Some(b'0'..=b'9')) {
+            return Err(DocError::from(start.error(ParseErrorKind::InvalidNumber)));
+        }
+        read_digits(source, &mut text)?;
+    }
+    if let Some(b'e' | b'E') = source.peek()? {
+        text.push('e');
+        source.next_byte()?;
+        if let Some(sign @ (b'+' | b'-')) = source.peek()? {
+            text.push(sign as char);
+            source.next_byte()?;
+        }
+        if !matches!(source.peek()?, Some(b'0'..=b'9')) {
+            return Err(DocError::from(start.error(ParseErrorKind::InvalidNumber)));
+        }
+        read_digits(source, &mut text)?;
+    }
+
+    // The syntax is valid by now, so the only way left to fail is a power of
+    // ten too large to keep.
+    NumberValue::parse(&text)
+        .ok_or(DocError::from(start.error(ParseErrorKind::NumberOutOfRange)))
+}
+
+fn read_digits<R: Read>(source: &mut ByteReader<R>, text: &mut String) -> Result<(), DocError> {
+    while let Some(b @ b'0'..=b'9') = source.peek()? {
+        text.push(b as char);
+        source.next_byte()?;
+    }
+    Ok(())
+}
+
+pub(crate) fn read_string<R: Read>(source: &mut ByteReader<R>) -> Result<String, DocError> {
+    let start = source.position();
+    source.next_byte()?;
+    let mut bytes: Vec<u8> = Vec::new();
+
+    loop {
+        let position = source.position();
+        match source.expect_byte()? {
+            b'"' => break,
+            b'\\' => {
+                let escape = source.position();
+                let c = match source.expect_byte()? {
+                    b'"' => '"',
+                    b'\\' => '\\',
+                    b'/' => '/',
+                    b'b' => '\u{08}',
+                    b'f' => '\u{0C}',
+                    b'n' => '\n',
+                    b'r' => '\r',
+                    b't' => '\t',
+                    b'u' => read_unicode_escape(source, escape)?,
+                    _ => return Err(DocError::from(escape.error(ParseErrorKind::InvalidEscape))),
+                };
+                let mut buffer = [0u8; 4];
+                bytes.extend_from_slice(c.encode_utf8(&mut buffer).as_bytes());
+            },
+            b if b < 0x20 => return Err(DocError::from(position.error(ParseErrorKind::ControlCharacter))),
+            b => bytes.push(b),
+        }
+    }
+
+    String::from_utf8(bytes)
+        .map_err(|_| DocError::from(start.error(ParseErrorKind::InvalidUtf8)))
+}
+
+fn read_hex4<R: Read>(source: &mut ByteReader<R>, escape: SourcePosition) -> Result<u32, DocError> {
+    let mut value = 0u32;
+    for _ in 0..4 {
+        let digit = match source.expect_byte()? {
+            b @ b'0'..=b'9' => b - b'0',
+            b @ b'a'..=b'f' => b - b'a' + 10,
+            b @ b'A'..=b'F' => b - b'A' + 10,
+            _ => return Err(DocError::from(escape.error(ParseErrorKind::InvalidUnicode))),
+        };
+        value = (value << 4) | digit as u32;
+    }
+    Ok(value)
+}
+
+fn read_unicode_escape<R: Read>(source: &mut ByteReader<R>, escape: SourcePosition) -> Result<char, DocError> {
+    let invalid = || DocError::from(escape.error(ParseErrorKind::InvalidUnicode));
+    let high = read_hex4(source, escape)?;
+    let code = match high {
+        0xD800..=0xDBFF => {
+            // a high surrogate must be followed by an escaped low surrogate
+            if source.expect_byte()? != b'\\' || source.expect_byte()? != b'u' {
+                return Err(invalid());
+            }
+            let low = read_hex4(source, escape)?;
+            if !(0xDC00..=0xDFFF).contains(&low) {
+                return Err(invalid());
+            }
+            0x10000 + ((high - 0xD800) << 10) + (low - 0xDC00)
+        },
+        0xDC00..=0xDFFF => return Err(invalid()),
+        _ => high,
+    };
+    char::from_u32(code).ok_or_else(invalid)
+}
+
+pub(crate) fn byte_char(b: u8) -> char {
+    if b.is_ascii() { b as char } else { char::REPLACEMENT_CHARACTER }
+}
@@ -0,0 +1,209 @@
+use std::result::Result;
+use std::io::Read;
+
+use crate::itemdoc::core::numbers::NumberValue;
+use crate::itemdoc::exports::core::ItemOutput;
+use crate::itemdoc::core::errors::DocError;
+
+use super::core::*;
+use super::json::{MAX_DEPTH, byte_char, read_key, read_literal, read_number, read_string, skip_whitespace};
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum JsonEvent {
+    ListOpen,
+    ListClose,
+    HashOpen,
+    HashClose,
+    Key(String),
+    Null,
+    Bool(bool),
+    Number(NumberValue),
+    String(String),
+    EmptyList,
+    EmptyHash,
+}
+
+#[derive(PartialEq)]
+enum Container {
+    List,
+    Hash,
+}
+
+#[derive(PartialEq)]
+enum Expect {
+    Value,
+    Key,
+    CommaOrClose,
+    End,
+    Finished,
+}
+
+pub struct JsonEventReader<R: Read> {
+    source: ByteReader<R>,
+    stack: Vec<Container>,
+    expect: Expect,
+}
+
+impl<R: Read> JsonEventReader<R> {
+    pub fn new(reader: R) -> Self {
+        Self {
+            source: ByteReader::new(reader),
+            stack: Vec::new(),
+            expect: Expect::Value,
+        }
+    }
+
+    pub fn position(&self) -> SourcePosition {
+        self.source.position()
+    }
+
+    pub fn get_depth(&self) -> usize {
+        self.stack.len()
+    }
+
+    pub fn next_event(&mut self) -> Result<Option<JsonEvent>, DocError> {
+        let result = self.read_event();
+        if result.is_err() {
+            self.expect = Expect::Finished;
+        }
+        result
+    }
+
+    fn read_event(&mut self) -> Result<Option<JsonEvent>, DocError> {
+        loop {
+            skip_whitespace(&mut self.source)?;
+            match self.expect {
+                Expect::Value => return self.read_value().map(Some),
+                Expect::Key => {
+                    let key = read_key(&mut self.source)?;
+                    self.expect = Expect::Value;
+                    return Ok(Some(JsonEvent::Key(key)));
+                },
+                Expect::CommaOrClose => {
+                    let top = self.stack.last();
+                    match self.source.peek()? {
+                        Some(b',') => {
+                            self.source.next_byte()?;
+                            self.expect = match top {
+                                Some(Container::Hash) => Expect::Key,
+                                _ => Expect::Value,
+                            };
+                        },
+                        Some(b']') if top == Some(&Container::List) => {
+                            self.source.next_byte()?;
+                            self.stack.pop();
+                            self.after_value();
+                            return Ok(Some(JsonEvent::ListClose));
+                        },
+                        Some(b'}') if top == Some(&Container::Hash) => {
+                            self.source.next_byte()?;
+                            self.stack.pop();
+                            self.after_value();
+                            return Ok(Some(JsonEvent::HashClose));
+                        },
+                        Some(b) => return Err(self.source.error(ParseErrorKind::UnexpectedCharacter(byte_char(b)))),
+                        None => return Err(self.source.error(ParseErrorKind::UnexpectedEndOfInput)),
+                    }
+                },
+                Expect::End => {
+                    if self.source.peek()?.is_some() {
+                        return Err(self.source.error(ParseErrorKind::TrailingCharacters));
+                    }
+                    self.expect = Expect::Finished;
+                    return Ok(None);
+                },
+                Expect::Finished => return Ok(None),
+            }
+        }
+    }
+
+    fn read_value(&mut self) -> Result<JsonEvent, DocError> {
+        let event = match self.source.peek()? {
+            Some(b'[') => return self.read_open(Container::List),
+            Some(b'{') => return self.read_open(Container::Hash),
+            Some(b'"') => JsonEvent::String(read_string(&mut self.source)?),
+            Some(b'n') => { read_literal(&mut self.source, b"null")?; JsonEvent::Null },
+            Some(b't') => { read_literal(&mut self.source, b"true")?; JsonEvent::Bool(true) },
+            Some(b'f') => { read_literal(&mut self.source, b"false")?; JsonEvent::Bool(false) },
+            Some(b'-' | b'0'..=b'9') => JsonEvent::Number(read_number(&mut self.source)?),
+            Some(b) => return Err(self.source.error(ParseErrorKind::UnexpectedCharacter(byte_char(b)))),
+            None => return Err(self.source.error(ParseErrorKind::UnexpectedEndOfInput)),
+        };
+        self.after_value();
+        Ok(event)
+    }
+
+    fn read_open(&mut self, container: Container) -> Result<JsonEvent, DocError> {
+        if self.stack.len() >= MAX_DEPTH {
+            return Err(self.source.error(ParseErrorKind::DepthLimitExceeded));
+        }
+        self.source.next_byte()?;
+        skip_whitespace(&mut self.source)?;
+
+        // an empty container is reported as a single event, the same way
+        // the exporters expect it
+        let close = if container == Container::List { b']' } else { b'}' };
+        if self.source.peek()? == Some(close) {
+            self.source.next_byte()?;
+            self.after_value();
+            return Ok(match container {
+                Container::List => JsonEvent::EmptyList,
+                Container::Hash => JsonEvent::EmptyHash,
+            });
+        }
+
+        let event = match container {
+            Container::List => { self.expect = Expect::Value; JsonEvent::ListOpen },
+            Container::Hash => { self.expect = Expect::Key; JsonEvent::HashOpen },
+        };
+        self.stack.push(container);
+        Ok(event)
+    }
+
+    fn after_value(&mut self) {
+        self.expect = if self.stack.is_empty() { Expect::End } else { Expect::CommaOrClose };
+    }
+}
+
+impl<R: Read> Iterator for JsonEventReader<R> {
+    type Item = Result<JsonEvent, DocError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.next_event().transpose()
+    }
+}
+
+pub fn pipe_str(input: &str, output: &mut dyn ItemOutput) -> Result<(), DocError> {
+    pipe_reader(input.as_bytes(), output)
+}
+
+// Streams a JSON document into an ItemOutput without building an ItemType.
+pub fn pipe_reader<R: Read>(reader: R, output: &mut dyn ItemOutput) -> Result<(), DocError> {
+    let mut events = JsonEventReader::new(reader);
+    let mut key: Option<String> = None;
+
+    while let Some(event) = events.next_event()? {
+        match (event, key.take()) {
+            (JsonEvent::Key(k), _) => key = Some(k),
+            (JsonEvent::ListOpen, Some(k)) => { output.hash_write_key(&k)?; output.list_open()?; },
+            (JsonEvent::ListOpen, None) => { output.list_open()?; },
+            (JsonEvent::HashOpen, Some(k)) => { output.hash_write_key(&k)?; output.hash_open()?; },
+            (JsonEvent::HashOpen, None) => { output.hash_open()?; },
+            (JsonEvent::ListClose, _) => { output.list_close()?; },
+            (JsonEvent::HashClose, _) => { output.hash_close()?; },
+            (JsonEvent::Null, Some(k)) => output.hash_write_null(&k)?,
+            (JsonEvent::Null, None) => output.list_write_null()?,
+            (JsonEvent::Bool(b), Some(k)) => output.hash_write_bool(&k, b)?,
+            (JsonEvent::Bool(b), None) => output.list_write_bool(b)?,
+            (JsonEvent::Number(n), Some(k)) => output.hash_write_number(&k, &n)?,
+            (JsonEvent::Number(n), None) => output.list_write_number(&n)?,
+            (JsonEvent::String(s), Some(k)) => output.hash_write_string(&k, &s)?,
+            (JsonEvent::String(s), None) => output.list_write_string(&s)?,
+            (JsonEvent::EmptyList, Some(k)) => output.hash_write_empty_list(&k)?,
+            (JsonEvent::EmptyList, None) => output.list_write_empty_list()?,
+            (JsonEvent::EmptyHash, Some(k)) => output.hash_write_empty_hash(&k)?,
+            (JsonEvent::EmptyHash, None) => output.list_write_empty_hash()?,
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,799 @@
+use std::result::Result;
+use std::io::Read;
+
+use crate::itemdoc::core::items::ItemType;
+use crate::itemdoc::core::nulls::ItemNull;
+use crate::itemdoc::core::booleans::ItemBoolean;
+use crate::itemdoc::core::numbers::{ItemNumber, NumberValue};
+use crate::itemdoc::core::strings::ItemString;
+use crate::itemdoc::core::lists::ItemList;
+use crate::itemdoc::core::hashes::ItemHash;
+use crate::itemdoc::core::errors::DocError;
+
+use super::core::*;
+use super::json::MAX_DEPTH;
+
+pub fn parse_str(input: &str) -> Result<ItemType, DocError> {
+    YamlParser::new(input).parse_document()
+}
+
+pub fn parse_reader<R: Read>(mut reader: R) -> Result<ItemType, DocError> {
+    let mut bytes = Vec::new();
+    reader.read_to_end(&mut bytes)?;
+    match String::from_utf8(bytes) {
+        Ok(text) => parse_str(&text),
+        Err(err) => {
+            let valid = err.utf8_error().valid_up_to();
+            let text = std::str::from_utf8(&err.as_bytes()[..valid]).unwrap_or_default();
+            Err(DocError::from(SourcePosition::from_offset(text, valid).error(ParseErrorKind::InvalidUtf8)))
+        },
+    }
+}
+
+// Resolves a plain scalar with the YAML 1.2 core schema.
+pub fn resolve_plain_scalar(text: &str) -> ItemType {
+    match text {
+        "" | "~" | "null" | "Null" | "NULL" => return ItemNull::new(),
+        "true" | "True" | "TRUE" => return ItemBoolean::new(Some(true)),
+        "false" | "False" | "FALSE" => return ItemBoolean::new(Some(false)),
+        ".nan" | ".NaN" | ".NAN" => return ItemNumber::new(Some(f64::NAN)),
+        _ => {},
+    }
+    let (negative, unsigned) = match text.as_bytes()[0] {
+        b'-' => (true, &text[1..]),
+        b'+' => (false, &text[1..]),
+        _ => (false, text),
+    };
+    let sign = if negative { -1.0 } else { 1.0 };
+    match unsigned {
+        ".inf" | ".Inf" | ".INF" => return ItemNumber::new(Some(sign * f64::INFINITY)),
+        _ => {},
+    }
+    if unsigned.len() == text.len() {
+        let radix = if let Some(digits) = text.strip_prefix("0o") {
+            u64::from_str_radix(digits, 8).ok()
+        } else if let Some(digits) = text.strip_prefix("0x") {
+            u64::from_str_radix(digits, 16).ok()
+        } else {
+            None
+        };
+        if let Some(n) = radix {
+            return ItemNumber::with_value(NumberValue::UInt(n));
+        }
+    }
+    if is_core_number(unsigned) {
+        if let Some(n) = NumberValue::parse(text) {
+            return ItemNumber::with_value(n);
+        }
+    }
+    ItemString::new(Some(text.to_string()))
+}
+
+// [0-9]+ | (\.[0-9]+ | [0-9]+(\.[0-9]*)?)([eE][-+]?[0-9]+)?
+fn is_core_number(text: &str) -> bool {
+    let bytes = text.as_bytes();
+    let mut i = 0;
+    let digits = |i: &mut usize| {
+        let start = *i;
+        while *i < bytes.len() && bytes[*i].is_ascii_digit() {
+            *i += 1;
+        }
+        *i - start
+    };
+    let whole = digits(&mut i);
+    let mut fraction = 0;
+    if i < bytes.len() && bytes[i] == b'.' {
+        i += 1;
+        fraction = digits(&mut i);
+    }
+    if whole == 0 && fraction == 0 {
+        return false;
+    }
+    if i < bytes.len() && (bytes[i] == b'e' || bytes[i] == b'E') {
+        i += 1;
+        if i < bytes.len() && (bytes[i] == b'+' || bytes[i] == b'-') {
+            i += 1;
+        }
+        if digits(&mut i) == 0 {
+            return false;
+        }
+    }
+    i == bytes.len()
+}
+
+fn is_blank(b: Option<u8>) -> bool {
+    matches!(b, None | Some(b' ' | b'\t' | b'\r' | b'\n'))
+}
+
+fn is_flow_indicator(b: Option<u8>) -> bool {
+    matches!(b, Some(b',' | b'[' | b']' | b'{' | b'}'))
+}
+
+struct YamlParser<'a> {
+    text: &'a str,
+    bytes: &'a [u8],
+    pos: usize,
+    depth: usize,
+}
+
+impl<'a> YamlParser<'a> {
+    fn new(text: &'a str) -> Self {
+        Self { text, bytes: text.as_bytes(), pos: 0, depth: 0 }
+    }
+
+    fn error_at(&self, offset: usize, kind: ParseErrorKind) -> DocError {
+        DocError::from(SourcePosition::from_offset(self.text, offset).error(kind))
+    }
+
+    fn error(&self, kind: ParseErrorKind) -> DocError {
+        self.error_at(self.pos, kind)
+    }
+
+    fn unexpected(&self) -> DocError {
+        match self.text[self.pos..].chars().next() {
+            Some(c) => self.error(ParseErrorKind::UnexpectedCharacter(c)),
+            None => self.error(ParseErrorKind::UnexpectedEndOfInput),
+        }
+    }
+
+    fn peek(&self) -> Option<u8> {
+        self.bytes.get(self.pos).copied()
+    }
+
+    fn peek_at(&self, ahead: usize) -> Option<u8> {
+        self.bytes.get(self.pos + ahead).copied()
+    }
+
+    fn line_start(&self, pos: usize) -> usize {
+        self.bytes[..pos].iter().rposition(|b| *b == b'\n').map_or(0, |i| i + 1)
+    }
+
+    fn column(&self) -> usize {
+        self.pos - self.line_start(self.pos)
+    }
+
+    fn at_line_end(&self) -> bool {
+        matches!(self.peek(), None | Some(b'\n' | b'\r'))
+    }
+
+    fn at_document_marker(&self) -> bool {
+        self.column() == 0
+            && (self.bytes[self.pos..].starts_with(b"---") || self.bytes[self.pos..].starts_with(b"..."))
+            && is_blank(self.peek_at(3))
+    }
+
+    fn at_mapping_indicator(&self) -> bool {
+        self.peek() == Some(b':') && is_blank(self.peek_at(1))
+    }
+
+    fn at_sequence_entry(&self) -> bool {
+        self.peek() == Some(b'-') && is_blank(self.peek_at(1))
+    }
+
+    fn skip_spaces(&mut self) {
+        while let Some(b' ' | b'\t') = self.peek() {
+            self.pos += 1;
+        }
+    }
+
+    fn skip_line_break(&mut self) {
+        if self.peek() == Some(b'\r') {
+            self.pos += 1;
+        }
+        if self.peek() == Some(b'\n') {
+            self.pos += 1;
+        }
+    }
+
+    fn skip_comment(&mut self) {
+        if self.peek() == Some(b'#') {
+            while !self.at_line_end() {
+                self.pos += 1;
+            }
+        }
+    }
+
+    // Skips whitespace, comments and line breaks up to the next content
+    // character, returning false at the end of the input.
+    fn skip_to_content(&mut self) -> Result<bool, DocError> {
+        loop {
+            self.skip_spaces();
+            self.skip_comment();
+            match self.peek() {
+                Some(b'\r' | b'\n') => self.skip_line_break(),
+                Some(_) => {
+                    let start = self.line_start(self.pos);
+                    if self.bytes[start..self.pos].iter().all(|b| *b == b' ' || *b == b'\t')
+                        && self.bytes[start..self.pos].contains(&b'\t') {
+                        return Err(self.error_at(start, ParseErrorKind::InvalidIndentation));
+                    }
+                    return Ok(true);
+                },
+                None => return Ok(false),
+            }
+        }
+    }
+
+    // Ensures nothing but a comment follows a completed value on its line.
+    fn expect_line_end(&mut self) -> Result<(), DocError> {
+        self.skip_spaces();
+        self.skip_comment();
+        if self.at_line_end() { Ok(()) } else { Err(self.unexpected()) }
+    }
+
+    fn enter(&mut self) -> Result<(), DocError> {
+        self.depth += 1;
+        if self.depth > MAX_DEPTH {
+            return Err(self.error(ParseErrorKind::DepthLimitExceeded));
+        }
+        Ok(())
+    }
+
+    fn parse_document(&mut self) -> Result<ItemType, DocError> {
+        let mut has_content = self.skip_to_content()?;
+        while has_content && self.column() == 0 && self.peek() == Some(b'%') {
+            while !self.at_line_end() {
+                self.pos += 1;
+            }
+            has_content = self.skip_to_content()?;
+        }
+        if has_content && self.at_document_marker() && self.peek() == Some(b'-') {
+            self.pos += 3;
+            has_content = self.skip_to_content()?;
+        }
+
+        let root = if has_content && !self.at_document_marker() {
+            self.parse_block_node(-1)?
+        } else {
+            ItemNull::new()
+        };
+
+        if self.skip_to_content()? && self.at_document_marker() && self.peek() == Some(b'.') {
+            self.pos += 3;
+        }
+        if self.skip_to_content()? {
+            return Err(self.error(ParseErrorKind::TrailingCharacters));
+        }
+        Ok(root)
+    }
+
+    // Parses the node starting at the current content character; its
+    // parent sits at column parent_indent (-1 for the document root).
+    fn parse_block_node(&mut self, parent_indent: isize) -> Result<ItemType, DocError> {
+        self.enter()?;
+        let column = self.column();
+        let result = match self.peek() {
+            Some(b'-') if self.at_sequence_entry() => self.parse_block_sequence(column),
+            Some(b'[' | b'{') => {
+                let item = self.parse_flow_node()?;
+                self.skip_spaces();
+                if self.peek() == Some(b':') {
+                    return Err(self.error(ParseErrorKind::UnsupportedFeature));
+                }
+                Ok(item)
+            },
+            Some(b'|' | b'>') => Ok(ItemString::new(Some(self.parse_block_scalar(parent_indent)?))),
+            Some(b'&' | b'*' | b'!') => Err(self.error(ParseErrorKind::UnsupportedFeature)),
+            Some(b'?' | b':') if is_blank(self.peek_at(1)) => Err(self.error(ParseErrorKind::UnsupportedFeature)),
+            _ if self.line_has_mapping_key() => self.parse_block_mapping(column),
+            Some(b'"') => Ok(ItemString::new(Some(self.parse_double_quoted()?))),
+            Some(b'\'') => Ok(ItemString::new(Some(self.parse_single_quoted()?))),
+            _ => {
+                let text = self.parse_plain_block(parent_indent)?;
+                Ok(resolve_plain_scalar(&text))
+            },
+        };
+        self.depth -= 1;
+        result
+    }
+
+    fn line_has_mapping_key(&self) -> bool {
+        let mut i = self.pos;
+        let quote = self.bytes[i];
+        if quote == b'[' || quote == b'{' {
+            return false;
+        }
+        if quote == b'"' || quote == b'\'' {
+            i += 1;
+            while i < self.bytes.len() && self.bytes[i] != b'\n' {
+                if quote == b'"' && self.bytes[i] == b'\\' {
+                    i += 2;
+                    continue;
+                }
+                if self.bytes[i] == quote {
+                    if quote == b'\'' && self.bytes.get(i + 1) == Some(&b'\'') {
+                        i += 2;
+                        continue;
+                    }
+                    break;
+                }
+                i += 1;
+            }
+            if i >= self.bytes.len() || self.bytes[i] != quote {
+                return false;
+            }
+            i += 1;
+            while let Some(b' ' | b'\t') = self.bytes.get(i) {
+                i += 1;
+            }
+            return self.bytes.get(i) == Some(&b':') && is_blank(self.bytes.get(i + 1).copied());
+        }
+        while i < self.bytes.len() {
+            match self.bytes[i] {
+                b'\n' | b'\r' => return false,
+                b':' if is_blank(self.bytes.get(i + 1).copied()) => return true,
+                b'#' if i > self.pos && matches!(self.bytes[i - 1], b' ' | b'\t') => return false,
+                _ => i += 1,
+            }
+        }
+        false
+    }
+
+    fn parse_block_sequence(&mut self, column: usize) -> Result<ItemType, DocError> {
+        let mut list = ItemList::new();
+        loop {
+            self.pos += 1;
+            let item = self.parse_block_value(column, true).map_err(|e| e.within(list.count()))?;
+            list.add_item(item, None)?;
+
+            if !self.skip_to_content()? || self.at_document_marker() {
+                break;
+            }
+            let next = self.column();
+            if next == column && self.at_sequence_entry() {
+                continue;
+            }
+            if next > column {
+                return Err(self.error(ParseErrorKind::InvalidIndentation));
+            }
+            break;
+        }
+        Ok(list)
+    }
+
+    fn parse_block_mapping(&mut self, column: usize) -> Result<ItemType, DocError> {
+        let mut hash = ItemHash::new();
+        loop {
+            let key = self.parse_mapping_key()?;
+            let item = self.parse_block_value(column, false).map_err(|e| e.within(key.as_str()))?;
+            hash.add_item(item, Some(&key))?;
+
+            if !self.skip_to_content()? || self.at_document_marker() {
+                break;
+            }
+            let next = self.column();
+            if next < column {
+                break;
+            }
+            if next > column || self.at_sequence_entry() || !self.line_has_mapping_key() {
+                return Err(self.error(ParseErrorKind::InvalidIndentation));
+            }
+        }
+        Ok(hash)
+    }
+
+    fn parse_mapping_key(&mut self) -> Result<String, DocError> {
+        let key = match self.peek() {
+            Some(b'"') => self.parse_double_quoted()?,
+            Some(b'\'') => self.parse_single_quoted()?,
+            _ => {
+                let start = self.pos;
+                while !self.at_line_end() && !self.at_mapping_indicator() {
+                    self.pos += 1;
+                }
+                self.text[start..self.pos].trim_end().to_string()
+            },
+        };
+        self.skip_spaces();
+        if self.peek() != Some(b':') {
+            return Err(self.unexpected());
+        }
+        self.pos += 1;
+        Ok(key)
+    }
+
+    // Parses the value following "key:" or "- " belonging to an entry at
+    // the given column.
+    fn parse_block_value(&mut self, column: usize, in_sequence: bool) -> Result<ItemType, DocError> {
+        self.skip_spaces();
+        if self.at_line_end() || self.peek() == Some(b'#') {
+            if !self.skip_to_content()? || self.at_document_marker() {
+                return Ok(ItemNull::new());
+            }
+            let next = self.column();
+            if next > column || (next == column && !in_sequence && self.at_sequence_entry()) {
+                return self.parse_block_node(column as isize);
+            }
+            return Ok(ItemNull::new());
+        }
+
+        // only a sequence entry may start a compact collection on its line
+        let is_collection = self.at_sequence_entry() || self.line_has_mapping_key();
+        if is_collection && !in_sequence {
+            return Err(self.unexpected());
+        }
+        let is_block_scalar = matches!(self.peek(), Some(b'|' | b'>'));
+        let item = self.parse_block_node(column as isize)?;
+        if !is_collection && !is_block_scalar {
+            self.expect_line_end()?;
+        }
+        Ok(item)
+    }
+
+    fn parse_plain_block(&mut self, parent_indent: isize) -> Result<String, DocError> {
+        let mut text = self.read_plain_line();
+        loop {
+            let resume = self.pos;
+            let mut breaks = 0;
+            loop {
+                self.skip_spaces();
+                if self.peek() == Some(b'\r') || self.peek() == Some(b'\n') {
+                    self.skip_line_break();
+                    breaks += 1;
+                } else {
+                    break;
+                }
+            }
+            let continues = breaks > 0
+                && self.peek().is_some()
+                && self.peek() != Some(b'#')
+                && self.column() as isize > parent_indent
+                && !self.at_document_marker();
+            if !continues {
+                self.pos = resume;
+                break;
+            }
+            let line = self.read_plain_line();
+            if line.is_empty() {
+                self.pos = resume;
+                break;
+            }
+            if breaks == 1 {
+                text.push(' ');
+            } else {
+                text.push_str(&"\n".repeat(breaks - 1));
+            }
+            text.push_str(&line);
+        }
+        Ok(text)
+    }
+
+    fn read_plain_line(&mut self) -> String {
+        let start = self.pos;
+        while !self.at_line_end() {
+            match self.peek() {
+                Some(b':') if self.at_mapping_indicator() => break,
+                Some(b'#') if self.pos > start && matches!(self.bytes[self.pos - 1], b' ' | b'\t') => break,
+                _ => self.pos += 1,
+            }
+        }
+        self.text[start..self.pos].trim_end().to_string()
+    }
+
+    fn parse_block_scalar(&mut self, parent_indent: isize) -> Result<String, DocError> {
+        let literal = self.peek() == Some(b'|');
+        self.pos += 1;
+
+        let mut explicit: Option<usize> = None;
+        let mut chomp = b' ';
+        for _ in 0..2 {
+            match self.peek() {
+                Some(d @ b'1'..=b'9') if explicit.is_none() => { explicit = Some((d - b'0') as usize); self.pos += 1; },
+                Some(c @ (b'+' | b'-')) if chomp == b' ' => { chomp = c; self.pos += 1; },
+                _ => break,
+            }
+        }
+        self.expect_line_end()?;
+        self.skip_line_break();
+
+        let base = parent_indent.max(0) as usize;
+        let indent = match explicit {
+            Some(n) => base + n,
+            None => self.detect_block_indent(parent_indent),
+        };
+
+        let mut lines: Vec<&str> = Vec::new();
+        while self.pos < self.bytes.len() {
+            let start = self.pos;
+            let mut spaces = 0;
+            while self.peek_at(spaces) == Some(b' ') {
+                spaces += 1;
+            }
+            let mut end = start + spaces;
+            while end < self.bytes.len() && self.bytes[end] != b'\n' {
+                end += 1;
+            }
+            let line = self.text[start..end].trim_end_matches('\r');
+            if line.trim_start_matches(' ').is_empty() {
+                lines.push(if spaces > indent { &line[indent..] } else { "" });
+            } else if spaces >= indent && indent as isize > parent_indent && !self.at_document_marker() {
+                lines.push(&line[indent..]);
+            } else {
+                break;
+            }
+            self.pos = end;
+            self.skip_line_break();
+        }
+
+        let trailing = lines.iter().rev().take_while(|l| l.is_empty()).count();
+        let content = &lines[..lines.len() - trailing];
+        let mut text = if literal { content.join("\n") } else { fold_lines(content) };
+        if !content.is_empty() {
+            match chomp {
+                b'-' => {},
+                b'+' => text.push_str(&"\n".repeat(trailing + 1)),
+                _ => text.push('\n'),
+            }
+        } else if chomp == b'+' {
+            text.push_str(&"\n".repeat(trailing));
+        }
+        Ok(text)
+    }
+
+    fn detect_block_indent(&self, parent_indent: isize) -> usize {
+        let mut i = self.pos;
+        while i < self.bytes.len() {
+            let mut spaces = 0;
+            while self.bytes.get(i + spaces) == Some(&b' ') {
+                spaces += 1;
+            }
+            match self.bytes.get(i + spaces) {
+                Some(b'\n') | Some(b'\r') => {
+                    while i < self.bytes.len() && self.bytes[i] != b'\n' {
+                        i += 1;
+                    }
+                    i += 1;
+                },
+                Some(_) => return spaces.max((parent_indent + 1) as usize),
+                None => break,
+            }
+        }
+        (parent_indent + 1) as usize
+    }
+
+    fn parse_flow_node(&mut self) -> Result<ItemType, DocError> {
+        self.enter()?;
+        self.skip_flow_whitespace()?;
+        let result = match self.peek() {
+            Some(b'[') => self.parse_flow_sequence(),
+            Some(b'{') => self.parse_flow_mapping(),
+            Some(b'"') => Ok(ItemString::new(Some(self.parse_double_quoted()?))),
+            Some(b'\'') => Ok(ItemString::new(Some(self.parse_single_quoted()?))),
+            Some(b'&' | b'*' | b'!' | b'?') => Err(self.error(ParseErrorKind::UnsupportedFeature)),
+            Some(b']' | b'}' | b',') | None => Err(self.unexpected()),
+            _ => {
+                let text = self.parse_plain_flow()?;
+                Ok(resolve_plain_scalar(&text))
+            },
+        };
+        self.depth -= 1;
+        result
+    }
+
+    fn skip_flow_whitespace(&mut self) -> Result<(), DocError> {
+        if !self.skip_to_content()? {
+            return Err(self.error(ParseErrorKind::UnexpectedEndOfInput));
+        }
+        Ok(())
+    }
+
+    fn parse_flow_sequence(&mut self) -> Result<ItemType, DocError> {
+        self.pos += 1;
+        let mut list = ItemList::new();
+        loop {
+            self.skip_flow_whitespace()?;
+            if self.peek() == Some(b']') {
+                self.pos += 1;
+                return Ok(list);
+            }
+            let item = self.parse_flow_node().map_err(|e| e.within(list.count()))?;
+            list.add_item(item, None)?;
+            self.skip_flow_whitespace()?;
+            match self.peek() {
+                Some(b',') => self.pos += 1,
+                Some(b']') => {},
+                Some(b':') => return Err(self.error(ParseErrorKind::UnsupportedFeature)),
+                _ => return Err(self.unexpected()),
+            }
+        }
+    }
+
+    fn parse_flow_mapping(&mut self) -> Result<ItemType, DocError> {
+        self.pos += 1;
+        let mut hash = ItemHash::new();
+        loop {
+            self.skip_flow_whitespace()?;
+            if self.peek() == Some(b'}') {
+                self.pos += 1;
+                return Ok(hash);
+            }
+            let key = match self.peek() {
+                Some(b'"') => self.parse_double_quoted()?,
+                Some(b'\'') => self.parse_single_quoted()?,
+                Some(b'[' | b'{' | b',' | b'&' | b'*' | b'!' | b'?') => return Err(self.error(ParseErrorKind::UnsupportedFeature)),
+                _ => self.parse_plain_flow()?,
+            };
+            self.skip_flow_whitespace()?;
+            let item = if self.peek() == Some(b':') {
+                self.pos += 1;
+                self.skip_flow_whitespace()?;
+                match self.peek() {
+                    Some(b',' | b'}') => ItemNull::new(),
+                    _ => self.parse_flow_node().map_err(|e| e.within(key.as_str()))?,
+                }
+            } else {
+                ItemNull::new()
+            };
+            hash.add_item(item, Some(&key))?;
+            self.skip_flow_whitespace()?;
+            match self.peek() {
+                Some(b',') => self.pos += 1,
+                Some(b'}') => {},
+                _ => return Err(self.unexpected()),
+            }
+        }
+    }
+
+    fn parse_plain_flow(&mut self) -> Result<String, DocError> {
+        let mut text = String::new();
+        let mut breaks = 0;
+        loop {
+            let start = self.pos;
+            while !self.at_line_end() {
+                let next = self.peek_at(1);
+                match self.peek() {
+                    Some(b':') if is_blank(next) || is_flow_indicator(next) => break,
+                    Some(b',' | b'[' | b']' | b'{' | b'}') => break,
+                    Some(b'#') if self.pos > start && matches!(self.bytes[self.pos - 1], b' ' | b'\t') => break,
+                    _ => self.pos += 1,
+                }
+            }
+            let line = self.text[start..self.pos].trim();
+            if !line.is_empty() {
+                if !text.is_empty() {
+                    if breaks <= 1 { text.push(' '); } else { text.push_str(&"\n".repeat(breaks - 1)); }
+                }
+                text.push_str(line);
+            }
+            if !self.at_line_end() {
+                break;
+            }
+            // the scalar may continue on the next line inside a collection
+            let resume = self.pos;
+            breaks = 0;
+            while matches!(self.peek(), Some(b'\r' | b'\n')) {
+                self.skip_line_break();
+                breaks += 1;
+                self.skip_spaces();
+            }
+            if breaks == 0 || matches!(self.peek(), None | Some(b'#' | b',' | b']' | b'}' | b':')) {
+                self.pos = resume;
+                break;
+            }
+        }
+        Ok(text)
+    }
+
+    fn parse_single_quoted(&mut self) -> Result<String, DocError> {
+        let start = self.pos;
+        self.pos += 1;
+        let mut text = String::new();
+        loop {
+            match self.peek() {
+                None => return Err(self.error_at(start, ParseErrorKind::UnexpectedEndOfInput)),
+                Some(b'\'') if self.peek_at(1) == Some(b'\'') => { text.push('\''); self.pos += 2; },
+                Some(b'\'') => { self.pos += 1; return Ok(text); },
+                Some(b'\r' | b'\n') => self.fold_quoted_break(&mut text),
+                Some(_) => self.push_char(&mut text),
+            }
+        }
+    }
+
+    fn parse_double_quoted(&mut self) -> Result<String, DocError> {
+        let start = self.pos;
+        self.pos += 1;
+        let mut text = String::new();
+        loop {
+            match self.peek() {
+                None => return Err(self.error_at(start, ParseErrorKind::UnexpectedEndOfInput)),
+                Some(b'"') => { self.pos += 1; return Ok(text); },
+                Some(b'\\') => {
+                    let escape = self.pos;
+                    self.pos += 1;
+                    let c = match self.peek() {
+                        Some(b'\r' | b'\n') => {
+                            // an escaped line break joins the lines without a space
+                            self.skip_line_break();
+                            self.skip_spaces();
+                            continue;
+                        },
+                        Some(b'0') => '\0',
+                        Some(b'a') => '\u{07}',
+                        Some(b'b') => '\u{08}',
+                        Some(b't' | b'\t') => '\t',
+                        Some(b'n') => '\n',
+                        Some(b'v') => '\u{0B}',
+                        Some(b'f') => '\u{0C}',
+                        Some(b'r') => '\r',
+                        Some(b'e') => '\u{1B}',
+                        Some(b' ') => ' ',
+                        Some(b'"') => '"',
+                        Some(b'/') => '/',
+                        Some(b'\\') => '\\',
+                        Some(b'N') => '\u{85}',
+                        Some(b'_') => '\u{A0}',
+                        Some(b'L') => '\u{2028}',
+                        Some(b'P') => '\u{2029}',
+                        Some(b'x') => self.read_hex_escape(escape, 2)?,
+                        Some(b'u') => self.read_hex_escape(escape, 4)?,
+                        Some(b'U') => self.read_hex_escape(escape, 8)?,
+                        _ => return Err(self.error_at(escape, ParseErrorKind::InvalidEscape)),
+                    };
+                    self.pos += 1;
+                    text.push(c);
+                },
+                Some(b'\r' | b'\n') => self.fold_quoted_break(&mut text),
+                Some(_) => self.push_char(&mut text),
+            }
+        }
+    }
+
+    fn read_hex_escape(&mut self, escape: usize, digits: usize) -> Result<char, DocError> {
+        let hex = self.text.get(self.pos + 1..self.pos + 1 + digits)
+            .ok_or_else(|| self.error_at(escape, ParseErrorKind::InvalidUnicode))?;
+        let code = u32::from_str_radix(hex, 16)
+            .map_err(|_| self.error_at(escape, ParseErrorKind::InvalidUnicode))?;
+        self.pos += digits;
+        char::from_u32(code).ok_or_else(|| self.error_at(escape, ParseErrorKind::InvalidUnicode))
+    }
+
+    fn push_char(&mut self, text: &mut String) {
+        let c = self.text[self.pos..].chars().next().unwrap_or_default();
+        text.push(c);
+        self.pos += c.len_utf8();
+    }
+
+    // Folds a line break inside a quoted scalar: trailing and leading white
+    // space is dropped, a single break becomes a space and each further
+    // empty line is kept as a newline.
+    fn fold_quoted_break(&mut self, text: &mut String) {
+        let trimmed = text.trim_end_matches([' ', '\t']).len();
+        text.truncate(trimmed);
+        let mut breaks = 0;
+        while matches!(self.peek(), Some(b'\r' | b'\n')) {
+            self.skip_line_break();
+            breaks += 1;
+            self.skip_spaces();
+        }
+        if breaks == 1 {
+            text.push(' ');
+        } else {
+            text.push_str(&"\n".repeat(breaks - 1));
+        }
+    }
+}
+
+// Applies folded block scalar line folding to the content lines.
+fn fold_lines(lines: &[&str]) -> String {
+    let mut text = String::new();
+    let mut previous_more_indented: Option<bool> = None;
+    let mut breaks = 0;
+    for line in lines {
+        if line.is_empty() {
+            breaks += 1;
+            continue;
+        }
+        let more_indented = line.starts_with([' ', '\t']);
+        match previous_more_indented {
+            None => text.push_str(&"\n".repeat(breaks)),
+            Some(previous) if !previous && !more_indented => {
+                if breaks == 0 { text.push(' '); } else { text.push_str(&"\n".repeat(breaks)); }
+            },
+            Some(_) => text.push_str(&"\n".repeat(breaks + 1)),
+        }
+        text.push_str(line);
+        previous_more_indented = Some(more_indented);
+        breaks = 0;
+    }
+    text
+}
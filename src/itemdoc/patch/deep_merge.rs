@@ -0,0 +1,170 @@
+use std::fmt;
+
+use crate::itemdoc::core::items::*;
+use crate::itemdoc::core::lists::ItemList;
+use crate::itemdoc::core::hashes::ItemHash;
+use crate::itemdoc::core::pointers::ItemPointer;
+
+// How a list on the right is combined with a list on the left.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub enum ListMerge {
+    // The right list replaces the left one.
+    #[default]
+    Replace,
+    // Right members are appended after the left ones.
+    Append,
+    // Members at the same index are merged; extra right members are appended.
+    ByIndex,
+    // Hash members sharing a value for the named field are merged; anything
+    // else on the right is appended.
+    ByKey(String),
+}
+
+// What happens when the two sides hold different kinds of item at a path.
+#[derive(Debug, Copy, Clone, PartialEq, Default)]
+pub enum TypeConflict {
+    Error,
+    PreferLeft,
+    #[default]
+    PreferRight,
+}
+
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct MergeOptions {
+    pub lists: ListMerge,
+    pub conflicts: TypeConflict,
+}
+
+impl MergeOptions {
+    pub fn new() -> Self {
+        MergeOptions::default()
+    }
+    pub fn with_lists(mut self, lists: ListMerge) -> Self {
+        self.lists = lists;
+        self
+    }
+    pub fn with_conflicts(mut self, conflicts: TypeConflict) -> Self {
+        self.conflicts = conflicts;
+        self
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum MergeError {
+    TypeConflict(ItemPointer),
+}
+
+impl fmt::Display for MergeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MergeError::TypeConflict(path) => write!(f, "Merge type conflict at '{}'!", path),
+        }
+    }
+}
+
+impl std::error::Error for MergeError {}
+
+// Merges `other` into `target`. Hashes merge key by key and scalars of the
+// same kind are overwritten by the right side. On error the target is left
+// untouched.
+pub fn deep_merge(target: &mut ItemType, other: &ItemType, options: &MergeOptions) -> Result<(), MergeError> {
+    let mut working = target.clone();
+    merge(&mut ItemPointer::root(), &mut working, other, options)?;
+    *target = working;
+    Ok(())
+}
+
+fn merge(path: &mut ItemPointer, left: &mut ItemType, right: &ItemType, options: &MergeOptions) -> Result<(), MergeError> {
+    match (left, right) {
+        (ItemType::THash(a), ItemType::THash(b)) => merge_hashes(path, a, b, options),
+        (ItemType::TList(a), ItemType::TList(b)) => merge_lists(path, a, b, options),
+        (left, right) if left.get_type_name() == right.get_type_name() => {
+            *left = right.clone();
+            Ok(())
+        },
+        (left, right) => match options.conflicts {
+            TypeConflict::Error => Err(MergeError::TypeConflict(path.clone())),
+            TypeConflict::PreferLeft => Ok(()),
+            TypeConflict::PreferRight => {
+                *left = right.clone();
+                Ok(())
+            },
+        },
+    }
+}
+
+fn merge_hashes(path: &mut ItemPointer, a: &mut ItemHash, b: &ItemHash, options: &MergeOptions) -> Result<(), MergeError> {
+    for key in b.get_keys().unwrap_or_default() {
+        let (name, value) = match (key, b.get_item(key)) {
+            (ContainerKey::Key(name), Ok(Some(value))) => (name, value),
+            _ => continue,
+        };
+        match a.get_item_mut(name) {
+            Ok(Some(existing)) => merge_child(path, key, existing, value, options)?,
+            _ => {
+                a.add_item(value.clone(), Some(name)).ok();
+            },
+        }
+    }
+    Ok(())
+}
+
+fn merge_lists(path: &mut ItemPointer, a: &mut ItemList, b: &ItemList, options: &MergeOptions) -> Result<(), MergeError> {
+    let right = (0..b.count()).filter_map(|index| b.get_item(index).ok().flatten());
+    match &options.lists {
+        ListMerge::Replace => {
+            a.clear().ok();
+            right.for_each(|member| { a.add_item(member.clone(), None).ok(); });
+        },
+        ListMerge::Append => {
+            right.for_each(|member| { a.add_item(member.clone(), None).ok(); });
+        },
+        ListMerge::ByIndex => {
+            for (index, member) in right.enumerate() {
+                match a.get_item_mut(index) {
+                    Ok(Some(existing)) => merge_child(path, ContainerKey::Idx(index), existing, member, options)?,
+                    _ => { a.add_item(member.clone(), None).ok(); },
+                }
+            }
+        },
+        ListMerge::ByKey(field) => {
+            for member in right {
+                match identity(member, field).and_then(|id| position_of(a, field, id)) {
+                    Some(index) => {
+                        if let Ok(Some(existing)) = a.get_item_mut(index) {
+                            merge_child(path, ContainerKey::Idx(index), existing, member, options)?;
+                        }
+                    },
+                    None => { a.add_item(member.clone(), None).ok(); },
+                }
+            }
+        },
+    }
+    Ok(())
+}
+
+fn merge_child<'a>(
+    path: &mut ItemPointer,
+    key: ContainerKey<'a>,
+    left: &mut ItemType,
+    right: &ItemType,
+    options: &MergeOptions,
+) -> Result<(), MergeError> {
+    path.push_key(&key);
+    let result = merge(path, left, right, options);
+    path.pop();
+    result
+}
+
+fn identity<'a>(member: &'a ItemType, field: &str) -> Option<&'a ItemType> {
+    match member {
+        ItemType::THash(hash) => hash.get_item(field).ok().flatten(),
+        _ => None,
+    }
+}
+
+fn position_of(list: &ItemList, field: &str, id: &ItemType) -> Option<usize> {
+    (0..list.count()).find(|index| {
+        list.get_item(*index).ok().flatten().and_then(|member| identity(member, field)) == Some(id)
+    })
+}
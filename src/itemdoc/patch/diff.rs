@@ -0,0 +1,388 @@
+use std::cell::RefCell;
+use std::fmt;
+use std::io::Write;
+use std::rc::Rc;
+
+use crate::itemdoc::core::items::*;
+use crate::itemdoc::core::hashes::ItemHash;
+use crate::itemdoc::core::lists::ItemList;
+use crate::itemdoc::core::pointers::ItemPointer;
+use crate::itemdoc::exports::json::compact::JSONFormatCompact;
+
+// How list members are paired up before they are compared.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub enum ListMode {
+    // Lists are sequences; members are aligned on their longest common
+    // subsequence so an insertion is reported as one addition.
+    #[default]
+    Ordered,
+    // Lists are sets of hashes identified by the named field. Members
+    // without that field are matched by value.
+    KeyedBy(String),
+}
+
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct DiffOptions {
+    pub lists: ListMode,
+}
+
+impl DiffOptions {
+    pub fn new() -> Self {
+        DiffOptions::default()
+    }
+    pub fn keyed_by(field: &str) -> Self {
+        DiffOptions { lists: ListMode::KeyedBy(field.to_string()) }
+    }
+}
+
+// Removed members are addressed by their index in the old list; added and
+// changed members by their index in the new one.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ItemChange {
+    Added { path: ItemPointer, value: ItemType },
+    Removed { path: ItemPointer, value: ItemType },
+    Changed { path: ItemPointer, old: ItemType, new: ItemType },
+    TypeChanged { path: ItemPointer, old: ItemType, new: ItemType },
+}
+
+impl ItemChange {
+
+    pub fn get_name(&self) -> &'static str {
+        match self {
+            ItemChange::Added { .. } => "added",
+            ItemChange::Removed { .. } => "removed",
+            ItemChange::Changed { .. } => "changed",
+            ItemChange::TypeChanged { .. } => "type-changed",
+        }
+    }
+
+    pub fn get_path(&self) -> &ItemPointer {
+        match self {
+            ItemChange::Added { path, .. } => path,
+            ItemChange::Removed { path, .. } => path,
+            ItemChange::Changed { path, .. } => path,
+            ItemChange::TypeChanged { path, .. } => path,
+        }
+    }
+
+    pub fn get_old(&self) -> Option<&ItemType> {
+        match self {
+            ItemChange::Added { .. } => None,
+            ItemChange::Removed { value, .. } => Some(value),
+            ItemChange::Changed { old, .. } | ItemChange::TypeChanged { old, .. } => Some(old),
+        }
+    }
+
+    pub fn get_new(&self) -> Option<&ItemType> {
+        match self {
+            ItemChange::Added { value, .. } => Some(value),
+            ItemChange::Removed { .. } => None,
+            ItemChange::Changed { new, .. } | ItemChange::TypeChanged { new, .. } => Some(new),
+        }
+    }
+
+}
+
+// Renders one hunk per change in the style of a unified diff:
+//
+//   @@ /servers/0/port @@
+//   -80
+//   +8080
+impl fmt::Display for ItemChange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let path = match self.get_path() {
+            path if path.is_root() => "/".to_string(),
+            path => path.to_string(),
+        };
+        match (self.get_old(), self.get_new()) {
+            (Some(old), Some(new)) if old.get_type_name() != new.get_type_name() => {
+                writeln!(f, "@@ {} ({} -> {}) @@", path, old.get_type_name(), new.get_type_name())?;
+            },
+            _ => writeln!(f, "@@ {} @@", path)?,
+        }
+        if let Some(old) = self.get_old() {
+            writeln!(f, "-{}", render(old))?;
+        }
+        if let Some(new) = self.get_new() {
+            writeln!(f, "+{}", render(new))?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct ItemDiff {
+    changes: Vec<ItemChange>,
+}
+
+impl ItemDiff {
+
+    pub fn get_changes(&self) -> &[ItemChange] {
+        &self.changes
+    }
+    pub fn count(&self) -> usize {
+        self.changes.len()
+    }
+    pub fn is_empty(&self) -> bool {
+        self.changes.is_empty()
+    }
+
+}
+
+impl fmt::Display for ItemDiff {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.changes.iter().try_for_each(|change| write!(f, "{}", change))
+    }
+}
+
+pub fn diff(old: &ItemType, new: &ItemType) -> ItemDiff {
+    diff_with(old, new, &DiffOptions::default())
+}
+
+pub fn diff_with(old: &ItemType, new: &ItemType, options: &DiffOptions) -> ItemDiff {
+    let mut changes = Vec::new();
+    compare(&mut ItemPointer::root(), old, new, options, &mut changes);
+    ItemDiff { changes }
+}
+
+fn compare(path: &mut ItemPointer, old: &ItemType, new: &ItemType, options: &DiffOptions, changes: &mut Vec<ItemChange>) {
+    if old == new {
+        return;
+    }
+    match (old, new) {
+        (ItemType::THash(a), ItemType::THash(b)) => compare_hashes(path, a, b, options, changes),
+        (ItemType::TList(a), ItemType::TList(b)) => match &options.lists {
+            ListMode::Ordered => compare_ordered(path, a, b, options, changes),
+            ListMode::KeyedBy(field) => compare_keyed(path, a, b, field, options, changes),
+        },
+        _ if old.get_type_name() == new.get_type_name() => {
+            changes.push(ItemChange::Changed { path: path.clone(), old: old.clone(), new: new.clone() });
+        },
+        _ => changes.push(ItemChange::TypeChanged { path: path.clone(), old: old.clone(), new: new.clone() }),
+    }
+}
+
+fn compare_hashes(path: &mut ItemPointer, a: &ItemHash, b: &ItemHash, options: &DiffOptions, changes: &mut Vec<ItemChange>) {
+    for key in a.get_keys().unwrap_or_default() {
+        if let (false, Ok(Some(value))) = (b.has_key(key), a.get_item(key)) {
+            changes.push(ItemChange::Removed { path: child(path, key), value: value.clone() });
+        }
+    }
+    for key in b.get_keys().unwrap_or_default() {
+        match (a.get_item(key).ok().flatten(), b.get_item(key).ok().flatten()) {
+            (Some(before), Some(after)) => {
+                path.push_key(&key);
+                compare(path, before, after, options, changes);
+                path.pop();
+            },
+            (None, Some(after)) => {
+                changes.push(ItemChange::Added { path: child(path, key), value: after.clone() });
+            },
+            _ => {},
+        }
+    }
+}
+
+// Members outside the common subsequence are paired off within each gap so
+// that an edited member is reported as a change inside it rather than as a
+// removal followed by an addition.
+fn compare_ordered(path: &mut ItemPointer, a: &ItemList, b: &ItemList, options: &DiffOptions, changes: &mut Vec<ItemChange>) {
+    let before = members(a);
+    let after = members(b);
+    let (mut i, mut j) = (0, 0);
+    for (next_i, next_j) in common_subsequence(&before, &after).into_iter().chain([(before.len(), after.len())]) {
+        while i < next_i && j < next_j {
+            path.push_key(&ContainerKey::Idx(j));
+            compare(path, before[i], after[j], options, changes);
+            path.pop();
+            i += 1;
+            j += 1;
+        }
+        for (index, member) in before.iter().enumerate().take(next_i).skip(i) {
+            changes.push(ItemChange::Removed { path: child(path, index), value: (*member).clone() });
+        }
+        for (index, member) in after.iter().enumerate().take(next_j).skip(j) {
+            changes.push(ItemChange::Added { path: child(path, index), value: (*member).clone() });
+        }
+        i = next_i + 1;
+        j = next_j + 1;
+    }
+}
+
+fn compare_keyed(path: &mut ItemPointer, a: &ItemList, b: &ItemList, field: &str, options: &DiffOptions, changes: &mut Vec<ItemChange>) {
+    let before = members(a);
+    let after = members(b);
+    let mut matched = vec![false; before.len()];
+    let mut pairs = Vec::new();
+    for (j, member) in after.iter().enumerate() {
+        let identity = identity_of(member, field);
+        let found = (0..before.len()).find(|i| {
+            !matched[*i] && match identity {
+                Some(id) => identity_of(before[*i], field) == Some(id),
+                None => before[*i] == *member,
+            }
+        });
+        match found {
+            Some(i) => {
+                matched[i] = true;
+                pairs.push((i, j));
+            },
+            None => pairs.push((usize::MAX, j)),
+        }
+    }
+    for (index, member) in before.iter().enumerate() {
+        if !matched[index] {
+            changes.push(ItemChange::Removed { path: child(path, index), value: (*member).clone() });
+        }
+    }
+    for (i, j) in pairs {
+        if i == usize::MAX {
+            changes.push(ItemChange::Added { path: child(path, j), value: after[j].clone() });
+        } else {
+            path.push_key(&ContainerKey::Idx(j));
+            compare(path, before[i], after[j], options, changes);
+            path.pop();
+        }
+    }
+}
+
+fn identity_of<'a>(member: &'a ItemType, field: &str) -> Option<&'a ItemType> {
+    match member {
+        ItemType::THash(hash) => hash.get_item(field).ok().flatten(),
+        _ => None,
+    }
+}
+
+fn members(list: &ItemList) -> Vec<&ItemType> {
+    (0..list.count()).filter_map(|index| list.get_item(index).ok().flatten()).collect()
+}
+
+// The index pairs of a longest common subsequence, in order.
+fn common_subsequence(a: &[&ItemType], b: &[&ItemType]) -> Vec<(usize, usize)> {
+    let mut pairs = Vec::new();
+    match_range(a, b, 0, 0, &mut pairs);
+    pairs
+}
+
+// Matches equal leading and trailing members directly, then splits what is
+// left where Myers' forward and backward searches meet. That takes O(ND)
+// time for D differences and memory linear in the list lengths.
+fn match_range(a: &[&ItemType], b: &[&ItemType], a_base: usize, b_base: usize, pairs: &mut Vec<(usize, usize)>) {
+    let prefix = a.iter().zip(b).take_while(|(x, y)| x == y).count();
+    pairs.extend((0..prefix).map(|k| (a_base + k, b_base + k)));
+    let (a, b) = (&a[prefix..], &b[prefix..]);
+    let suffix = a.iter().rev().zip(b.iter().rev()).take_while(|(x, y)| x == y).count();
+    let (a, b) = (&a[..a.len() - suffix], &b[..b.len() - suffix]);
+    let (a_base, b_base) = (a_base + prefix, b_base + prefix);
+    if !a.is_empty() && !b.is_empty() {
+        if let Some((x, y)) = bisect(a, b) {
+            match_range(&a[..x], &b[..y], a_base, b_base, pairs);
+            match_range(&a[x..], &b[y..], a_base + x, b_base + y, pairs);
+        }
+    }
+    pairs.extend((0..suffix).map(|k| (a_base + a.len() + k, b_base + b.len() + k)));
+}
+
+// Finds a point on a shortest edit path by running the search from both
+// ends until the paths overlap; None when the lists share no member.
+fn bisect(a: &[&ItemType], b: &[&ItemType]) -> Option<(usize, usize)> {
+    let (n, m) = (a.len() as isize, b.len() as isize);
+    let max_d = (n + m + 1) / 2;
+    let offset = max_d;
+    let size = 2 * max_d as usize + 2;
+    let mut forward = vec![-1isize; size];
+    let mut backward = vec![-1isize; size];
+    forward[offset as usize + 1] = 0;
+    backward[offset as usize + 1] = 0;
+    let delta = n - m;
+    let front = delta % 2 != 0;
+    let (mut k1_start, mut k1_end, mut k2_start, mut k2_end) = (0, 0, 0, 0);
+    for d in 0..max_d {
+        let mut k1 = -d + k1_start;
+        while k1 <= d - k1_end {
+            let k1_offset = (offset + k1) as usize;
+            let mut x1 = if k1 == -d || (k1 != d && forward[k1_offset - 1] < forward[k1_offset + 1]) {
+                forward[k1_offset + 1]
+            } else {
+                forward[k1_offset - 1] + 1
+            };
+            let mut y1 = x1 - k1;
+            while x1 < n && y1 < m && a[x1 as usize] == b[y1 as usize] {
+                x1 += 1;
+                y1 += 1;
+            }
+            forward[k1_offset] = x1;
+            if x1 > n {
+                k1_end += 2;
+            } else if y1 > m {
+                k1_start += 2;
+            } else if front {
+                let k2_offset = offset + delta - k1;
+                if (0..size as isize).contains(&k2_offset) && backward[k2_offset as usize] != -1 && x1 >= n - backward[k2_offset as usize] {
+                    return Some((x1 as usize, y1 as usize));
+                }
+            }
+            k1 += 2;
+        }
+        let mut k2 = -d + k2_start;
+        while k2 <= d - k2_end {
+            let k2_offset = (offset + k2) as usize;
+            let mut x2 = if k2 == -d || (k2 != d && backward[k2_offset - 1] < backward[k2_offset + 1]) {
+                backward[k2_offset + 1]
+            } else {
+                backward[k2_offset - 1] + 1
+            };
+            let mut y2 = x2 - k2;
+            while x2 < n && y2 < m && a[(n - x2 - 1) as usize] == b[(m - y2 - 1) as usize] {
+                x2 += 1;
+                y2 += 1;
+            }
+            backward[k2_offset] = x2;
+            if x2 > n {
+                k2_end += 2;
+            } else if y2 > m {
+                k2_start += 2;
+            } else if !front {
+                let k1_offset = offset + delta - k2;
+                if (0..size as isize).contains(&k1_offset) && forward[k1_offset as usize] != -1 {
+                    let x1 = forward[k1_offset as usize];
+                    let y1 = offset + x1 - k1_offset;
+                    if x1 >= n - x2 {
+                        return Some((x1 as usize, y1 as usize));
+                    }
+                }
+            }
+            k2 += 2;
+        }
+    }
+    None
+}
+
+fn child<'a, K: Into<ContainerKey<'a>>>(path: &ItemPointer, key: K) -> ItemPointer {
+    let mut pointer = path.clone();
+    pointer.push_key(&key.into());
+    pointer
+}
+
+#[derive(Clone, Default)]
+struct Capture(Rc<RefCell<Vec<u8>>>);
+
+impl Write for Capture {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.0.borrow_mut().extend_from_slice(buf);
+        Ok(buf.len())
+    }
+    fn flush(&mut self) -> std::io::Result<()> { Ok(()) }
+}
+
+// Values are shown as compact JSON so strings keep their quotes.
+fn render(item: &ItemType) -> String {
+    let capture = Capture::default();
+    let mut output = JSONFormatCompact::new(Box::new(capture.clone()));
+    if item.export(&mut output).is_err() {
+        return item.to_string();
+    }
+    drop(output);
+    let bytes = capture.0.borrow();
+    String::from_utf8_lossy(&bytes).into_owned()
+}
@@ -0,0 +1,274 @@
+use std::fmt;
+
+use crate::itemdoc::core::items::*;
+use crate::itemdoc::core::errors::DocError;
+use crate::itemdoc::core::lists::ItemList;
+use crate::itemdoc::core::hashes::ItemHash;
+use crate::itemdoc::core::pointers::{self, ItemPointer};
+use crate::itemdoc::exports::core::ItemOutput;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum PatchOperation {
+    Add { path: ItemPointer, value: ItemType },
+    Remove { path: ItemPointer },
+    Replace { path: ItemPointer, value: ItemType },
+    Move { from: ItemPointer, path: ItemPointer },
+    Copy { from: ItemPointer, path: ItemPointer },
+    Test { path: ItemPointer, value: ItemType },
+}
+
+// Every variant carries the index of the operation that failed.
+#[derive(Debug)]
+#[derive(Copy, Clone, PartialEq)]
+pub enum PatchError {
+    InvalidOperation(usize),
+    Item(usize, ItemError),
+    TestFailed(usize),
+}
+
+impl fmt::Display for PatchError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PatchError::InvalidOperation(n) => write!(f, "Patch operation {} is invalid!", n),
+            PatchError::Item(n, err) => write!(f, "Patch operation {} failed: {}", n, err),
+            PatchError::TestFailed(n) => write!(f, "Patch operation {} test failed!", n),
+        }
+    }
+}
+
+impl std::error::Error for PatchError {}
+
+impl PatchOperation {
+
+    pub fn get_name(&self) -> &'static str {
+        match self {
+            PatchOperation::Add { .. } => "add",
+            PatchOperation::Remove { .. } => "remove",
+            PatchOperation::Replace { .. } => "replace",
+            PatchOperation::Move { .. } => "move",
+            PatchOperation::Copy { .. } => "copy",
+            PatchOperation::Test { .. } => "test",
+        }
+    }
+
+    pub fn get_path(&self) -> &ItemPointer {
+        match self {
+            PatchOperation::Add { path, .. } => path,
+            PatchOperation::Remove { path } => path,
+            PatchOperation::Replace { path, .. } => path,
+            PatchOperation::Move { path, .. } => path,
+            PatchOperation::Copy { path, .. } => path,
+            PatchOperation::Test { path, .. } => path,
+        }
+    }
+
+    fn apply(&self, target: &mut ItemType, index: usize) -> Result<(), PatchError> {
+        // pointer operations only fail with item errors
+        let item_error = |err: DocError| PatchError::Item(index, err.get_item_error().unwrap_or(ItemError::InvalidPointer));
+        match self {
+            PatchOperation::Add { path, value } => {
+                pointers::insert(target, path, value.clone()).map_err(item_error)
+            },
+            PatchOperation::Remove { path } => {
+                target.remove_at_pointer(path).map(|_| ()).map_err(item_error)
+            },
+            PatchOperation::Replace { path, value } => {
+                *target.pointer_mut(path).map_err(item_error)? = value.clone();
+                Ok(())
+            },
+            PatchOperation::Move { from, path } => {
+                if from == path {
+                    return target.pointer(from).map(|_| ()).map_err(item_error);
+                }
+                // a value cannot be moved into one of its own children
+                if path.starts_with(from) {
+                    return Err(PatchError::InvalidOperation(index));
+                }
+                let value = target.remove_at_pointer(from).map_err(item_error)?;
+                pointers::insert(target, path, value).map_err(item_error)
+            },
+            PatchOperation::Copy { from, path } => {
+                let value = target.pointer(from).map_err(item_error)?.clone();
+                pointers::insert(target, path, value).map_err(item_error)
+            },
+            PatchOperation::Test { path, value } => {
+                if target.pointer(path).map_err(item_error)? == value {
+                    Ok(())
+                } else {
+                    Err(PatchError::TestFailed(index))
+                }
+            },
+        }
+    }
+
+    fn to_item(&self) -> ItemType {
+        let mut hash = ItemHash::new();
+        hash.add_value(self.get_name(), Some("op")).ok();
+        hash.add_value(self.get_path().to_string(), Some("path")).ok();
+        match self {
+            PatchOperation::Move { from, .. } | PatchOperation::Copy { from, .. } => {
+                hash.add_value(from.to_string(), Some("from")).ok();
+            },
+            PatchOperation::Add { value, .. }
+            | PatchOperation::Replace { value, .. }
+            | PatchOperation::Test { value, .. } => {
+                hash.add_item(value.clone(), Some("value")).ok();
+            },
+            PatchOperation::Remove { .. } => {},
+        }
+        hash
+    }
+
+    fn from_item(item: &ItemType, index: usize) -> Result<PatchOperation, PatchError> {
+        let invalid = PatchError::InvalidOperation(index);
+        let pointer = |name: &str| match item.get_item(name) {
+            Ok(Some(ItemType::TString(s))) => ItemPointer::parse(s.get_value()).map_err(|_| invalid),
+            _ => Err(invalid),
+        };
+        let value = || match item.get_item("value") {
+            Ok(Some(value)) => Ok(value.clone()),
+            _ => Err(invalid),
+        };
+        let op = match item.get_item("op") {
+            Ok(Some(ItemType::TString(s))) => s.get_value().as_str(),
+            _ => return Err(invalid),
+        };
+        match op {
+            "add" => Ok(PatchOperation::Add { path: pointer("path")?, value: value()? }),
+            "remove" => Ok(PatchOperation::Remove { path: pointer("path")? }),
+            "replace" => Ok(PatchOperation::Replace { path: pointer("path")?, value: value()? }),
+            "move" => Ok(PatchOperation::Move { from: pointer("from")?, path: pointer("path")? }),
+            "copy" => Ok(PatchOperation::Copy { from: pointer("from")?, path: pointer("path")? }),
+            "test" => Ok(PatchOperation::Test { path: pointer("path")?, value: value()? }),
+            _ => Err(invalid),
+        }
+    }
+
+}
+
+// An RFC 6902 JSON Patch: a sequence of operations that is applied to a
+// document as a whole or not at all.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct ItemPatch {
+    operations: Vec<PatchOperation>,
+}
+
+impl ItemPatch {
+
+    pub fn new() -> Self {
+        ItemPatch { operations: Vec::new() }
+    }
+
+    pub fn get_operations(&self) -> &[PatchOperation] {
+        &self.operations
+    }
+    pub fn count(&self) -> usize {
+        self.operations.len()
+    }
+    pub fn push(&mut self, operation: PatchOperation) {
+        self.operations.push(operation);
+    }
+
+
+    // Works on a copy so a failing operation leaves the target untouched.
+    pub fn apply(&self, target: &mut ItemType) -> Result<(), PatchError> {
+        let mut working = target.clone();
+        for (index, operation) in self.operations.iter().enumerate() {
+            operation.apply(&mut working, index)?;
+        }
+        *target = working;
+        Ok(())
+    }
+
+
+    // Builds the operations that turn `old` into `new`. Lists are compared
+    // position by position, so an insertion near the front becomes a run
+    // of replacements.
+    pub fn create(old: &ItemType, new: &ItemType) -> ItemPatch {
+        let mut patch = ItemPatch::new();
+        generate(&mut ItemPointer::root(), old, new, &mut patch.operations);
+        patch
+    }
+
+
+    pub fn from_item(item: &ItemType) -> Result<ItemPatch, PatchError> {
+        let list = match item {
+            ItemType::TList(list) => list,
+            _ => return Err(PatchError::InvalidOperation(0)),
+        };
+        let mut operations = Vec::new();
+        for index in 0..list.count() {
+            let entry = list.get_item(index).ok().flatten().ok_or(PatchError::InvalidOperation(index))?;
+            operations.push(PatchOperation::from_item(entry, index)?);
+        }
+        Ok(ItemPatch { operations })
+    }
+
+    pub fn to_item(&self) -> ItemType {
+        let mut list = ItemList::new();
+        for operation in &self.operations {
+            list.add_item(operation.to_item(), None).ok();
+        }
+        list
+    }
+
+    pub fn export(&self, output: &mut dyn ItemOutput) -> Result<(), DocError> {
+        self.to_item().export(output)
+    }
+
+}
+
+fn generate(path: &mut ItemPointer, old: &ItemType, new: &ItemType, operations: &mut Vec<PatchOperation>) {
+    if old == new {
+        return;
+    }
+    match (old, new) {
+        (ItemType::THash(a), ItemType::THash(b)) => {
+            for key in a.get_keys().unwrap_or_default() {
+                if !b.has_key(key) {
+                    operations.push(PatchOperation::Remove { path: child(path, &key) });
+                }
+            }
+            for key in b.get_keys().unwrap_or_default() {
+                let value = b.get_item(key).ok().flatten();
+                match (a.get_item(key).ok().flatten(), value) {
+                    (Some(before), Some(after)) => {
+                        path.push_key(&key);
+                        generate(path, before, after, operations);
+                        path.pop();
+                    },
+                    (None, Some(after)) => {
+                        operations.push(PatchOperation::Add { path: child(path, &key), value: after.clone() });
+                    },
+                    _ => {},
+                }
+            }
+        },
+        (ItemType::TList(a), ItemType::TList(b)) => {
+            let common = a.count().min(b.count());
+            for index in 0..common {
+                if let (Ok(Some(before)), Ok(Some(after))) = (a.get_item(index), b.get_item(index)) {
+                    path.push_key(&ContainerKey::Idx(index));
+                    generate(path, before, after, operations);
+                    path.pop();
+                }
+            }
+            // remove from the back so earlier indexes stay valid
+            for index in (common..a.count()).rev() {
+                operations.push(PatchOperation::Remove { path: child(path, &ContainerKey::Idx(index)) });
+            }
+            for index in common..b.count() {
+                if let Ok(Some(after)) = b.get_item(index) {
+                    operations.push(PatchOperation::Add { path: child(path, &ContainerKey::Idx(index)), value: after.clone() });
+                }
+            }
+        },
+        _ => operations.push(PatchOperation::Replace { path: path.clone(), value: new.clone() }),
+    }
+}
+
+fn child<'a>(path: &ItemPointer, key: &ContainerKey<'a>) -> ItemPointer {
+    let mut pointer = path.clone();
+    pointer.push_key(key);
+    pointer
+}
@@ -0,0 +1,63 @@
+use crate::itemdoc::core::items::*;
+use crate::itemdoc::core::nulls::ItemNull;
+use crate::itemdoc::core::hashes::ItemHash;
+
+// Applies an RFC 7386 merge patch: hashes merge key by key, a null member
+// deletes the key and anything else replaces the target outright.
+pub fn merge_patch(target: &mut ItemType, patch: &ItemType) {
+    let members = match patch {
+        ItemType::THash(members) => members,
+        _ => {
+            *target = patch.clone();
+            return;
+        },
+    };
+    if !target.is_hash() {
+        *target = ItemHash::new();
+    }
+    for key in members.get_keys().unwrap_or_default() {
+        let (name, value) = match (key, members.get_item(key)) {
+            (ContainerKey::Key(name), Ok(Some(value))) => (name, value),
+            _ => continue,
+        };
+        if value.is_null() {
+            target.remove_item(ContainerKey::Key(name)).ok();
+        } else if let Ok(entry) = target.entry(name) {
+            merge_patch(entry.or_insert_null(), value);
+        }
+    }
+}
+
+// Builds the merge patch that turns `old` into `new`. Merge patches cannot
+// express a null value inside a hash, so such members are dropped when the
+// patch is applied.
+pub fn create_merge_patch(old: &ItemType, new: &ItemType) -> ItemType {
+    let (before, after) = match (old, new) {
+        (ItemType::THash(before), ItemType::THash(after)) => (before, after),
+        _ => return new.clone(),
+    };
+    let mut patch = ItemHash::new();
+    for key in before.get_keys().unwrap_or_default() {
+        if let ContainerKey::Key(name) = key {
+            if !after.has_key(name) {
+                patch.add_item(ItemNull::new(), Some(name)).ok();
+            }
+        }
+    }
+    for key in after.get_keys().unwrap_or_default() {
+        let (name, value) = match (key, after.get_item(key)) {
+            (ContainerKey::Key(name), Ok(Some(value))) => (name, value),
+            _ => continue,
+        };
+        match before.get_item(name) {
+            Ok(Some(previous)) if previous == value => {},
+            Ok(Some(previous)) => {
+                patch.add_item(create_merge_patch(previous, value), Some(name)).ok();
+            },
+            _ => {
+                patch.add_item(value.clone(), Some(name)).ok();
+            },
+        }
+    }
+    patch
+}
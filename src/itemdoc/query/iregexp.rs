@@ -0,0 +1,473 @@
+// A small matcher for RFC 9485 I-Regexp, the interoperable regular
+// expression subset used by the JSONPath match() and search() functions,
+// and for the slightly wider ECMA-262 subset that JSON Schema patterns use.
+// Patterns compile to a Thompson NFA that is run as a Pike VM, so matching
+// takes time linear in the text and never recurses.
+
+use super::unicode::GeneralCategory;
+
+// Bounds on what a pattern may ask for, since patterns come from documents
+// and queries: the largest {n,m} count, the deepest group nesting and the
+// largest compiled program. Every node expanded counts against the program
+// size, so repeated empty groups cannot stall compilation.
+const REPEAT_LIMIT: u32 = 1000;
+const NESTING_LIMIT: usize = 128;
+const PROGRAM_LIMIT: usize = 100_000;
+
+#[derive(Debug, Clone, PartialEq)]
+enum Node {
+    Char(char),
+    Any,
+    Class(Vec<ClassItem>, bool),
+    Start,
+    End,
+    Concat(Vec<Node>),
+    Alternate(Vec<Node>),
+    Repeat(Box<Node>, u32, Option<u32>),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum ClassItem {
+    Range(char, char),
+    Category(Category, bool),
+}
+
+// The \p{..} names RFC 9485 allows: a major class or one of its general
+// categories. Surrogates (Cs) are left out.
+const CATEGORY_NAMES: &[&str] = &[
+    "L", "Lu", "Ll", "Lt", "Lm", "Lo",
+    "M", "Mn", "Mc", "Me",
+    "N", "Nd", "Nl", "No",
+    "P", "Pc", "Pd", "Ps", "Pe", "Pi", "Pf", "Po",
+    "Z", "Zs", "Zl", "Zp",
+    "S", "Sm", "Sc", "Sk", "So",
+    "C", "Cc", "Cf", "Co", "Cn",
+];
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+enum Category {
+    General(&'static str),
+    // The ECMA-262 classes, which stay ASCII apart from whitespace.
+    Digit,
+    Word,
+    Space,
+}
+
+impl Category {
+    fn from_name(name: &str) -> Option<Category> {
+        CATEGORY_NAMES.iter().find(|known| **known == name).map(|known| Category::General(known))
+    }
+
+    fn contains(&self, c: char) -> bool {
+        match self {
+            Category::General(name) => GeneralCategory::of(c).name().starts_with(name),
+            Category::Digit => c.is_ascii_digit(),
+            Category::Word => c.is_ascii_alphanumeric() || c == '_',
+            Category::Space => c.is_whitespace() || c == '\u{feff}',
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Inst {
+    Char(char),
+    Any,
+    Class(Vec<ClassItem>, bool),
+    // Zero-width assertions on the start and end of the text.
+    Start,
+    End,
+    // Continues at both targets.
+    Split(usize, usize),
+    Jump(usize),
+    Match,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Regex {
+    program: Vec<Inst>,
+}
+
+impl Regex {
+
+    // Returns None when the pattern is not valid I-Regexp or goes past the
+    // repetition, nesting or size limits.
+    pub fn new(pattern: &str) -> Option<Regex> {
+        Regex::compile(pattern, false)
+    }
+
+    // The ECMA-262 subset accepted for JSON Schema patterns: I-Regexp plus
+    // the '^' and '$' anchors (anywhere, including inside alternatives), the
+    // \d, \w and \s classes and their negations, and escaped '$' and '/'.
+    // Backreferences, lookaround, lazy quantifiers and flags are not
+    // supported.
+    pub fn ecma(pattern: &str) -> Option<Regex> {
+        Regex::compile(pattern, true)
+    }
+
+    fn compile(pattern: &str, ecma: bool) -> Option<Regex> {
+        let chars: Vec<char> = pattern.chars().collect();
+        let mut parser = Parser { chars: &chars, pos: 0, depth: 0, ecma };
+        let root = parser.parse_alternation()?;
+        if parser.pos != chars.len() {
+            return None;
+        }
+        let mut compiler = Compiler { program: Vec::new(), expanded: 0 };
+        compiler.compile(&root)?;
+        compiler.emit(Inst::Match)?;
+        Some(Regex { program: compiler.program })
+    }
+
+    // True when the whole of text matches, as for match().
+    pub fn is_match(&self, text: &str) -> bool {
+        self.run(text, false)
+    }
+
+    // True when any substring of text matches, as for search().
+    pub fn is_found(&self, text: &str) -> bool {
+        self.run(text, true)
+    }
+
+    // Steps every live thread over one character at a time. A search starts
+    // a new thread at each position and succeeds as soon as one matches.
+    fn run(&self, text: &str, search: bool) -> bool {
+        let mut current = Threads::new(self.program.len());
+        let mut next = Threads::new(self.program.len());
+        let mut stack = Vec::new();
+        let mut chars = text.chars().peekable();
+        self.add(&mut current, 0, (true, chars.peek().is_none()), &mut stack);
+        while let Some(c) = chars.next() {
+            let edges = (false, chars.peek().is_none());
+            if search && current.matched {
+                return true;
+            }
+            next.clear();
+            for &pc in &current.pcs {
+                let accepts = match &self.program[pc] {
+                    Inst::Char(expected) => *expected == c,
+                    Inst::Any => c != '\n' && c != '\r',
+                    Inst::Class(items, negated) => class_contains(items, c) != *negated,
+                    _ => false,
+                };
+                if accepts {
+                    self.add(&mut next, pc + 1, edges, &mut stack);
+                }
+            }
+            if search {
+                self.add(&mut next, 0, edges, &mut stack);
+            }
+            std::mem::swap(&mut current, &mut next);
+            if current.pcs.is_empty() {
+                return false;
+            }
+        }
+        current.matched
+    }
+
+    // Adds the thread at pc and everything reachable from it without
+    // consuming a character; `edges` says whether the position is at the
+    // start and at the end of the text, for the anchors.
+    fn add(&self, threads: &mut Threads, pc: usize, edges: (bool, bool), stack: &mut Vec<usize>) {
+        stack.push(pc);
+        while let Some(pc) = stack.pop() {
+            if !threads.insert(pc) {
+                continue;
+            }
+            match &self.program[pc] {
+                Inst::Jump(to) => stack.push(*to),
+                Inst::Split(first, second) => {
+                    stack.push(*second);
+                    stack.push(*first);
+                },
+                Inst::Start if edges.0 => stack.push(pc + 1),
+                Inst::End if edges.1 => stack.push(pc + 1),
+                Inst::Match => threads.matched = true,
+                _ => {},
+            }
+        }
+    }
+
+}
+
+struct Threads {
+    pcs: Vec<usize>,
+    seen: Vec<bool>,
+    matched: bool,
+}
+
+impl Threads {
+    fn new(size: usize) -> Self {
+        Threads { pcs: Vec::new(), seen: vec![false; size], matched: false }
+    }
+
+    fn insert(&mut self, pc: usize) -> bool {
+        if self.seen[pc] {
+            return false;
+        }
+        self.seen[pc] = true;
+        self.pcs.push(pc);
+        true
+    }
+
+    fn clear(&mut self) {
+        for pc in self.pcs.drain(..) {
+            self.seen[pc] = false;
+        }
+        self.matched = false;
+    }
+}
+
+struct Compiler {
+    program: Vec<Inst>,
+    expanded: usize,
+}
+
+impl Compiler {
+    fn emit(&mut self, inst: Inst) -> Option<usize> {
+        if self.program.len() >= PROGRAM_LIMIT {
+            return None;
+        }
+        self.program.push(inst);
+        Some(self.program.len() - 1)
+    }
+
+    // Splits are emitted with placeholder targets and patched once the
+    // position after their body is known.
+    fn compile(&mut self, node: &Node) -> Option<()> {
+        self.expanded += 1;
+        if self.expanded > PROGRAM_LIMIT {
+            return None;
+        }
+        match node {
+            Node::Char(c) => { self.emit(Inst::Char(*c))?; },
+            Node::Any => { self.emit(Inst::Any)?; },
+            Node::Class(items, negated) => { self.emit(Inst::Class(items.clone(), *negated))?; },
+            Node::Start => { self.emit(Inst::Start)?; },
+            Node::End => { self.emit(Inst::End)?; },
+            Node::Concat(nodes) => {
+                for node in nodes {
+                    self.compile(node)?;
+                }
+            },
+            Node::Alternate(branches) => {
+                let mut jumps = Vec::new();
+                for (i, branch) in branches.iter().enumerate() {
+                    if i + 1 == branches.len() {
+                        self.compile(branch)?;
+                        break;
+                    }
+                    let split = self.emit(Inst::Split(0, 0))?;
+                    self.compile(branch)?;
+                    jumps.push(self.emit(Inst::Jump(0))?);
+                    self.program[split] = Inst::Split(split + 1, self.program.len());
+                }
+                let end = self.program.len();
+                for jump in jumps {
+                    self.program[jump] = Inst::Jump(end);
+                }
+            },
+            Node::Repeat(inner, min, max) => {
+                for _ in 0..*min {
+                    self.compile(inner)?;
+                }
+                match max {
+                    None => {
+                        let split = self.emit(Inst::Split(0, 0))?;
+                        self.compile(inner)?;
+                        self.emit(Inst::Jump(split))?;
+                        self.program[split] = Inst::Split(split + 1, self.program.len());
+                    },
+                    Some(max) => {
+                        let mut splits = Vec::new();
+                        for _ in *min..*max {
+                            splits.push(self.emit(Inst::Split(0, 0))?);
+                            self.compile(inner)?;
+                        }
+                        let end = self.program.len();
+                        for split in splits {
+                            self.program[split] = Inst::Split(split + 1, end);
+                        }
+                    },
+                }
+            },
+        }
+        Some(())
+    }
+}
+
+struct Parser<'a> {
+    chars: &'a [char],
+    pos: usize,
+    depth: usize,
+    ecma: bool,
+}
+
+impl<'a> Parser<'a> {
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.pos).copied()
+    }
+
+    fn next(&mut self) -> Option<char> {
+        let c = self.peek()?;
+        self.pos += 1;
+        Some(c)
+    }
+
+    fn parse_alternation(&mut self) -> Option<Node> {
+        let mut branches = vec![self.parse_concat()?];
+        while self.peek() == Some('|') {
+            self.pos += 1;
+            branches.push(self.parse_concat()?);
+        }
+        Some(if branches.len() == 1 { branches.remove(0) } else { Node::Alternate(branches) })
+    }
+
+    fn parse_concat(&mut self) -> Option<Node> {
+        let mut nodes = Vec::new();
+        while let Some(c) = self.peek() {
+            if c == '|' || c == ')' {
+                break;
+            }
+            let atom = self.parse_atom()?;
+            if matches!(atom, Node::Start | Node::End) {
+                nodes.push(atom);
+                continue;
+            }
+            nodes.push(self.parse_quantifier(atom)?);
+        }
+        Some(Node::Concat(nodes))
+    }
+
+    fn parse_atom(&mut self) -> Option<Node> {
+        match self.next()? {
+            '(' => {
+                self.depth += 1;
+                if self.depth > NESTING_LIMIT {
+                    return None;
+                }
+                let inner = self.parse_alternation()?;
+                self.depth -= 1;
+                (self.next()? == ')').then_some(inner)
+            },
+            '.' => Some(Node::Any),
+            '^' if self.ecma => Some(Node::Start),
+            '$' if self.ecma => Some(Node::End),
+            '[' => self.parse_class(),
+            '\\' => self.parse_escape().map(|item| match item {
+                ClassItem::Range(c, _) => Node::Char(c),
+                category => Node::Class(vec![category], false),
+            }),
+            ')' | ']' | '{' | '}' | '*' | '+' | '?' => None,
+            c => Some(Node::Char(c)),
+        }
+    }
+
+    fn parse_quantifier(&mut self, atom: Node) -> Option<Node> {
+        let (min, max) = match self.peek() {
+            Some('*') => (0, None),
+            Some('+') => (1, None),
+            Some('?') => (0, Some(1)),
+            Some('{') => {
+                self.pos += 1;
+                let min = self.parse_count()?;
+                let max = if self.peek() == Some(',') {
+                    self.pos += 1;
+                    if self.peek() == Some('}') { None } else { Some(self.parse_count()?) }
+                } else {
+                    Some(min)
+                };
+                if self.peek() != Some('}') || max.is_some_and(|m| m < min) {
+                    return None;
+                }
+                (min, max)
+            },
+            _ => return Some(atom),
+        };
+        self.pos += 1;
+        Some(Node::Repeat(Box::new(atom), min, max))
+    }
+
+    fn parse_count(&mut self) -> Option<u32> {
+        let start = self.pos;
+        while self.peek().is_some_and(|c| c.is_ascii_digit()) {
+            self.pos += 1;
+        }
+        let count = self.chars[start..self.pos].iter().collect::<String>().parse().ok()?;
+        (count <= REPEAT_LIMIT).then_some(count)
+    }
+
+    fn parse_class(&mut self) -> Option<Node> {
+        let negated = self.peek() == Some('^');
+        if negated {
+            self.pos += 1;
+        }
+        let mut items = Vec::new();
+        loop {
+            let item = match self.next()? {
+                ']' => break,
+                '\\' => self.parse_escape()?,
+                '[' => return None,
+                c => ClassItem::Range(c, c),
+            };
+            let item = match (item, self.peek(), self.chars.get(self.pos + 1)) {
+                (ClassItem::Range(low, _), Some('-'), Some(next)) if *next != ']' => {
+                    self.pos += 1;
+                    let high = match self.next()? {
+                        '\\' => match self.parse_escape()? {
+                            ClassItem::Range(c, _) => c,
+                            _ => return None,
+                        },
+                        c => c,
+                    };
+                    if high < low {
+                        return None;
+                    }
+                    ClassItem::Range(low, high)
+                },
+                (item, _, _) => item,
+            };
+            items.push(item);
+        }
+        Some(Node::Class(items, negated))
+    }
+
+    fn parse_escape(&mut self) -> Option<ClassItem> {
+        let c = match self.next()? {
+            'n' => '\n',
+            'r' => '\r',
+            't' => '\t',
+            'p' | 'P' => {
+                let negated = self.chars[self.pos - 1] == 'P';
+                if self.next()? != '{' {
+                    return None;
+                }
+                let start = self.pos;
+                while self.peek().is_some_and(|c| c != '}') {
+                    self.pos += 1;
+                }
+                let name: String = self.chars[start..self.pos].iter().collect();
+                self.next()?;
+                return Category::from_name(&name).map(|category| ClassItem::Category(category, negated));
+            },
+            'd' | 'D' | 'w' | 'W' | 's' | 'S' if self.ecma => {
+                let c = self.chars[self.pos - 1];
+                let category = match c.to_ascii_lowercase() {
+                    'd' => Category::Digit,
+                    'w' => Category::Word,
+                    _ => Category::Space,
+                };
+                return Some(ClassItem::Category(category, c.is_ascii_uppercase()));
+            },
+            c if "\\|.-^?*+{}()[]".contains(c) => c,
+            c @ ('$' | '/') if self.ecma => c,
+            _ => return None,
+        };
+        Some(ClassItem::Range(c, c))
+    }
+}
+
+fn class_contains(items: &[ClassItem], c: char) -> bool {
+    items.iter().any(|item| match item {
+        ClassItem::Range(low, high) => *low <= c && c <= *high,
+        ClassItem::Category(category, negated) => category.contains(c) != *negated,
+    })
+}
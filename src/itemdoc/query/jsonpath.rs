@@ -0,0 +1,1003 @@
+use std::cmp::Ordering;
+use std::fmt;
+use std::str::FromStr;
+
+use crate::itemdoc::core::items::*;
+use crate::itemdoc::core::nulls::ItemNull;
+use crate::itemdoc::core::booleans::ItemBoolean;
+use crate::itemdoc::core::numbers::{ItemNumber, NumberValue};
+use crate::itemdoc::core::strings::ItemString;
+use crate::itemdoc::core::pointers::ItemPointer;
+use crate::itemdoc::imports::core::SourcePosition;
+
+use super::iregexp::Regex;
+
+// Indexes are limited to the I-JSON exact integer range.
+const MAX_INDEX: i64 = (1 << 53) - 1;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum PathElement {
+    Index(usize),
+    Name(String),
+}
+
+// The location of a selected node, printed as an RFC 9535 normalized path
+// such as $['items'][0]['price'].
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct QueryPath {
+    elements: Vec<PathElement>,
+}
+
+impl QueryPath {
+
+    pub fn root() -> Self {
+        QueryPath { elements: Vec::new() }
+    }
+
+    pub fn get_elements(&self) -> &[PathElement] {
+        &self.elements
+    }
+
+    pub fn to_pointer(&self) -> ItemPointer {
+        ItemPointer::from_segments(self.elements.iter().map(|element| match element {
+            PathElement::Index(i) => i.to_string(),
+            PathElement::Name(name) => name.clone(),
+        }))
+    }
+
+    fn child(&self, element: PathElement) -> QueryPath {
+        let mut path = self.clone();
+        path.elements.push(element);
+        path
+    }
+
+}
+
+impl fmt::Display for QueryPath {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "$")?;
+        for element in &self.elements {
+            match element {
+                PathElement::Index(i) => write!(f, "[{}]", i)?,
+                PathElement::Name(name) => {
+                    write!(f, "['")?;
+                    for c in name.chars() {
+                        match c {
+                            '\u{8}' => write!(f, "\\b")?,
+                            '\u{c}' => write!(f, "\\f")?,
+                            '\n' => write!(f, "\\n")?,
+                            '\r' => write!(f, "\\r")?,
+                            '\t' => write!(f, "\\t")?,
+                            '\'' => write!(f, "\\'")?,
+                            '\\' => write!(f, "\\\\")?,
+                            c if (c as u32) < 0x20 => write!(f, "\\u{:04x}", c as u32)?,
+                            c => write!(f, "{}", c)?,
+                        }
+                    }
+                    write!(f, "']")?;
+                },
+            }
+        }
+        Ok(())
+    }
+}
+
+pub struct QueryMatch<'a> {
+    pub path: QueryPath,
+    pub item: &'a ItemType,
+}
+
+enum Segment {
+    Child(Vec<Selector>),
+    Descendant(Vec<Selector>),
+}
+
+enum Selector {
+    Name(String),
+    Wildcard,
+    Index(i64),
+    Slice(Option<i64>, Option<i64>, Option<i64>),
+    Filter(Expr),
+}
+
+enum Expr {
+    Or(Vec<Expr>),
+    And(Vec<Expr>),
+    Not(Box<Expr>),
+    Compare(Operand, CompareOp, Operand),
+    Test(Operand),
+}
+
+#[derive(Copy, Clone, PartialEq)]
+enum CompareOp {
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+}
+
+enum Operand {
+    Literal(ItemType),
+    Query(FilterQuery),
+    Function(Function),
+}
+
+struct FilterQuery {
+    relative: bool,
+    segments: Vec<Segment>,
+}
+
+impl FilterQuery {
+    fn is_singular(&self) -> bool {
+        self.segments.iter().all(|segment| match segment {
+            Segment::Child(selectors) => {
+                selectors.len() == 1 && matches!(selectors[0], Selector::Name(_) | Selector::Index(_))
+            },
+            Segment::Descendant(_) => false,
+        })
+    }
+}
+
+#[derive(Copy, Clone, PartialEq)]
+enum FunctionKind {
+    Length,
+    Count,
+    Match,
+    Search,
+    Value,
+}
+
+#[derive(Copy, Clone, PartialEq)]
+enum FunctionType {
+    Value,
+    Logical,
+    Nodes,
+}
+
+impl FunctionKind {
+    fn from_name(name: &str) -> Option<FunctionKind> {
+        match name {
+            "length" => Some(FunctionKind::Length),
+            "count" => Some(FunctionKind::Count),
+            "match" => Some(FunctionKind::Match),
+            "search" => Some(FunctionKind::Search),
+            "value" => Some(FunctionKind::Value),
+            _ => None,
+        }
+    }
+
+    fn parameters(&self) -> &'static [FunctionType] {
+        match self {
+            FunctionKind::Length => &[FunctionType::Value],
+            FunctionKind::Count | FunctionKind::Value => &[FunctionType::Nodes],
+            FunctionKind::Match | FunctionKind::Search => &[FunctionType::Value, FunctionType::Value],
+        }
+    }
+
+    fn result(&self) -> FunctionType {
+        match self {
+            FunctionKind::Match | FunctionKind::Search => FunctionType::Logical,
+            _ => FunctionType::Value,
+        }
+    }
+}
+
+struct Function {
+    kind: FunctionKind,
+    args: Vec<Operand>,
+    // a literal pattern for match() or search() is compiled only once
+    pattern: Option<Regex>,
+}
+
+#[derive(Debug)]
+#[derive(Copy, Clone, PartialEq)]
+pub enum QueryErrorKind {
+    UnexpectedEndOfInput,
+    UnexpectedCharacter(char),
+    TrailingCharacters,
+    InvalidNumber,
+    InvalidEscape,
+    InvalidUnicode,
+    ControlCharacter,
+    InvalidExpression,
+    UnknownFunction,
+}
+
+impl fmt::Display for QueryErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            QueryErrorKind::UnexpectedEndOfInput => write!(f, "Unexpected end of query!"),
+            QueryErrorKind::UnexpectedCharacter(c) => write!(f, "Unexpected character {:?}!", c),
+            QueryErrorKind::TrailingCharacters => write!(f, "Trailing characters after query!"),
+            QueryErrorKind::InvalidNumber => write!(f, "Invalid number!"),
+            QueryErrorKind::InvalidEscape => write!(f, "Invalid escape sequence!"),
+            QueryErrorKind::InvalidUnicode => write!(f, "Invalid unicode escape!"),
+            QueryErrorKind::ControlCharacter => write!(f, "Unescaped control character!"),
+            QueryErrorKind::InvalidExpression => write!(f, "Invalid expression!"),
+            QueryErrorKind::UnknownFunction => write!(f, "Unknown function!"),
+        }
+    }
+}
+
+// Where and why a query failed to compile; positions are within the query
+// text.
+#[derive(Debug)]
+#[derive(Copy, Clone, PartialEq)]
+pub struct QueryError {
+    pub kind: QueryErrorKind,
+    pub offset: usize,
+    pub line: usize,
+    pub column: usize,
+}
+
+impl fmt::Display for QueryError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} (line {}, column {}, offset {})", self.kind, self.line, self.column, self.offset)
+    }
+}
+
+impl std::error::Error for QueryError {}
+
+// A compiled RFC 9535 JSONPath expression.
+pub struct Query {
+    segments: Vec<Segment>,
+}
+
+impl Query {
+
+    pub fn compile(text: &str) -> Result<Query, QueryError> {
+        let mut parser = Parser { text, pos: 0 };
+        parser.expect('$')?;
+        let segments = parser.parse_segments()?;
+        if parser.pos != text.len() {
+            return Err(parser.error(QueryErrorKind::TrailingCharacters));
+        }
+        Ok(Query { segments })
+    }
+
+    pub fn select<'a>(&self, root: &'a ItemType) -> Vec<QueryMatch<'a>> {
+        evaluate(&self.segments, root, root)
+            .into_iter()
+            .map(|(path, item)| QueryMatch { path, item })
+            .collect()
+    }
+
+    pub fn select_items<'a>(&self, root: &'a ItemType) -> Vec<&'a ItemType> {
+        evaluate(&self.segments, root, root).into_iter().map(|(_, item)| item).collect()
+    }
+
+    pub fn select_paths(&self, root: &ItemType) -> Vec<QueryPath> {
+        evaluate(&self.segments, root, root).into_iter().map(|(path, _)| path).collect()
+    }
+
+    // Visits every selected node mutably, one at a time, and returns how
+    // many were visited. The paths are resolved up front, so descendants are
+    // visited before their ancestors and later list members before earlier
+    // ones; an update may then reshape its own node without moving a path
+    // that is still to come.
+    pub fn apply_mut<F>(&self, root: &mut ItemType, mut update: F) -> usize
+    where
+        F: FnMut(&QueryPath, &mut ItemType),
+    {
+        let mut paths = self.select_paths(root);
+        paths.sort_by(visit_order);
+        let mut visited = 0;
+        for path in paths {
+            if let Ok(item) = root.pointer_mut(&path.to_pointer()) {
+                update(&path, item);
+                visited += 1;
+            }
+        }
+        visited
+    }
+
+}
+
+// Orders the deeper of two nested paths and the higher of two sibling
+// indexes first; sibling names only need a stable order.
+fn visit_order(a: &QueryPath, b: &QueryPath) -> Ordering {
+    for (x, y) in a.elements.iter().zip(&b.elements) {
+        let order = match (x, y) {
+            (PathElement::Index(i), PathElement::Index(j)) => j.cmp(i),
+            (PathElement::Name(m), PathElement::Name(n)) => m.cmp(n),
+            (PathElement::Index(_), PathElement::Name(_)) => Ordering::Less,
+            (PathElement::Name(_), PathElement::Index(_)) => Ordering::Greater,
+        };
+        if order != Ordering::Equal {
+            return order;
+        }
+    }
+    b.elements.len().cmp(&a.elements.len())
+}
+
+impl FromStr for Query {
+    type Err = QueryError;
+
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        Query::compile(text)
+    }
+}
+
+struct Parser<'a> {
+    text: &'a str,
+    pos: usize,
+}
+
+impl<'a> Parser<'a> {
+
+    fn peek(&self) -> Option<char> {
+        self.text[self.pos..].chars().next()
+    }
+
+    fn bump(&mut self) -> Option<char> {
+        let c = self.peek()?;
+        self.pos += c.len_utf8();
+        Some(c)
+    }
+
+    fn error(&self, kind: QueryErrorKind) -> QueryError {
+        self.error_at(self.pos, kind)
+    }
+
+    fn error_at(&self, offset: usize, kind: QueryErrorKind) -> QueryError {
+        let position = SourcePosition::from_offset(self.text, offset);
+        QueryError { kind, offset: position.offset, line: position.line, column: position.column }
+    }
+
+    fn unexpected(&self) -> QueryError {
+        match self.peek() {
+            Some(c) => self.error(QueryErrorKind::UnexpectedCharacter(c)),
+            None => self.error(QueryErrorKind::UnexpectedEndOfInput),
+        }
+    }
+
+    fn expect(&mut self, expected: char) -> Result<(), QueryError> {
+        if self.peek() == Some(expected) {
+            self.pos += expected.len_utf8();
+            Ok(())
+        } else {
+            Err(self.unexpected())
+        }
+    }
+
+    fn skip_blank(&mut self) {
+        while let Some(' ' | '\t' | '\n' | '\r') = self.peek() {
+            self.pos += 1;
+        }
+    }
+
+
+    fn parse_segments(&mut self) -> Result<Vec<Segment>, QueryError> {
+        let mut segments = Vec::new();
+        loop {
+            let save = self.pos;
+            self.skip_blank();
+            match self.peek() {
+                Some('[') => segments.push(Segment::Child(self.parse_bracketed()?)),
+                Some('.') => {
+                    self.pos += 1;
+                    let descendant = self.peek() == Some('.');
+                    if descendant {
+                        self.pos += 1;
+                    }
+                    let selectors = match self.peek() {
+                        Some('[') if descendant => self.parse_bracketed()?,
+                        Some('*') => {
+                            self.pos += 1;
+                            vec![Selector::Wildcard]
+                        },
+                        _ => vec![Selector::Name(self.parse_member_name()?)],
+                    };
+                    segments.push(if descendant { Segment::Descendant(selectors) } else { Segment::Child(selectors) });
+                },
+                _ => {
+                    self.pos = save;
+                    return Ok(segments);
+                },
+            }
+        }
+    }
+
+    fn parse_member_name(&mut self) -> Result<String, QueryError> {
+        let start = self.pos;
+        match self.peek() {
+            Some(c) if c.is_ascii_alphabetic() || c == '_' || !c.is_ascii() => {},
+            _ => return Err(self.unexpected()),
+        }
+        while let Some(c) = self.peek() {
+            if !(c.is_ascii_alphanumeric() || c == '_' || !c.is_ascii()) {
+                break;
+            }
+            self.pos += c.len_utf8();
+        }
+        Ok(self.text[start..self.pos].to_string())
+    }
+
+    fn parse_bracketed(&mut self) -> Result<Vec<Selector>, QueryError> {
+        self.expect('[')?;
+        let mut selectors = Vec::new();
+        loop {
+            self.skip_blank();
+            selectors.push(self.parse_selector()?);
+            self.skip_blank();
+            match self.peek() {
+                Some(',') => self.pos += 1,
+                Some(']') => {
+                    self.pos += 1;
+                    return Ok(selectors);
+                },
+                _ => return Err(self.unexpected()),
+            }
+        }
+    }
+
+    fn parse_selector(&mut self) -> Result<Selector, QueryError> {
+        match self.peek() {
+            Some('\'' | '"') => Ok(Selector::Name(self.parse_string_literal()?)),
+            Some('*') => {
+                self.pos += 1;
+                Ok(Selector::Wildcard)
+            },
+            Some('?') => {
+                self.pos += 1;
+                self.skip_blank();
+                Ok(Selector::Filter(self.parse_logical_or()?))
+            },
+            Some('-' | '0'..='9' | ':') => {
+                let start = if self.peek() == Some(':') { None } else { Some(self.parse_int()?) };
+                self.skip_blank();
+                if self.peek() != Some(':') {
+                    return Ok(Selector::Index(start.unwrap_or_default()));
+                }
+                self.pos += 1;
+                self.skip_blank();
+                let end = self.parse_optional_int()?;
+                self.skip_blank();
+                let mut step = None;
+                if self.peek() == Some(':') {
+                    self.pos += 1;
+                    self.skip_blank();
+                    step = self.parse_optional_int()?;
+                }
+                Ok(Selector::Slice(start, end, step))
+            },
+            _ => Err(self.unexpected()),
+        }
+    }
+
+    fn parse_optional_int(&mut self) -> Result<Option<i64>, QueryError> {
+        match self.peek() {
+            Some('-' | '0'..='9') => Ok(Some(self.parse_int()?)),
+            _ => Ok(None),
+        }
+    }
+
+    fn parse_int(&mut self) -> Result<i64, QueryError> {
+        let start = self.pos;
+        if self.peek() == Some('-') {
+            self.pos += 1;
+        }
+        let digits = self.pos;
+        while let Some('0'..='9') = self.peek() {
+            self.pos += 1;
+        }
+        let text = &self.text[start..self.pos];
+        let body = &self.text[digits..self.pos];
+        if body.is_empty() || (body.len() > 1 && body.starts_with('0')) || text == "-0" {
+            return Err(self.error_at(start, QueryErrorKind::InvalidNumber));
+        }
+        match text.parse::<i64>() {
+            Ok(n) if (-MAX_INDEX..=MAX_INDEX).contains(&n) => Ok(n),
+            _ => Err(self.error_at(start, QueryErrorKind::InvalidNumber)),
+        }
+    }
+
+    fn parse_string_literal(&mut self) -> Result<String, QueryError> {
+        let quote = self.bump().ok_or_else(|| self.unexpected())?;
+        let mut value = String::new();
+        loop {
+            let c = self.bump().ok_or_else(|| self.error(QueryErrorKind::UnexpectedEndOfInput))?;
+            match c {
+                c if c == quote => return Ok(value),
+                '\\' => {
+                    let escape = self.bump().ok_or_else(|| self.error(QueryErrorKind::UnexpectedEndOfInput))?;
+                    value.push(match escape {
+                        'b' => '\u{8}',
+                        'f' => '\u{c}',
+                        'n' => '\n',
+                        'r' => '\r',
+                        't' => '\t',
+                        '/' => '/',
+                        '\\' => '\\',
+                        'u' => self.parse_unicode_escape()?,
+                        c if c == quote => c,
+                        _ => return Err(self.error(QueryErrorKind::InvalidEscape)),
+                    });
+                },
+                c if (c as u32) < 0x20 => return Err(self.error(QueryErrorKind::ControlCharacter)),
+                c => value.push(c),
+            }
+        }
+    }
+
+    fn parse_hex4(&mut self) -> Result<u32, QueryError> {
+        let hex = self.text.get(self.pos..self.pos + 4)
+            .ok_or_else(|| self.error(QueryErrorKind::InvalidUnicode))?;
+        let code = u32::from_str_radix(hex, 16).map_err(|_| self.error(QueryErrorKind::InvalidUnicode))?;
+        self.pos += 4;
+        Ok(code)
+    }
+
+    fn parse_unicode_escape(&mut self) -> Result<char, QueryError> {
+        let high = self.parse_hex4()?;
+        let code = match high {
+            0xD800..=0xDBFF => {
+                if !self.text[self.pos..].starts_with("\\u") {
+                    return Err(self.error(QueryErrorKind::InvalidUnicode));
+                }
+                self.pos += 2;
+                let low = self.parse_hex4()?;
+                if !(0xDC00..=0xDFFF).contains(&low) {
+                    return Err(self.error(QueryErrorKind::InvalidUnicode));
+                }
+                0x10000 + ((high - 0xD800) << 10) + (low - 0xDC00)
+            },
+            0xDC00..=0xDFFF => return Err(self.error(QueryErrorKind::InvalidUnicode)),
+            code => code,
+        };
+        char::from_u32(code).ok_or_else(|| self.error(QueryErrorKind::InvalidUnicode))
+    }
+
+
+    fn parse_logical_or(&mut self) -> Result<Expr, QueryError> {
+        let mut terms = vec![self.parse_logical_and()?];
+        while self.skip_operator("||") {
+            terms.push(self.parse_logical_and()?);
+        }
+        Ok(if terms.len() == 1 { terms.remove(0) } else { Expr::Or(terms) })
+    }
+
+    fn parse_logical_and(&mut self) -> Result<Expr, QueryError> {
+        let mut terms = vec![self.parse_basic()?];
+        while self.skip_operator("&&") {
+            terms.push(self.parse_basic()?);
+        }
+        Ok(if terms.len() == 1 { terms.remove(0) } else { Expr::And(terms) })
+    }
+
+    // Consumes an operator surrounded by optional blanks, or nothing at all.
+    fn skip_operator(&mut self, operator: &str) -> bool {
+        let save = self.pos;
+        self.skip_blank();
+        if self.text[self.pos..].starts_with(operator) {
+            self.pos += operator.len();
+            self.skip_blank();
+            true
+        } else {
+            self.pos = save;
+            false
+        }
+    }
+
+    fn parse_paren(&mut self) -> Result<Expr, QueryError> {
+        self.expect('(')?;
+        self.skip_blank();
+        let expr = self.parse_logical_or()?;
+        self.skip_blank();
+        self.expect(')')?;
+        Ok(expr)
+    }
+
+    fn parse_basic(&mut self) -> Result<Expr, QueryError> {
+        if self.peek() == Some('!') {
+            self.pos += 1;
+            self.skip_blank();
+            if self.peek() == Some('(') {
+                return Ok(Expr::Not(Box::new(self.parse_paren()?)));
+            }
+            let start = self.pos;
+            let operand = self.parse_operand()?;
+            return Ok(Expr::Not(Box::new(self.test_expr(operand, start)?)));
+        }
+        if self.peek() == Some('(') {
+            return self.parse_paren();
+        }
+
+        let start = self.pos;
+        let left = self.parse_operand()?;
+        let save = self.pos;
+        self.skip_blank();
+        let op = match self.peek() {
+            Some('=') if self.text[self.pos..].starts_with("==") => Some(CompareOp::Eq),
+            Some('!') if self.text[self.pos..].starts_with("!=") => Some(CompareOp::Ne),
+            Some('<') if self.text[self.pos..].starts_with("<=") => Some(CompareOp::Le),
+            Some('>') if self.text[self.pos..].starts_with(">=") => Some(CompareOp::Ge),
+            Some('<') => Some(CompareOp::Lt),
+            Some('>') => Some(CompareOp::Gt),
+            _ => None,
+        };
+        match op {
+            Some(op) => {
+                self.pos += if matches!(op, CompareOp::Lt | CompareOp::Gt) { 1 } else { 2 };
+                self.skip_blank();
+                let right_start = self.pos;
+                let right = self.parse_operand()?;
+                let left = self.comparable(left, start)?;
+                let right = self.comparable(right, right_start)?;
+                Ok(Expr::Compare(left, op, right))
+            },
+            None => {
+                self.pos = save;
+                self.test_expr(left, start)
+            },
+        }
+    }
+
+    // Only a query or a logical function can stand alone as a test.
+    fn test_expr(&self, operand: Operand, start: usize) -> Result<Expr, QueryError> {
+        match &operand {
+            Operand::Query(_) => Ok(Expr::Test(operand)),
+            Operand::Function(f) if f.kind.result() != FunctionType::Value => Ok(Expr::Test(operand)),
+            _ => Err(self.error_at(start, QueryErrorKind::InvalidExpression)),
+        }
+    }
+
+    // Comparisons need single values: literals, singular queries and
+    // functions returning a value.
+    fn comparable(&self, operand: Operand, start: usize) -> Result<Operand, QueryError> {
+        match &operand {
+            Operand::Literal(_) => Ok(operand),
+            Operand::Query(query) if query.is_singular() => Ok(operand),
+            Operand::Function(f) if f.kind.result() == FunctionType::Value => Ok(operand),
+            _ => Err(self.error_at(start, QueryErrorKind::InvalidExpression)),
+        }
+    }
+
+    fn parse_operand(&mut self) -> Result<Operand, QueryError> {
+        match self.peek() {
+            Some(c @ ('@' | '$')) => {
+                self.pos += 1;
+                let segments = self.parse_segments()?;
+                Ok(Operand::Query(FilterQuery { relative: c == '@', segments }))
+            },
+            Some('\'' | '"') => Ok(Operand::Literal(ItemString::new(Some(self.parse_string_literal()?)))),
+            Some('-' | '0'..='9') => self.parse_number_literal(),
+            Some('a'..='z') => {
+                let start = self.pos;
+                while let Some('a'..='z' | '0'..='9' | '_') = self.peek() {
+                    self.pos += 1;
+                }
+                let name = &self.text[start..self.pos];
+                if self.peek() == Some('(') {
+                    return self.parse_function(name, start);
+                }
+                match name {
+                    "true" => Ok(Operand::Literal(ItemBoolean::new(Some(true)))),
+                    "false" => Ok(Operand::Literal(ItemBoolean::new(Some(false)))),
+                    "null" => Ok(Operand::Literal(ItemNull::new())),
+                    _ => Err(self.error_at(start, QueryErrorKind::InvalidExpression)),
+                }
+            },
+            _ => Err(self.unexpected()),
+        }
+    }
+
+    // number = (int / "-0") [ frac ] [ exp ], as in RFC 9535.
+    fn parse_number_literal(&mut self) -> Result<Operand, QueryError> {
+        let start = self.pos;
+        let invalid = |parser: &Self| parser.error_at(start, QueryErrorKind::InvalidNumber);
+        if self.peek() == Some('-') {
+            self.pos += 1;
+        }
+        match self.peek() {
+            Some('0') => self.pos += 1,
+            Some('1'..='9') => self.skip_digits(),
+            _ => return Err(invalid(self)),
+        }
+        if self.peek() == Some('.') {
+            self.pos += 1;
+            if !matches!(self.peek(), Some('0'..='9')) {
+                return Err(invalid(self));
+            }
+            self.skip_digits();
+        }
+        if let Some('e' | 'E') = self.peek() {
+            self.pos += 1;
+            if let Some('+' | '-') = self.peek() {
+                self.pos += 1;
+            }
+            if !matches!(self.peek(), Some('0'..='9')) {
+                return Err(invalid(self));
+            }
+            self.skip_digits();
+        }
+        let number = NumberValue::parse(&self.text[start..self.pos]).ok_or_else(|| invalid(self))?;
+        Ok(Operand::Literal(ItemNumber::with_value(number)))
+    }
+
+    fn skip_digits(&mut self) {
+        while let Some('0'..='9') = self.peek() {
+            self.pos += 1;
+        }
+    }
+
+    fn parse_function(&mut self, name: &str, start: usize) -> Result<Operand, QueryError> {
+        let kind = FunctionKind::from_name(name)
+            .ok_or_else(|| self.error_at(start, QueryErrorKind::UnknownFunction))?;
+        self.expect('(')?;
+        self.skip_blank();
+        let mut args = Vec::new();
+        if self.peek() != Some(')') {
+            loop {
+                let arg_start = self.pos;
+                let operand = self.parse_operand()?;
+                let accepted = match (kind.parameters().get(args.len()), &operand) {
+                    (Some(FunctionType::Value), Operand::Literal(_)) => true,
+                    (Some(FunctionType::Value), Operand::Query(query)) => query.is_singular(),
+                    (Some(FunctionType::Value), Operand::Function(f)) => f.kind.result() == FunctionType::Value,
+                    (Some(FunctionType::Nodes), Operand::Query(_)) => true,
+                    _ => false,
+                };
+                if !accepted {
+                    return Err(self.error_at(arg_start, QueryErrorKind::InvalidExpression));
+                }
+                args.push(operand);
+                self.skip_blank();
+                match self.peek() {
+                    Some(',') => {
+                        self.pos += 1;
+                        self.skip_blank();
+                    },
+                    Some(')') => break,
+                    _ => return Err(self.unexpected()),
+                }
+            }
+        }
+        self.expect(')')?;
+        if args.len() != kind.parameters().len() {
+            return Err(self.error_at(start, QueryErrorKind::InvalidExpression));
+        }
+        let pattern = match args.get(1) {
+            Some(Operand::Literal(ItemType::TString(s))) => Regex::new(s.get_value()),
+            _ => None,
+        };
+        Ok(Operand::Function(Function { kind, args, pattern }))
+    }
+
+}
+
+fn children(item: &ItemType) -> Vec<(PathElement, &ItemType)> {
+    let keys = match item.get_keys() {
+        Ok(keys) => keys,
+        Err(_) => return Vec::new(),
+    };
+    keys.into_iter()
+        .filter_map(|key| {
+            let element = match key {
+                ContainerKey::Idx(i) => PathElement::Index(i),
+                ContainerKey::Key(k) => PathElement::Name(k.to_string()),
+            };
+            item.get_item(key).ok().flatten().map(|child| (element, child))
+        })
+        .collect()
+}
+
+fn descendants<'a>(path: QueryPath, item: &'a ItemType, visited: &mut Vec<(QueryPath, &'a ItemType)>) {
+    visited.push((path.clone(), item));
+    for (element, child) in children(item) {
+        descendants(path.child(element), child, visited);
+    }
+}
+
+fn evaluate<'a>(segments: &[Segment], root: &'a ItemType, start: &'a ItemType) -> Vec<(QueryPath, &'a ItemType)> {
+    let mut nodes = vec![(QueryPath::root(), start)];
+    for segment in segments {
+        let mut selected = Vec::new();
+        for (path, item) in nodes {
+            match segment {
+                Segment::Child(selectors) => {
+                    for selector in selectors {
+                        select(selector, root, &path, item, &mut selected);
+                    }
+                },
+                Segment::Descendant(selectors) => {
+                    let mut visited = Vec::new();
+                    descendants(path, item, &mut visited);
+                    for (path, item) in visited {
+                        for selector in selectors {
+                            select(selector, root, &path, item, &mut selected);
+                        }
+                    }
+                },
+            }
+        }
+        nodes = selected;
+    }
+    nodes
+}
+
+fn select<'a>(
+    selector: &Selector,
+    root: &'a ItemType,
+    path: &QueryPath,
+    item: &'a ItemType,
+    selected: &mut Vec<(QueryPath, &'a ItemType)>,
+) {
+    match (selector, item) {
+        (Selector::Name(name), ItemType::THash(hash)) => {
+            if let Ok(Some(child)) = hash.get_item(name.as_str()) {
+                selected.push((path.child(PathElement::Name(name.clone())), child));
+            }
+        },
+        (Selector::Wildcard, _) => {
+            for (element, child) in children(item) {
+                selected.push((path.child(element), child));
+            }
+        },
+        (Selector::Index(index), ItemType::TList(list)) => {
+            let len = list.count() as i64;
+            let index = if *index < 0 { len + index } else { *index };
+            if (0..len).contains(&index) {
+                if let Ok(Some(child)) = list.get_item(index as usize) {
+                    selected.push((path.child(PathElement::Index(index as usize)), child));
+                }
+            }
+        },
+        (Selector::Slice(start, end, step), ItemType::TList(list)) => {
+            for index in slice_indices(list.count() as i64, *start, *end, step.unwrap_or(1)) {
+                if let Ok(Some(child)) = list.get_item(index) {
+                    selected.push((path.child(PathElement::Index(index)), child));
+                }
+            }
+        },
+        (Selector::Filter(expr), _) => {
+            for (element, child) in children(item) {
+                if test(expr, root, child) {
+                    selected.push((path.child(element), child));
+                }
+            }
+        },
+        _ => {},
+    }
+}
+
+fn slice_indices(len: i64, start: Option<i64>, end: Option<i64>, step: i64) -> Vec<usize> {
+    let normalize = |i: i64| if i >= 0 { i } else { len + i };
+    let mut indices = Vec::new();
+    if step > 0 {
+        let lower = normalize(start.unwrap_or(0)).clamp(0, len);
+        let upper = normalize(end.unwrap_or(len)).clamp(0, len);
+        let mut i = lower;
+        while i < upper {
+            indices.push(i as usize);
+            i += step;
+        }
+    } else if step < 0 {
+        let upper = normalize(start.unwrap_or(len - 1)).clamp(-1, len - 1);
+        let lower = normalize(end.unwrap_or(-len - 1)).clamp(-1, len - 1);
+        let mut i = upper;
+        while lower < i {
+            indices.push(i as usize);
+            i += step;
+        }
+    }
+    indices
+}
+
+enum Value<'v> {
+    Nothing,
+    Node(&'v ItemType),
+    Owned(ItemType),
+}
+
+impl<'v> Value<'v> {
+    fn get(&self) -> Option<&ItemType> {
+        match self {
+            Value::Nothing => None,
+            Value::Node(item) => Some(item),
+            Value::Owned(item) => Some(item),
+        }
+    }
+}
+
+fn test(expr: &Expr, root: &ItemType, current: &ItemType) -> bool {
+    match expr {
+        Expr::Or(terms) => terms.iter().any(|term| test(term, root, current)),
+        Expr::And(terms) => terms.iter().all(|term| test(term, root, current)),
+        Expr::Not(inner) => !test(inner, root, current),
+        Expr::Compare(left, op, right) => {
+            let left = value_of(left, root, current);
+            let right = value_of(right, root, current);
+            compare(left.get(), *op, right.get())
+        },
+        Expr::Test(Operand::Query(query)) => !nodes_of(query, root, current).is_empty(),
+        Expr::Test(Operand::Function(function)) => logical_of(function, root, current),
+        Expr::Test(Operand::Literal(_)) => false,
+    }
+}
+
+fn nodes_of<'v>(query: &FilterQuery, root: &'v ItemType, current: &'v ItemType) -> Vec<&'v ItemType> {
+    let start = if query.relative { current } else { root };
+    evaluate(&query.segments, root, start).into_iter().map(|(_, item)| item).collect()
+}
+
+fn value_of<'v>(operand: &'v Operand, root: &'v ItemType, current: &'v ItemType) -> Value<'v> {
+    match operand {
+        Operand::Literal(item) => Value::Node(item),
+        Operand::Query(query) => match nodes_of(query, root, current).as_slice() {
+            [item] => Value::Node(item),
+            _ => Value::Nothing,
+        },
+        Operand::Function(function) => match function.kind {
+            FunctionKind::Length => match value_of(&function.args[0], root, current).get() {
+                Some(ItemType::TString(s)) => Value::Owned(count_value(s.get_value().chars().count())),
+                Some(item) if item.is_container() => Value::Owned(count_value(item.count())),
+                _ => Value::Nothing,
+            },
+            FunctionKind::Count => match &function.args[0] {
+                Operand::Query(query) => Value::Owned(count_value(nodes_of(query, root, current).len())),
+                _ => Value::Nothing,
+            },
+            FunctionKind::Value => match &function.args[0] {
+                Operand::Query(query) => match nodes_of(query, root, current).as_slice() {
+                    [item] => Value::Node(item),
+                    _ => Value::Nothing,
+                },
+                _ => Value::Nothing,
+            },
+            FunctionKind::Match | FunctionKind::Search => Value::Nothing,
+        },
+    }
+}
+
+fn count_value(count: usize) -> ItemType {
+    ItemNumber::with_value(NumberValue::UInt(count as u64))
+}
+
+fn logical_of(function: &Function, root: &ItemType, current: &ItemType) -> bool {
+    let subject = value_of(&function.args[0], root, current);
+    let pattern = value_of(&function.args[1], root, current);
+    let (text, pattern) = match (subject.get(), pattern.get()) {
+        (Some(ItemType::TString(text)), Some(ItemType::TString(pattern))) => (text.get_value(), pattern.get_value()),
+        _ => return false,
+    };
+    let compiled;
+    let regex = match &function.pattern {
+        Some(regex) => regex,
+        None => {
+            compiled = Regex::new(pattern);
+            match &compiled {
+                Some(regex) => regex,
+                None => return false,
+            }
+        },
+    };
+    match function.kind {
+        FunctionKind::Match => regex.is_match(text),
+        _ => regex.is_found(text),
+    }
+}
+
+fn compare(left: Option<&ItemType>, op: CompareOp, right: Option<&ItemType>) -> bool {
+    match op {
+        CompareOp::Eq => left == right,
+        CompareOp::Ne => left != right,
+        CompareOp::Lt => less_than(left, right),
+        CompareOp::Gt => less_than(right, left),
+        CompareOp::Le => less_than(left, right) || left == right,
+        CompareOp::Ge => less_than(right, left) || left == right,
+    }
+}
+
+// Only numbers and strings are ordered; every other pairing is false.
+fn less_than(left: Option<&ItemType>, right: Option<&ItemType>) -> bool {
+    match (left, right) {
+        (Some(ItemType::TNumber(a)), Some(ItemType::TNumber(b))) => {
+            a.get_number().partial_cmp(b.get_number()) == Some(Ordering::Less)
+        },
+        (Some(ItemType::TString(a)), Some(ItemType::TString(b))) => a.get_value() < b.get_value(),
+        _ => false,
+    }
+}
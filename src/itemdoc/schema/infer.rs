@@ -0,0 +1,161 @@
+use crate::itemdoc::core::items::*;
+use crate::itemdoc::core::nulls::ItemNull;
+use crate::itemdoc::core::lists::ItemList;
+use crate::itemdoc::core::hashes::ItemHash;
+use crate::itemdoc::core::mapping::flatten_into;
+
+const DIALECT: &str = "https://json-schema.org/draft/2020-12/schema";
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct InferOptions {
+    // A string location becomes an enum when it holds at most this many
+    // distinct values and at least one of them repeats; zero turns enum
+    // detection off.
+    pub enum_threshold: usize,
+}
+
+impl Default for InferOptions {
+    fn default() -> Self {
+        InferOptions { enum_threshold: 8 }
+    }
+}
+
+impl InferOptions {
+    pub fn new() -> Self {
+        InferOptions::default()
+    }
+    pub fn with_enum_threshold(mut self, enum_threshold: usize) -> Self {
+        self.enum_threshold = enum_threshold;
+        self
+    }
+}
+
+// Infers a draft 2020-12 schema that accepts every sample, with default
+// options.
+pub fn infer<'a, I: IntoIterator<Item = &'a ItemType>>(samples: I) -> ItemType {
+    infer_with(samples, &InferOptions::default())
+}
+
+pub fn infer_with<'a, I: IntoIterator<Item = &'a ItemType>>(samples: I, options: &InferOptions) -> ItemType {
+    let mut observed = Observed::default();
+    for sample in samples {
+        observed.add(sample, options);
+    }
+    let mut schema = ItemHash::new();
+    schema.add_value(DIALECT, Some("$schema")).ok();
+    flatten_into(&mut schema, observed.to_schema());
+    schema
+}
+
+// Everything seen at one location across the samples.
+#[derive(Default)]
+struct Observed {
+    nulls: usize,
+    booleans: usize,
+    integers: usize,
+    numbers: usize,
+    strings: usize,
+    // Distinct strings, until there are more than the enum threshold.
+    values: Option<Vec<String>>,
+    lists: usize,
+    items: Option<Box<Observed>>,
+    hashes: usize,
+    // Properties in order of first appearance with the number of hashes
+    // that had them.
+    properties: Vec<(String, usize, Observed)>,
+}
+
+impl Observed {
+
+    fn add(&mut self, item: &ItemType, options: &InferOptions) {
+        match item {
+            ItemType::TNull(_) => self.nulls += 1,
+            ItemType::TBoolean(_) => self.booleans += 1,
+            ItemType::TNumber(n) if n.as_i128().is_some() => self.integers += 1,
+            ItemType::TNumber(_) => self.numbers += 1,
+            ItemType::TString(s) => {
+                if self.strings == 0 {
+                    self.values = Some(Vec::new());
+                }
+                self.strings += 1;
+                if let Some(values) = &mut self.values {
+                    if !values.contains(s.get_value()) {
+                        values.push(s.get_value().clone());
+                    }
+                    if values.len() > options.enum_threshold {
+                        self.values = None;
+                    }
+                }
+            },
+            ItemType::TList(list) => {
+                self.lists += 1;
+                for (_, member) in list.iter() {
+                    self.items.get_or_insert_with(Box::default).add(member, options);
+                }
+            },
+            ItemType::THash(hash) => {
+                self.hashes += 1;
+                for (key, member) in hash.iter() {
+                    let ContainerKey::Key(name) = key else { continue };
+                    match self.properties.iter_mut().find(|(property, _, _)| property == name) {
+                        Some((_, seen, observed)) => {
+                            *seen += 1;
+                            observed.add(member, options);
+                        },
+                        None => {
+                            let mut observed = Observed::default();
+                            observed.add(member, options);
+                            self.properties.push((name.to_string(), 1, observed));
+                        },
+                    }
+                }
+            },
+        }
+    }
+
+    fn to_schema(&self) -> ItemType {
+        let mut types = Vec::new();
+        if self.hashes > 0 { types.push("object"); }
+        if self.lists > 0 { types.push("array"); }
+        if self.strings > 0 { types.push("string"); }
+        if self.numbers > 0 { types.push("number"); } else if self.integers > 0 { types.push("integer"); }
+        if self.booleans > 0 { types.push("boolean"); }
+        if self.nulls > 0 { types.push("null"); }
+
+        let mut schema = ItemHash::new();
+        match types.as_slice() {
+            [] => {},
+            [single] => { schema.add_value(*single, Some("type")).ok(); },
+            _ => { schema.add_item(ItemType::from(types.clone()), Some("type")).ok(); },
+        }
+        // Only a location holding nothing but strings (and perhaps nulls)
+        // can be narrowed to an enum without rejecting the other kinds.
+        let only_strings = types.iter().all(|t| *t == "string" || *t == "null");
+        if let Some(values) = self.values.as_ref().filter(|v| only_strings && v.len() < self.strings) {
+            let mut members = ItemType::from(values.clone());
+            if self.nulls > 0 {
+                members.add_item(ItemNull::new(), None).ok();
+            }
+            schema.add_item(members, Some("enum")).ok();
+        }
+        if self.hashes > 0 {
+            let mut properties = ItemHash::new();
+            let mut required = ItemList::new();
+            for (name, seen, observed) in &self.properties {
+                properties.add_item(observed.to_schema(), Some(name)).ok();
+                if *seen == self.hashes {
+                    required.add_value(name.as_str(), None).ok();
+                }
+            }
+            schema.add_item(properties, Some("properties")).ok();
+            if required.count() > 0 {
+                schema.add_item(required, Some("required")).ok();
+            }
+        }
+        if let Some(items) = &self.items {
+            schema.add_item(items.to_schema(), Some("items")).ok();
+        }
+        schema
+    }
+
+}
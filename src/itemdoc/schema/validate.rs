@@ -0,0 +1,482 @@
+use std::cmp::Ordering;
+use std::collections::HashMap;
+use std::fmt;
+
+use crate::itemdoc::core::errors::{DocError, DocErrorKind};
+use crate::itemdoc::core::items::*;
+use crate::itemdoc::core::numbers::NumberValue;
+use crate::itemdoc::core::pointers::ItemPointer;
+use crate::itemdoc::query::iregexp::Regex;
+
+// Bounds how many subschemas may apply in place to one value through $ref,
+// allOf and the like. Descending into a member starts again, so deep
+// instances are not cut short; an in-place cycle is caught on its first
+// repeat instead.
+const DEPTH_LIMIT: usize = 256;
+
+// Problems found while compiling a schema. The DocError carrying one has
+// its path set to the offending location within the schema document.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum SchemaError {
+    InvalidSchema,
+    InvalidKeyword,
+    InvalidPattern,
+    InvalidReference,
+}
+
+impl SchemaError {
+    fn at(self, path: &ItemPointer) -> DocError {
+        DocError::new(DocErrorKind::Schema(self)).with_path(path.clone())
+    }
+}
+
+impl fmt::Display for SchemaError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SchemaError::InvalidSchema => write!(f, "Not a schema!"),
+            SchemaError::InvalidKeyword => write!(f, "Invalid keyword value!"),
+            SchemaError::InvalidPattern => write!(f, "Unsupported pattern!"),
+            SchemaError::InvalidReference => write!(f, "Unresolvable reference!"),
+        }
+    }
+}
+
+impl std::error::Error for SchemaError {}
+
+// One failed keyword. The schema path is the absolute location of the
+// keyword within the schema document, so a failure reached through $ref
+// points at the referenced definition.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Violation {
+    pub instance_path: ItemPointer,
+    pub schema_path: ItemPointer,
+    pub keyword: &'static str,
+    pub message: String,
+}
+
+impl fmt::Display for Violation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let instance = match &self.instance_path {
+            path if path.is_root() => "/".to_string(),
+            path => path.to_string(),
+        };
+        write!(f, "At '{}': {} (schema '#{}')", instance, self.message, self.schema_path)
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum SchemaType {
+    Null,
+    Boolean,
+    Integer,
+    Number,
+    String,
+    Array,
+    Object,
+}
+
+impl SchemaType {
+
+    pub fn parse(name: &str) -> Option<SchemaType> {
+        match name {
+            "null" => Some(SchemaType::Null),
+            "boolean" => Some(SchemaType::Boolean),
+            "integer" => Some(SchemaType::Integer),
+            "number" => Some(SchemaType::Number),
+            "string" => Some(SchemaType::String),
+            "array" => Some(SchemaType::Array),
+            "object" => Some(SchemaType::Object),
+            _ => None,
+        }
+    }
+
+    // The narrowest type of an item; numbers with no fractional part are
+    // integers whatever their representation.
+    pub fn of(item: &ItemType) -> SchemaType {
+        match item {
+            ItemType::TNull(_) => SchemaType::Null,
+            ItemType::TBoolean(_) => SchemaType::Boolean,
+            ItemType::TNumber(n) if n.as_i128().is_some() => SchemaType::Integer,
+            ItemType::TNumber(_) => SchemaType::Number,
+            ItemType::TString(_) => SchemaType::String,
+            ItemType::TList(_) => SchemaType::Array,
+            ItemType::THash(_) => SchemaType::Object,
+        }
+    }
+
+    pub fn get_name(&self) -> &'static str {
+        match self {
+            SchemaType::Null => "null",
+            SchemaType::Boolean => "boolean",
+            SchemaType::Integer => "integer",
+            SchemaType::Number => "number",
+            SchemaType::String => "string",
+            SchemaType::Array => "array",
+            SchemaType::Object => "object",
+        }
+    }
+
+    pub fn matches(&self, item: &ItemType) -> bool {
+        let actual = SchemaType::of(item);
+        *self == actual || (*self == SchemaType::Number && actual == SchemaType::Integer)
+    }
+
+}
+
+// Patterns use the ECMA-262 subset described at Regex::ecma: I-Regexp with
+// anchors and the \d, \w and \s classes. As JSON Schema requires, a pattern
+// may match anywhere in the string unless it is anchored.
+#[derive(Debug, Clone, PartialEq)]
+struct Pattern {
+    source: String,
+    regex: Regex,
+}
+
+impl Pattern {
+
+    fn new(source: &str) -> Option<Pattern> {
+        Regex::ecma(source).map(|regex| Pattern { source: source.to_string(), regex })
+    }
+
+    fn is_found(&self, text: &str) -> bool {
+        self.regex.is_found(text)
+    }
+
+}
+
+#[derive(Debug, Clone, PartialEq, Default)]
+struct Keywords {
+    types: Option<Vec<SchemaType>>,
+    enumeration: Option<Vec<ItemType>>,
+    constant: Option<ItemType>,
+    properties: Vec<(String, usize)>,
+    required: Vec<String>,
+    additional: Option<usize>,
+    prefix_items: Vec<usize>,
+    items: Option<usize>,
+    bounds: Vec<(&'static str, NumberValue)>,
+    counts: Vec<(&'static str, usize)>,
+    pattern: Option<Pattern>,
+    all_of: Vec<usize>,
+    any_of: Vec<usize>,
+    one_of: Vec<usize>,
+    not: Option<usize>,
+    reference: Option<usize>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Rule {
+    Always(bool),
+    Keywords(Box<Keywords>),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct SchemaNode {
+    path: ItemPointer,
+    rule: Rule,
+}
+
+// A compiled schema. Subschemas are stored once each, addressed by index,
+// so references (including recursive ones) are plain indices.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Schema {
+    nodes: Vec<SchemaNode>,
+}
+
+impl Schema {
+
+    // Compiles the draft 2020-12 keywords listed in Keywords. Unknown
+    // keywords are ignored, and $ref may only point into the same document.
+    pub fn compile(schema: &ItemType) -> Result<Schema, DocError> {
+        let mut compiler = Compiler { root: schema, nodes: Vec::new(), by_path: HashMap::new() };
+        compiler.node(ItemPointer::root())?;
+        let nodes = compiler.nodes.into_iter().map(|node| node.expect("every node is compiled")).collect();
+        Ok(Schema { nodes })
+    }
+
+    // Every violation in the instance, in document order.
+    pub fn validate(&self, instance: &ItemType) -> Vec<Violation> {
+        let mut violations = Vec::new();
+        self.check(0, instance, &mut ItemPointer::root(), &mut Vec::new(), &mut violations);
+        violations
+    }
+
+    pub fn is_valid(&self, instance: &ItemType) -> bool {
+        self.validate(instance).is_empty()
+    }
+
+    // `active` holds the subschemas applied to this same value so far; a
+    // repeat would never descend and so can never finish.
+    fn check(&self, id: usize, item: &ItemType, at: &mut ItemPointer, active: &mut Vec<usize>, out: &mut Vec<Violation>) {
+        let node = &self.nodes[id];
+        let mut fail = |keyword: &'static str, message: String| out.push(Violation {
+            instance_path: at.clone(),
+            schema_path: node.path.join(keyword),
+            keyword,
+            message,
+        });
+        if active.contains(&id) {
+            fail("$ref", "Schema recursion does not descend into the value!".to_string());
+            return;
+        }
+        if active.len() >= DEPTH_LIMIT {
+            fail("$ref", "Schema recursion limit exceeded!".to_string());
+            return;
+        }
+        let keywords = match &node.rule {
+            Rule::Always(true) => return,
+            Rule::Always(false) => {
+                out.push(Violation {
+                    instance_path: at.clone(),
+                    schema_path: node.path.clone(),
+                    keyword: "false",
+                    message: "No value is allowed here!".to_string(),
+                });
+                return;
+            },
+            Rule::Keywords(keywords) => keywords,
+        };
+        active.push(id);
+
+        if let Some(types) = &keywords.types {
+            if !types.iter().any(|t| t.matches(item)) {
+                let names: Vec<&str> = types.iter().map(|t| t.get_name()).collect();
+                fail("type", format!("Expected {} but found {}!", names.join(" or "), SchemaType::of(item).get_name()));
+            }
+        }
+        if let Some(values) = &keywords.enumeration {
+            if !values.contains(item) {
+                fail("enum", "Value is not one of the allowed values!".to_string());
+            }
+        }
+        if let Some(value) = &keywords.constant {
+            if value != item {
+                fail("const", "Value does not equal the constant!".to_string());
+            }
+        }
+        if let ItemType::TNumber(number) = item {
+            let value = number.get_number();
+            for (keyword, limit) in &keywords.bounds {
+                let Some(order) = value.partial_cmp(limit) else { continue };
+                let message = match (*keyword, order) {
+                    ("minimum", Ordering::Less) => format!("{} is less than the minimum of {}!", value, limit),
+                    ("maximum", Ordering::Greater) => format!("{} is greater than the maximum of {}!", value, limit),
+                    ("exclusiveMinimum", Ordering::Less | Ordering::Equal) => format!("{} is not greater than {}!", value, limit),
+                    ("exclusiveMaximum", Ordering::Greater | Ordering::Equal) => format!("{} is not less than {}!", value, limit),
+                    _ => continue,
+                };
+                fail(keyword, message);
+            }
+        }
+        for (keyword, limit) in &keywords.counts {
+            let (what, size) = match (*keyword, item) {
+                ("minLength" | "maxLength", ItemType::TString(s)) => ("String length", s.get_value().chars().count()),
+                ("minItems" | "maxItems", ItemType::TList(l)) => ("Item count", l.count()),
+                ("minProperties" | "maxProperties", ItemType::THash(h)) => ("Property count", h.count()),
+                _ => continue,
+            };
+            if keyword.starts_with("min") && size < *limit {
+                fail(keyword, format!("{} {} is less than the minimum of {}!", what, size, limit));
+            } else if keyword.starts_with("max") && size > *limit {
+                fail(keyword, format!("{} {} is greater than the maximum of {}!", what, size, limit));
+            }
+        }
+        if let (Some(pattern), ItemType::TString(s)) = (&keywords.pattern, item) {
+            if !pattern.is_found(s.get_value()) {
+                fail("pattern", format!("'{}' does not match the pattern '{}'!", s.get_value(), pattern.source));
+            }
+        }
+        if let ItemType::THash(hash) = item {
+            for name in &keywords.required {
+                if !hash.has_key(name.as_str()) {
+                    fail("required", format!("Missing required property '{}'!", name));
+                }
+            }
+        }
+        if !keywords.any_of.is_empty() && !keywords.any_of.iter().any(|id| self.accepts(*id, item, at, active)) {
+            fail("anyOf", "Value does not match any of the schemas!".to_string());
+        }
+        if !keywords.one_of.is_empty() {
+            let matched = keywords.one_of.iter().filter(|id| self.accepts(**id, item, at, active)).count();
+            if matched != 1 {
+                fail("oneOf", format!("Value matches {} of the schemas instead of exactly one!", matched));
+            }
+        }
+        if let Some(not) = keywords.not {
+            if self.accepts(not, item, at, active) {
+                fail("not", "Value must not match the schema!".to_string());
+            }
+        }
+
+        for id in &keywords.all_of {
+            self.check(*id, item, at, active, out);
+        }
+        if let Some(id) = keywords.reference {
+            self.check(id, item, at, active, out);
+        }
+        active.pop();
+        match item {
+            ItemType::THash(hash) => {
+                for (key, member) in hash.iter() {
+                    let ContainerKey::Key(name) = key else { continue };
+                    let declared = keywords.properties.iter().find(|(property, _)| property == name);
+                    if let Some(id) = declared.map(|(_, id)| *id).or(keywords.additional) {
+                        self.check_member(id, key, member, at, out);
+                    }
+                }
+            },
+            ItemType::TList(list) => {
+                for (key, member) in list.iter() {
+                    let ContainerKey::Idx(index) = key else { continue };
+                    if let Some(id) = keywords.prefix_items.get(index).copied().or(keywords.items) {
+                        self.check_member(id, key, member, at, out);
+                    }
+                }
+            },
+            _ => {},
+        }
+    }
+
+    fn check_member(&self, id: usize, key: ContainerKey<'_>, member: &ItemType, at: &mut ItemPointer, out: &mut Vec<Violation>) {
+        at.push_key(&key);
+        self.check(id, member, at, &mut Vec::new(), out);
+        at.pop();
+    }
+
+    fn accepts(&self, id: usize, item: &ItemType, at: &ItemPointer, active: &mut Vec<usize>) -> bool {
+        let mut scratch = Vec::new();
+        self.check(id, item, &mut at.clone(), active, &mut scratch);
+        scratch.is_empty()
+    }
+
+}
+
+struct Compiler<'a> {
+    root: &'a ItemType,
+    nodes: Vec<Option<SchemaNode>>,
+    by_path: HashMap<ItemPointer, usize>,
+}
+
+impl<'a> Compiler<'a> {
+
+    // Compiles the subschema at `path` once, reserving its index first so
+    // that a reference back to it while compiling finds the index.
+    fn node(&mut self, path: ItemPointer) -> Result<usize, DocError> {
+        if let Some(id) = self.by_path.get(&path) {
+            return Ok(*id);
+        }
+        let root: &'a ItemType = self.root;
+        let item = root.pointer(&path).map_err(|_| SchemaError::InvalidReference.at(&path))?;
+        let id = self.nodes.len();
+        self.nodes.push(None);
+        self.by_path.insert(path.clone(), id);
+        let rule = match item {
+            ItemType::TBoolean(b) => Rule::Always(b.get_value()),
+            ItemType::THash(_) => Rule::Keywords(Box::new(self.keywords(item, &path)?)),
+            _ => return Err(SchemaError::InvalidSchema.at(&path)),
+        };
+        self.nodes[id] = Some(SchemaNode { path, rule });
+        Ok(id)
+    }
+
+    fn keywords(&mut self, schema: &'a ItemType, path: &ItemPointer) -> Result<Keywords, DocError> {
+        let mut keywords = Keywords::default();
+        for (key, value) in schema.iter() {
+            let ContainerKey::Key(name) = key else { continue };
+            let at = path.join(name);
+            let invalid = || SchemaError::InvalidKeyword.at(&at);
+            match name {
+                "type" => {
+                    let names: Option<Vec<&str>> = match value {
+                        ItemType::TList(_) => value.iter().map(|(_, t)| <&str>::try_from(t).ok()).collect(),
+                        _ => <&str>::try_from(value).ok().map(|name| vec![name]),
+                    };
+                    let types: Option<Vec<SchemaType>> = names.ok_or_else(invalid)?.into_iter().map(SchemaType::parse).collect();
+                    keywords.types = Some(types.ok_or_else(invalid)?);
+                },
+                "enum" => match value {
+                    ItemType::TList(list) => keywords.enumeration = Some(list.iter().map(|(_, v)| v.clone()).collect()),
+                    _ => return Err(invalid()),
+                },
+                "const" => keywords.constant = Some(value.clone()),
+                "properties" => {
+                    if !value.is_hash() {
+                        return Err(invalid());
+                    }
+                    for (member, _) in value.iter() {
+                        if let ContainerKey::Key(property) = member {
+                            keywords.properties.push((property.to_string(), self.node(at.join(property))?));
+                        }
+                    }
+                },
+                "required" => match Vec::<String>::try_from(value) {
+                    Ok(names) => keywords.required = names,
+                    Err(_) => return Err(invalid()),
+                },
+                "additionalProperties" => keywords.additional = Some(self.node(at)?),
+                "items" => keywords.items = Some(self.node(at)?),
+                "prefixItems" => keywords.prefix_items = self.subschemas(value, &at)?,
+                "minimum" | "maximum" | "exclusiveMinimum" | "exclusiveMaximum" => match value {
+                    ItemType::TNumber(n) => keywords.bounds.push((bound_name(name), n.get_number().clone())),
+                    _ => return Err(invalid()),
+                },
+                "minLength" | "maxLength" | "minItems" | "maxItems" | "minProperties" | "maxProperties" => {
+                    let limit = usize::try_from(value).map_err(|_| invalid())?;
+                    keywords.counts.push((count_name(name), limit));
+                },
+                "pattern" => {
+                    let source = <&str>::try_from(value).map_err(|_| invalid())?;
+                    keywords.pattern = Some(Pattern::new(source).ok_or_else(|| SchemaError::InvalidPattern.at(&at))?);
+                },
+                "allOf" => keywords.all_of = self.subschemas(value, &at)?,
+                "anyOf" => keywords.any_of = self.subschemas(value, &at)?,
+                "oneOf" => keywords.one_of = self.subschemas(value, &at)?,
+                "not" => keywords.not = Some(self.node(at)?),
+                "$ref" => {
+                    let target = <&str>::try_from(value).ok()
+                        .and_then(|reference| reference.strip_prefix('#'))
+                        .and_then(|fragment| ItemPointer::parse(fragment).ok())
+                        .ok_or_else(|| SchemaError::InvalidReference.at(&at))?;
+                    let id = self.node(target).map_err(|error| match error.get_kind() {
+                        DocErrorKind::Schema(SchemaError::InvalidReference) => SchemaError::InvalidReference.at(&at),
+                        _ => error,
+                    })?;
+                    keywords.reference = Some(id);
+                },
+                _ => {},
+            }
+        }
+        Ok(keywords)
+    }
+
+    fn subschemas(&mut self, value: &ItemType, at: &ItemPointer) -> Result<Vec<usize>, DocError> {
+        match value {
+            ItemType::TList(list) if list.count() > 0 => {
+                (0..list.count()).map(|index| self.node(at.join(&index.to_string()))).collect()
+            },
+            _ => Err(SchemaError::InvalidKeyword.at(at)),
+        }
+    }
+
+}
+
+// Keyword names are kept as static strings so violations can carry them.
+fn bound_name(name: &str) -> &'static str {
+    match name {
+        "minimum" => "minimum",
+        "maximum" => "maximum",
+        "exclusiveMinimum" => "exclusiveMinimum",
+        _ => "exclusiveMaximum",
+    }
+}
+
+fn count_name(name: &str) -> &'static str {
+    match name {
+        "minLength" => "minLength",
+        "maxLength" => "maxLength",
+        "minItems" => "minItems",
+        "maxItems" => "maxItems",
+        "minProperties" => "minProperties",
+        _ => "maxProperties",
+    }
+}
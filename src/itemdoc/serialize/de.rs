@@ -0,0 +1,323 @@
+use std::fmt;
+
+use serde::de::{self, Deserialize, IntoDeserializer, Visitor};
+use serde::forward_to_deserialize_any;
+
+use crate::itemdoc::core::items::{ContainerKey, ItemType};
+use crate::itemdoc::core::nulls::ItemNull;
+use crate::itemdoc::core::numbers::{ItemNumber, NumberValue};
+use crate::itemdoc::core::lists::{ItemList, ListIter};
+use crate::itemdoc::core::hashes::{ItemHash, HashIter};
+use super::error::SerdeError;
+use super::ser::NUMBER_TOKEN;
+
+// Reads any Deserialize value out of a document, borrowing strings from it
+// where the target allows.
+pub fn from_item<'de, T: Deserialize<'de>>(item: &'de ItemType) -> Result<T, SerdeError> {
+    T::deserialize(item)
+}
+
+impl<'de> Deserialize<'de> for ItemType {
+    fn deserialize<D: de::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_any(ItemVisitor)
+    }
+}
+
+struct ItemVisitor;
+
+impl<'de> Visitor<'de> for ItemVisitor {
+    type Value = ItemType;
+
+    fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "any item")
+    }
+
+    fn visit_bool<E>(self, v: bool) -> Result<ItemType, E> { Ok(ItemType::from(v)) }
+    fn visit_i64<E>(self, v: i64) -> Result<ItemType, E> { Ok(ItemType::from(v)) }
+    fn visit_i128<E>(self, v: i128) -> Result<ItemType, E> { Ok(ItemType::from(v)) }
+    fn visit_u64<E>(self, v: u64) -> Result<ItemType, E> { Ok(ItemType::from(v)) }
+    fn visit_u128<E>(self, v: u128) -> Result<ItemType, E> {
+        Ok(match i128::try_from(v) {
+            Ok(n) => ItemType::from(n),
+            Err(_) => ItemNumber::with_value(NumberValue::Decimal(v.to_string())),
+        })
+    }
+    fn visit_f64<E>(self, v: f64) -> Result<ItemType, E> { Ok(ItemType::from(v)) }
+    fn visit_str<E>(self, v: &str) -> Result<ItemType, E> { Ok(ItemType::from(v)) }
+    fn visit_string<E>(self, v: String) -> Result<ItemType, E> { Ok(ItemType::from(v)) }
+    fn visit_bytes<E>(self, v: &[u8]) -> Result<ItemType, E> { Ok(ItemType::from(v)) }
+    fn visit_none<E>(self) -> Result<ItemType, E> { Ok(ItemNull::new()) }
+    fn visit_unit<E>(self) -> Result<ItemType, E> { Ok(ItemNull::new()) }
+    fn visit_some<D: de::Deserializer<'de>>(self, deserializer: D) -> Result<ItemType, D::Error> {
+        ItemType::deserialize(deserializer)
+    }
+    fn visit_newtype_struct<D: de::Deserializer<'de>>(self, deserializer: D) -> Result<ItemType, D::Error> {
+        ItemType::deserialize(deserializer)
+    }
+
+    fn visit_seq<A: de::SeqAccess<'de>>(self, mut seq: A) -> Result<ItemType, A::Error> {
+        let mut list = ItemList::new();
+        while let Some(item) = seq.next_element::<ItemType>()? {
+            list.add_item(item, None).map_err(de::Error::custom)?;
+        }
+        Ok(list)
+    }
+
+    fn visit_map<A: de::MapAccess<'de>>(self, mut map: A) -> Result<ItemType, A::Error> {
+        let mut hash = ItemHash::new();
+        let mut first = true;
+        while let Some(key) = map.next_key::<String>()? {
+            // An arbitrary-precision number, as written by serialize_decimal.
+            if first && key == NUMBER_TOKEN {
+                let digits = map.next_value::<String>()?;
+                return NumberValue::parse(&digits)
+                    .map(ItemNumber::with_value)
+                    .ok_or_else(|| de::Error::custom(format!("invalid number: {}", digits)));
+            }
+            first = false;
+            let item = map.next_value::<ItemType>()?;
+            hash.add_item(item, Some(&key)).map_err(de::Error::custom)?;
+        }
+        Ok(hash)
+    }
+}
+
+impl<'de> IntoDeserializer<'de, SerdeError> for &'de ItemType {
+    type Deserializer = Self;
+
+    fn into_deserializer(self) -> Self {
+        self
+    }
+}
+
+impl<'de> de::Deserializer<'de> for &'de ItemType {
+    type Error = SerdeError;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, SerdeError> {
+        match self {
+            ItemType::TNull(_) => visitor.visit_unit(),
+            ItemType::TBoolean(mapped) => visitor.visit_bool(mapped.get_value()),
+            ItemType::TNumber(mapped) => match mapped.get_number() {
+                NumberValue::Int(n) => visitor.visit_i64(*n),
+                NumberValue::UInt(n) => visitor.visit_u64(*n),
+                NumberValue::Int128(n) => visitor.visit_i128(*n),
+                NumberValue::Float(n) => visitor.visit_f64(*n),
+                decimal @ NumberValue::Decimal(text) => match (decimal.as_i128(), text.parse::<u128>(), decimal.as_f64()) {
+                    (Some(n), _, _) => visitor.visit_i128(n),
+                    (None, Ok(n), _) => visitor.visit_u128(n),
+                    (None, Err(_), Some(n)) => visitor.visit_f64(n),
+                    // Digits no primitive holds exactly; integer targets
+                    // reject the map rather than round.
+                    _ => visitor.visit_map(NumberAccess { digits: Some(text) }),
+                },
+            },
+            ItemType::TString(mapped) => visitor.visit_borrowed_str(mapped.get_value()),
+            ItemType::TList(list) => visitor.visit_seq(ListAccess { iter: list.iter() }),
+            ItemType::THash(hash) => visitor.visit_map(HashAccess { iter: hash.iter(), value: None }),
+        }
+    }
+
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, SerdeError> {
+        match self {
+            ItemType::TNull(_) => visitor.visit_none(),
+            _ => visitor.visit_some(self),
+        }
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(self, _name: &'static str, visitor: V) -> Result<V::Value, SerdeError> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_enum<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        _variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, SerdeError> {
+        match self {
+            ItemType::TString(mapped) => visitor.visit_enum(mapped.get_value().as_str().into_deserializer()),
+            ItemType::THash(hash) if hash.count() == 1 => match hash.iter().next() {
+                Some((ContainerKey::Key(variant), value)) => visitor.visit_enum(VariantAccess { variant, value }),
+                _ => Err(de::Error::custom("expected a single-key hash for an enum")),
+            },
+            _ => Err(de::Error::invalid_type(unexpected(self), &"a string or a single-key hash")),
+        }
+    }
+
+    fn deserialize_ignored_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, SerdeError> {
+        visitor.visit_unit()
+    }
+
+    // Integral floats such as 8080.0 still fill integer fields.
+    fn deserialize_i64<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, SerdeError> {
+        deserialize_integer(self, visitor)
+    }
+    fn deserialize_i32<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, SerdeError> {
+        deserialize_integer(self, visitor)
+    }
+    fn deserialize_i16<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, SerdeError> {
+        deserialize_integer(self, visitor)
+    }
+    fn deserialize_i8<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, SerdeError> {
+        deserialize_integer(self, visitor)
+    }
+    fn deserialize_i128<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, SerdeError> {
+        deserialize_integer(self, visitor)
+    }
+    fn deserialize_u64<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, SerdeError> {
+        deserialize_integer(self, visitor)
+    }
+    fn deserialize_u32<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, SerdeError> {
+        deserialize_integer(self, visitor)
+    }
+    fn deserialize_u16<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, SerdeError> {
+        deserialize_integer(self, visitor)
+    }
+    fn deserialize_u8<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, SerdeError> {
+        deserialize_integer(self, visitor)
+    }
+    fn deserialize_u128<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, SerdeError> {
+        deserialize_integer(self, visitor)
+    }
+
+    // Float fields take any number, rounded to the nearest f64.
+    fn deserialize_f64<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, SerdeError> {
+        deserialize_float(self, visitor)
+    }
+    fn deserialize_f32<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, SerdeError> {
+        deserialize_float(self, visitor)
+    }
+
+    forward_to_deserialize_any! {
+        bool char str string bytes byte_buf unit unit_struct
+        seq tuple tuple_struct map struct identifier
+    }
+}
+
+fn deserialize_integer<'de, V: Visitor<'de>>(item: &'de ItemType, visitor: V) -> Result<V::Value, SerdeError> {
+    match item {
+        ItemType::TNumber(mapped) => match (mapped.as_i64(), mapped.as_u64(), mapped.as_i128()) {
+            (Some(n), _, _) => visitor.visit_i64(n),
+            (None, Some(n), _) => visitor.visit_u64(n),
+            (None, None, Some(n)) => visitor.visit_i128(n),
+            _ => de::Deserializer::deserialize_any(item, visitor),
+        },
+        _ => de::Deserializer::deserialize_any(item, visitor),
+    }
+}
+
+fn deserialize_float<'de, V: Visitor<'de>>(item: &'de ItemType, visitor: V) -> Result<V::Value, SerdeError> {
+    match item {
+        ItemType::TNumber(mapped) => visitor.visit_f64(mapped.get_number().to_f64()),
+        _ => de::Deserializer::deserialize_any(item, visitor),
+    }
+}
+
+fn unexpected(item: &ItemType) -> de::Unexpected<'_> {
+    match item {
+        ItemType::TNull(_) => de::Unexpected::Unit,
+        ItemType::TBoolean(mapped) => de::Unexpected::Bool(mapped.get_value()),
+        ItemType::TNumber(mapped) => de::Unexpected::Float(mapped.get_value()),
+        ItemType::TString(mapped) => de::Unexpected::Str(mapped.get_value()),
+        ItemType::TList(_) => de::Unexpected::Seq,
+        ItemType::THash(_) => de::Unexpected::Map,
+    }
+}
+
+struct ListAccess<'de> {
+    iter: ListIter<'de>,
+}
+
+impl<'de> de::SeqAccess<'de> for ListAccess<'de> {
+    type Error = SerdeError;
+
+    fn next_element_seed<T: de::DeserializeSeed<'de>>(&mut self, seed: T) -> Result<Option<T::Value>, SerdeError> {
+        match self.iter.next() {
+            Some((_, item)) => seed.deserialize(item).map(Some),
+            None => Ok(None),
+        }
+    }
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.iter.len())
+    }
+}
+
+struct NumberAccess<'de> {
+    digits: Option<&'de str>,
+}
+
+impl<'de> de::MapAccess<'de> for NumberAccess<'de> {
+    type Error = SerdeError;
+
+    fn next_key_seed<K: de::DeserializeSeed<'de>>(&mut self, seed: K) -> Result<Option<K::Value>, SerdeError> {
+        match self.digits {
+            Some(_) => seed.deserialize(de::value::BorrowedStrDeserializer::new(NUMBER_TOKEN)).map(Some),
+            None => Ok(None),
+        }
+    }
+    fn next_value_seed<V: de::DeserializeSeed<'de>>(&mut self, seed: V) -> Result<V::Value, SerdeError> {
+        let digits = self.digits.take().ok_or_else(|| de::Error::custom("value requested before its key"))?;
+        seed.deserialize(de::value::BorrowedStrDeserializer::new(digits))
+    }
+}
+
+struct HashAccess<'de> {
+    iter: HashIter<'de>,
+    value: Option<&'de ItemType>,
+}
+
+impl<'de> de::MapAccess<'de> for HashAccess<'de> {
+    type Error = SerdeError;
+
+    fn next_key_seed<K: de::DeserializeSeed<'de>>(&mut self, seed: K) -> Result<Option<K::Value>, SerdeError> {
+        match self.iter.next() {
+            Some((ContainerKey::Key(key), item)) => {
+                self.value = Some(item);
+                seed.deserialize(de::value::BorrowedStrDeserializer::new(key)).map(Some)
+            },
+            _ => Ok(None),
+        }
+    }
+    fn next_value_seed<V: de::DeserializeSeed<'de>>(&mut self, seed: V) -> Result<V::Value, SerdeError> {
+        let item = self.value.take().ok_or_else(|| de::Error::custom("value requested before its key"))?;
+        seed.deserialize(item)
+    }
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.iter.len())
+    }
+}
+
+struct VariantAccess<'de> {
+    variant: &'de str,
+    value: &'de ItemType,
+}
+
+impl<'de> de::EnumAccess<'de> for VariantAccess<'de> {
+    type Error = SerdeError;
+    type Variant = &'de ItemType;
+
+    fn variant_seed<V: de::DeserializeSeed<'de>>(self, seed: V) -> Result<(V::Value, Self::Variant), SerdeError> {
+        let variant = seed.deserialize(de::value::BorrowedStrDeserializer::new(self.variant))?;
+        Ok((variant, self.value))
+    }
+}
+
+impl<'de> de::VariantAccess<'de> for &'de ItemType {
+    type Error = SerdeError;
+
+    fn unit_variant(self) -> Result<(), SerdeError> {
+        match self {
+            ItemType::TNull(_) => Ok(()),
+            _ => Err(de::Error::invalid_type(unexpected(self), &"a unit variant")),
+        }
+    }
+    fn newtype_variant_seed<T: de::DeserializeSeed<'de>>(self, seed: T) -> Result<T::Value, SerdeError> {
+        seed.deserialize(self)
+    }
+    fn tuple_variant<V: Visitor<'de>>(self, _len: usize, visitor: V) -> Result<V::Value, SerdeError> {
+        de::Deserializer::deserialize_seq(self, visitor)
+    }
+    fn struct_variant<V: Visitor<'de>>(self, _fields: &'static [&'static str], visitor: V) -> Result<V::Value, SerdeError> {
+        de::Deserializer::deserialize_map(self, visitor)
+    }
+}
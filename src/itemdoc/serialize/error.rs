@@ -0,0 +1,31 @@
+use std::fmt;
+
+#[derive(Debug)]
+#[derive(Clone, PartialEq)]
+pub enum SerdeError {
+    KeyMustBeAString,
+    Custom(String),
+}
+
+impl fmt::Display for SerdeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SerdeError::KeyMustBeAString => write!(f, "Hash keys must be strings!"),
+            SerdeError::Custom(message) => write!(f, "{}", message),
+        }
+    }
+}
+
+impl std::error::Error for SerdeError {}
+
+impl serde::ser::Error for SerdeError {
+    fn custom<T: fmt::Display>(message: T) -> Self {
+        SerdeError::Custom(message.to_string())
+    }
+}
+
+impl serde::de::Error for SerdeError {
+    fn custom<T: fmt::Display>(message: T) -> Self {
+        SerdeError::Custom(message.to_string())
+    }
+}
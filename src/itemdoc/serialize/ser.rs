@@ -0,0 +1,368 @@
+use serde::ser::{self, Serialize};
+
+use crate::itemdoc::core::items::{ContainerKey, ItemType};
+use crate::itemdoc::core::nulls::ItemNull;
+use crate::itemdoc::core::numbers::{ItemNumber, NumberValue};
+use crate::itemdoc::core::lists::ItemList;
+use crate::itemdoc::core::hashes::ItemHash;
+use super::error::SerdeError;
+
+// Converts any Serialize value into a document. Enums use the externally
+// tagged layout: unit variants become strings and every other variant a
+// single-key hash.
+pub fn to_item<T: Serialize + ?Sized>(value: &T) -> Result<ItemType, SerdeError> {
+    value.serialize(ItemSerializer)
+}
+
+// serde has no arbitrary-precision number, so a decimal that no primitive
+// holds exactly travels as serde_json's arbitrary_precision struct: one
+// field, named like the struct, carrying the digits. Formats that don't
+// know the convention see a one-entry map instead of a rounded float.
+pub(crate) const NUMBER_TOKEN: &str = "$serde_json::private::Number";
+
+fn serialize_decimal<S: ser::Serializer>(value: &NumberValue, text: &str, serializer: S) -> Result<S::Ok, S::Error> {
+    use ser::SerializeStruct;
+    if let Some(n) = value.as_i128() {
+        return serializer.serialize_i128(n);
+    }
+    if let Ok(n) = text.parse::<u128>() {
+        return serializer.serialize_u128(n);
+    }
+    if let Some(n) = value.as_f64() {
+        return serializer.serialize_f64(n);
+    }
+    let mut number = serializer.serialize_struct(NUMBER_TOKEN, 1)?;
+    number.serialize_field(NUMBER_TOKEN, text)?;
+    number.end()
+}
+
+impl Serialize for ItemType {
+    fn serialize<S: ser::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        use ser::{SerializeMap, SerializeSeq};
+        match self {
+            ItemType::TNull(_) => serializer.serialize_unit(),
+            ItemType::TBoolean(mapped) => serializer.serialize_bool(mapped.get_value()),
+            ItemType::TNumber(mapped) => match mapped.get_number() {
+                NumberValue::Int(n) => serializer.serialize_i64(*n),
+                NumberValue::UInt(n) => serializer.serialize_u64(*n),
+                NumberValue::Int128(n) => serializer.serialize_i128(*n),
+                NumberValue::Float(n) => serializer.serialize_f64(*n),
+                decimal @ NumberValue::Decimal(text) => serialize_decimal(decimal, text, serializer),
+            },
+            ItemType::TString(mapped) => serializer.serialize_str(mapped.get_value()),
+            ItemType::TList(list) => {
+                let mut seq = serializer.serialize_seq(Some(list.count()))?;
+                for (_, item) in list.iter() {
+                    seq.serialize_element(item)?;
+                }
+                seq.end()
+            },
+            ItemType::THash(hash) => {
+                let mut map = serializer.serialize_map(Some(hash.count()))?;
+                for (key, item) in hash.iter() {
+                    if let ContainerKey::Key(name) = key {
+                        map.serialize_entry(name, item)?;
+                    }
+                }
+                map.end()
+            },
+        }
+    }
+}
+
+pub struct ItemSerializer;
+
+fn number(value: NumberValue) -> Result<ItemType, SerdeError> {
+    Ok(ItemNumber::with_value(value))
+}
+
+fn tagged(variant: &str, item: ItemType) -> ItemType {
+    let mut hash = ItemHash::new();
+    hash.add_item(item, Some(variant)).ok();
+    hash
+}
+
+impl ser::Serializer for ItemSerializer {
+    type Ok = ItemType;
+    type Error = SerdeError;
+    type SerializeSeq = SerializeList;
+    type SerializeTuple = SerializeList;
+    type SerializeTupleStruct = SerializeList;
+    type SerializeTupleVariant = SerializeList;
+    type SerializeMap = SerializeHash;
+    type SerializeStruct = SerializeHash;
+    type SerializeStructVariant = SerializeHash;
+
+    fn serialize_bool(self, v: bool) -> Result<ItemType, SerdeError> { Ok(ItemType::from(v)) }
+    fn serialize_i8(self, v: i8) -> Result<ItemType, SerdeError> { number(NumberValue::Int(v as i64)) }
+    fn serialize_i16(self, v: i16) -> Result<ItemType, SerdeError> { number(NumberValue::Int(v as i64)) }
+    fn serialize_i32(self, v: i32) -> Result<ItemType, SerdeError> { number(NumberValue::Int(v as i64)) }
+    fn serialize_i64(self, v: i64) -> Result<ItemType, SerdeError> { number(NumberValue::Int(v)) }
+    fn serialize_i128(self, v: i128) -> Result<ItemType, SerdeError> { number(NumberValue::Int128(v)) }
+    fn serialize_u8(self, v: u8) -> Result<ItemType, SerdeError> { number(NumberValue::UInt(v as u64)) }
+    fn serialize_u16(self, v: u16) -> Result<ItemType, SerdeError> { number(NumberValue::UInt(v as u64)) }
+    fn serialize_u32(self, v: u32) -> Result<ItemType, SerdeError> { number(NumberValue::UInt(v as u64)) }
+    fn serialize_u64(self, v: u64) -> Result<ItemType, SerdeError> { number(NumberValue::UInt(v)) }
+    fn serialize_u128(self, v: u128) -> Result<ItemType, SerdeError> {
+        match i128::try_from(v) {
+            Ok(n) => number(NumberValue::Int128(n)),
+            Err(_) => number(NumberValue::Decimal(v.to_string())),
+        }
+    }
+    fn serialize_f32(self, v: f32) -> Result<ItemType, SerdeError> { number(NumberValue::Float(v as f64)) }
+    fn serialize_f64(self, v: f64) -> Result<ItemType, SerdeError> { number(NumberValue::Float(v)) }
+    fn serialize_char(self, v: char) -> Result<ItemType, SerdeError> { Ok(ItemType::from(v.to_string())) }
+    fn serialize_str(self, v: &str) -> Result<ItemType, SerdeError> { Ok(ItemType::from(v)) }
+    fn serialize_bytes(self, v: &[u8]) -> Result<ItemType, SerdeError> { Ok(ItemType::from(v)) }
+
+    fn serialize_none(self) -> Result<ItemType, SerdeError> { Ok(ItemNull::new()) }
+    fn serialize_some<T: Serialize + ?Sized>(self, value: &T) -> Result<ItemType, SerdeError> {
+        value.serialize(self)
+    }
+    fn serialize_unit(self) -> Result<ItemType, SerdeError> { Ok(ItemNull::new()) }
+    fn serialize_unit_struct(self, _name: &'static str) -> Result<ItemType, SerdeError> { Ok(ItemNull::new()) }
+    fn serialize_unit_variant(self, _name: &'static str, _index: u32, variant: &'static str) -> Result<ItemType, SerdeError> {
+        Ok(ItemType::from(variant))
+    }
+    fn serialize_newtype_struct<T: Serialize + ?Sized>(self, _name: &'static str, value: &T) -> Result<ItemType, SerdeError> {
+        value.serialize(self)
+    }
+    fn serialize_newtype_variant<T: Serialize + ?Sized>(
+        self,
+        _name: &'static str,
+        _index: u32,
+        variant: &'static str,
+        value: &T,
+    ) -> Result<ItemType, SerdeError> {
+        Ok(tagged(variant, value.serialize(self)?))
+    }
+
+    fn serialize_seq(self, _len: Option<usize>) -> Result<SerializeList, SerdeError> {
+        Ok(SerializeList { list: ItemList::new(), variant: None })
+    }
+    fn serialize_tuple(self, len: usize) -> Result<SerializeList, SerdeError> {
+        self.serialize_seq(Some(len))
+    }
+    fn serialize_tuple_struct(self, _name: &'static str, len: usize) -> Result<SerializeList, SerdeError> {
+        self.serialize_seq(Some(len))
+    }
+    fn serialize_tuple_variant(
+        self,
+        _name: &'static str,
+        _index: u32,
+        variant: &'static str,
+        _len: usize,
+    ) -> Result<SerializeList, SerdeError> {
+        Ok(SerializeList { list: ItemList::new(), variant: Some(variant) })
+    }
+    fn serialize_map(self, _len: Option<usize>) -> Result<SerializeHash, SerdeError> {
+        Ok(SerializeHash { hash: ItemHash::new(), key: None, variant: None, number: false })
+    }
+    fn serialize_struct(self, name: &'static str, len: usize) -> Result<SerializeHash, SerdeError> {
+        let mut hash = self.serialize_map(Some(len))?;
+        hash.number = name == NUMBER_TOKEN;
+        Ok(hash)
+    }
+    fn serialize_struct_variant(
+        self,
+        _name: &'static str,
+        _index: u32,
+        variant: &'static str,
+        _len: usize,
+    ) -> Result<SerializeHash, SerdeError> {
+        Ok(SerializeHash { hash: ItemHash::new(), key: None, variant: Some(variant), number: false })
+    }
+}
+
+pub struct SerializeList {
+    list: ItemType,
+    variant: Option<&'static str>,
+}
+
+impl SerializeList {
+    fn push<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), SerdeError> {
+        self.list.add_item(value.serialize(ItemSerializer)?, None).map_err(ser::Error::custom)
+    }
+    fn finish(self) -> Result<ItemType, SerdeError> {
+        Ok(match self.variant {
+            Some(variant) => tagged(variant, self.list),
+            None => self.list,
+        })
+    }
+}
+
+impl ser::SerializeSeq for SerializeList {
+    type Ok = ItemType;
+    type Error = SerdeError;
+
+    fn serialize_element<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), SerdeError> { self.push(value) }
+    fn end(self) -> Result<ItemType, SerdeError> { self.finish() }
+}
+
+impl ser::SerializeTuple for SerializeList {
+    type Ok = ItemType;
+    type Error = SerdeError;
+
+    fn serialize_element<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), SerdeError> { self.push(value) }
+    fn end(self) -> Result<ItemType, SerdeError> { self.finish() }
+}
+
+impl ser::SerializeTupleStruct for SerializeList {
+    type Ok = ItemType;
+    type Error = SerdeError;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), SerdeError> { self.push(value) }
+    fn end(self) -> Result<ItemType, SerdeError> { self.finish() }
+}
+
+impl ser::SerializeTupleVariant for SerializeList {
+    type Ok = ItemType;
+    type Error = SerdeError;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), SerdeError> { self.push(value) }
+    fn end(self) -> Result<ItemType, SerdeError> { self.finish() }
+}
+
+pub struct SerializeHash {
+    hash: ItemType,
+    key: Option<String>,
+    variant: Option<&'static str>,
+    number: bool,
+}
+
+impl SerializeHash {
+    fn insert<T: Serialize + ?Sized>(&mut self, key: &str, value: &T) -> Result<(), SerdeError> {
+        self.hash.add_item(value.serialize(ItemSerializer)?, Some(key)).map_err(ser::Error::custom)
+    }
+    fn finish(self) -> Result<ItemType, SerdeError> {
+        if self.number {
+            return match self.hash.get_item(NUMBER_TOKEN) {
+                Ok(Some(ItemType::TString(digits))) => NumberValue::parse(digits.get_value())
+                    .ok_or_else(|| ser::Error::custom(format!("invalid number: {}", digits.get_value())))
+                    .and_then(number),
+                _ => Err(ser::Error::custom("expected the digits of an arbitrary-precision number")),
+            };
+        }
+        Ok(match self.variant {
+            Some(variant) => tagged(variant, self.hash),
+            None => self.hash,
+        })
+    }
+}
+
+impl ser::SerializeMap for SerializeHash {
+    type Ok = ItemType;
+    type Error = SerdeError;
+
+    fn serialize_key<T: Serialize + ?Sized>(&mut self, key: &T) -> Result<(), SerdeError> {
+        self.key = Some(key.serialize(KeySerializer)?);
+        Ok(())
+    }
+    fn serialize_value<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), SerdeError> {
+        let key = self.key.take().ok_or_else(|| ser::Error::custom("value serialized before its key"))?;
+        self.insert(&key, value)
+    }
+    fn end(self) -> Result<ItemType, SerdeError> { self.finish() }
+}
+
+impl ser::SerializeStruct for SerializeHash {
+    type Ok = ItemType;
+    type Error = SerdeError;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, key: &'static str, value: &T) -> Result<(), SerdeError> {
+        self.insert(key, value)
+    }
+    fn end(self) -> Result<ItemType, SerdeError> { self.finish() }
+}
+
+impl ser::SerializeStructVariant for SerializeHash {
+    type Ok = ItemType;
+    type Error = SerdeError;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, key: &'static str, value: &T) -> Result<(), SerdeError> {
+        self.insert(key, value)
+    }
+    fn end(self) -> Result<ItemType, SerdeError> { self.finish() }
+}
+
+// Hash keys must be strings; chars, integers and unit variants are written
+// out as text the way the JSON exporters would print them.
+struct KeySerializer;
+
+impl ser::Serializer for KeySerializer {
+    type Ok = String;
+    type Error = SerdeError;
+    type SerializeSeq = ser::Impossible<String, SerdeError>;
+    type SerializeTuple = ser::Impossible<String, SerdeError>;
+    type SerializeTupleStruct = ser::Impossible<String, SerdeError>;
+    type SerializeTupleVariant = ser::Impossible<String, SerdeError>;
+    type SerializeMap = ser::Impossible<String, SerdeError>;
+    type SerializeStruct = ser::Impossible<String, SerdeError>;
+    type SerializeStructVariant = ser::Impossible<String, SerdeError>;
+
+    fn serialize_str(self, v: &str) -> Result<String, SerdeError> { Ok(v.to_string()) }
+    fn serialize_char(self, v: char) -> Result<String, SerdeError> { Ok(v.to_string()) }
+    fn serialize_i8(self, v: i8) -> Result<String, SerdeError> { Ok(v.to_string()) }
+    fn serialize_i16(self, v: i16) -> Result<String, SerdeError> { Ok(v.to_string()) }
+    fn serialize_i32(self, v: i32) -> Result<String, SerdeError> { Ok(v.to_string()) }
+    fn serialize_i64(self, v: i64) -> Result<String, SerdeError> { Ok(v.to_string()) }
+    fn serialize_i128(self, v: i128) -> Result<String, SerdeError> { Ok(v.to_string()) }
+    fn serialize_u8(self, v: u8) -> Result<String, SerdeError> { Ok(v.to_string()) }
+    fn serialize_u16(self, v: u16) -> Result<String, SerdeError> { Ok(v.to_string()) }
+    fn serialize_u32(self, v: u32) -> Result<String, SerdeError> { Ok(v.to_string()) }
+    fn serialize_u64(self, v: u64) -> Result<String, SerdeError> { Ok(v.to_string()) }
+    fn serialize_u128(self, v: u128) -> Result<String, SerdeError> { Ok(v.to_string()) }
+    fn serialize_unit_variant(self, _name: &'static str, _index: u32, variant: &'static str) -> Result<String, SerdeError> {
+        Ok(variant.to_string())
+    }
+    fn serialize_newtype_struct<T: Serialize + ?Sized>(self, _name: &'static str, value: &T) -> Result<String, SerdeError> {
+        value.serialize(self)
+    }
+
+    fn serialize_bool(self, _v: bool) -> Result<String, SerdeError> { Err(SerdeError::KeyMustBeAString) }
+    fn serialize_f32(self, _v: f32) -> Result<String, SerdeError> { Err(SerdeError::KeyMustBeAString) }
+    fn serialize_f64(self, _v: f64) -> Result<String, SerdeError> { Err(SerdeError::KeyMustBeAString) }
+    fn serialize_bytes(self, _v: &[u8]) -> Result<String, SerdeError> { Err(SerdeError::KeyMustBeAString) }
+    fn serialize_none(self) -> Result<String, SerdeError> { Err(SerdeError::KeyMustBeAString) }
+    fn serialize_some<T: Serialize + ?Sized>(self, _value: &T) -> Result<String, SerdeError> {
+        Err(SerdeError::KeyMustBeAString)
+    }
+    fn serialize_unit(self) -> Result<String, SerdeError> { Err(SerdeError::KeyMustBeAString) }
+    fn serialize_unit_struct(self, _name: &'static str) -> Result<String, SerdeError> { Err(SerdeError::KeyMustBeAString) }
+    fn serialize_newtype_variant<T: Serialize + ?Sized>(
+        self,
+        _name: &'static str,
+        _index: u32,
+        _variant: &'static str,
+        _value: &T,
+    ) -> Result<String, SerdeError> {
+        Err(SerdeError::KeyMustBeAString)
+    }
+    fn serialize_seq(self, _len: Option<usize>) -> Result<Self::SerializeSeq, SerdeError> { Err(SerdeError::KeyMustBeAString) }
+    fn serialize_tuple(self, _len: usize) -> Result<Self::SerializeTuple, SerdeError> { Err(SerdeError::KeyMustBeAString) }
+    fn serialize_tuple_struct(self, _name: &'static str, _len: usize) -> Result<Self::SerializeTupleStruct, SerdeError> {
+        Err(SerdeError::KeyMustBeAString)
+    }
+    fn serialize_tuple_variant(
+        self,
+        _name: &'static str,
+        _index: u32,
+        _variant: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeTupleVariant, SerdeError> {
+        Err(SerdeError::KeyMustBeAString)
+    }
+    fn serialize_map(self, _len: Option<usize>) -> Result<Self::SerializeMap, SerdeError> { Err(SerdeError::KeyMustBeAString) }
+    fn serialize_struct(self, _name: &'static str, _len: usize) -> Result<Self::SerializeStruct, SerdeError> {
+        Err(SerdeError::KeyMustBeAString)
+    }
+    fn serialize_struct_variant(
+        self,
+        _name: &'static str,
+        _index: u32,
+        _variant: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeStructVariant, SerdeError> {
+        Err(SerdeError::KeyMustBeAString)
+    }
+}
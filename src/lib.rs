@@ -1,17 +1,26 @@
 pub mod itemdoc {
     pub mod core {
+        pub mod arena;
+        pub mod convert;
+        pub mod errors;
         pub mod items;
+        pub mod macros;
+        pub mod mapping;
         pub mod nulls;
         pub mod booleans;
         pub mod numbers;
         pub mod strings;
         pub mod lists;
         pub mod hashes;
+        pub mod pointers;
         pub mod utility;
+        pub mod visitor;
+        pub mod walk;
     }
     pub mod exports {
         pub mod core;
         pub mod formats;
+        pub mod walker;
         pub mod json {
             pub mod allman;
             pub mod compact;
@@ -20,12 +29,48 @@ pub mod itemdoc {
             pub mod whitesmith;
         }
         pub mod yaml {
+            #[allow(clippy::module_inception)]
             pub mod yaml;
         }
         // pub mod item_output;
     }
+    pub mod imports {
+        pub mod core;
+        pub mod json;
+        pub mod json_events;
+        pub mod yaml;
+    }
+    pub mod patch {
+        pub mod deep_merge;
+        pub mod diff;
+        pub mod json_patch;
+        pub mod merge_patch;
+    }
+    pub mod query {
+        pub mod iregexp;
+        pub mod jsonpath;
+        pub mod unicode;
+    }
+    pub mod schema {
+        pub mod infer;
+        pub mod validate;
+    }
+    #[cfg(feature = "serde")]
+    pub mod serialize {
+        pub mod de;
+        pub mod error;
+        pub mod ser;
+    }
 }
 
+#[cfg(feature = "serde")]
+pub use itemdoc::serialize::{de::from_item, error::SerdeError, ser::to_item};
+
+pub use itemdoc::core::errors::{DocError, DocErrorKind};
+pub use itemdoc::core::mapping::{FromItem, FromItemError, IntoHash, IntoItem};
+#[cfg(feature = "derive")]
+pub use itemdoc_derive::{FromItem, IntoItem};
+
 pub fn add(left: u64, right: u64) -> u64 {
     left + right
 }
@@ -1,11 +1,75 @@
 #[cfg(test)]
+#[allow(clippy::module_inception)]
 mod tests {
 
     use crate::itemdoc::core::lists::*;
     use crate::itemdoc::core::hashes::*;
-    use crate::itemdoc::core::items::ItemType;
-    use crate::itemdoc::exports::core::*;
+    use crate::itemdoc::core::arena::ItemArena;
+    use crate::itemdoc::core::convert::ConversionError;
+    use crate::itemdoc::core::errors::{DocError, DocErrorKind};
+    use crate::itemdoc::core::items::{ContainerKey, ItemError, ItemKind, ItemType};
+    use crate::itemdoc::core::numbers::{ItemNumber, NumberValue};
+    use crate::itemdoc::core::pointers::ItemPointer;
+    use crate::itemdoc::core::strings::ItemString;
+    use crate::itemdoc::core::visitor::*;
+    use crate::itemdoc::core::walk::*;
     use crate::itemdoc::exports::formats::*;
+    use crate::itemdoc::exports::json::compact::*;
+    use crate::itemdoc::exports::json::linear::*;
+    use crate::itemdoc::exports::json::knr::*;
+    use crate::itemdoc::exports::json::allman::*;
+    use crate::itemdoc::exports::json::whitesmith::*;
+    use crate::itemdoc::exports::yaml::yaml::*;
+
+    use crate::itemdoc::imports::core::*;
+    use crate::itemdoc::imports::json;
+    use crate::itemdoc::imports::json_events::*;
+    use crate::itemdoc::imports::yaml;
+    use crate::itemdoc::patch::deep_merge::*;
+    use crate::itemdoc::patch::diff::*;
+    use crate::itemdoc::patch::json_patch::*;
+    use crate::itemdoc::patch::merge_patch::create_merge_patch;
+    use crate::itemdoc::query::iregexp::Regex;
+    use crate::itemdoc::query::jsonpath::{Query, QueryErrorKind};
+    use crate::itemdoc::schema::infer::*;
+    use crate::itemdoc::schema::validate::*;
+
+    use std::cell::RefCell;
+    use std::collections::{BTreeMap, HashMap};
+    use std::io::Write;
+    use std::rc::Rc;
+
+    #[derive(Clone, Default)]
+    struct SharedBuffer(Rc<RefCell<Vec<u8>>>);
+
+    impl SharedBuffer {
+        fn contents(&self) -> String {
+            String::from_utf8(self.0.borrow().clone()).unwrap().replace("\r\n", "\n")
+        }
+    }
+
+    impl Write for SharedBuffer {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0.borrow_mut().extend_from_slice(buf);
+            Ok(buf.len())
+        }
+        fn flush(&mut self) -> std::io::Result<()> { Ok(()) }
+    }
+
+    // The parser's own error, which a failed import keeps as its source.
+    fn parse_error(error: DocError) -> ParseError {
+        *std::error::Error::source(&error).and_then(|source| source.downcast_ref::<ParseError>()).unwrap()
+    }
+
+    fn sample_document() -> ItemType {
+        let mut doc = ItemHash::new();
+        let list = doc.add_list(Some("a")).unwrap();
+        list.add_value(1, None).unwrap();
+        list.add_hash(None).unwrap().add_null(Some("b")).unwrap();
+        list.add_list(None).unwrap();
+        list.add_hash(None).unwrap();
+        doc
+    }
 
     #[test]
     fn test_add_null_to_item_list() {
@@ -18,7 +82,7 @@ mod tests {
     fn test_add_number_to_item_list() {
         let mut list = ItemList::new();
         list.add_value(42.0,None).unwrap();
-        assert_eq!(list.to_string(), "[42]");
+        assert_eq!(list.to_string(), "[42.0]");
     }
 
     #[test]
@@ -60,7 +124,7 @@ mod tests {
         list.add_value("Hello World!", None).unwrap();
         list.add_list(None).unwrap();
         list.add_hash( None).unwrap();
-        assert_eq!(list.to_string(), "[null,true,42,Hello World!,[],{}]");
+        assert_eq!(list.to_string(), "[null,true,42.0,Hello World!,[],{}]");
     }
 
     #[test]
@@ -81,7 +145,7 @@ mod tests {
     fn test_add_number_to_item_hash() {
         let mut hash = ItemHash::new();
         hash.add_value(42.0, Some("Number")).unwrap();
-        assert_eq!(hash.to_string(), "{\"Number\":42}");
+        assert_eq!(hash.to_string(), "{\"Number\":42.0}");
     }
 
     #[test]
@@ -116,22 +180,22 @@ mod tests {
         hash.add_list(Some("List")).unwrap();
         hash.add_hash(Some("Hash")).unwrap();
 
-        assert_eq!(hash.has_key("Null"),true);
+        assert!(hash.has_key("Null"));
         assert!( hash.get_item("Null").is_ok_and(|x| matches!(x, Some(ItemType::TNull(_)))) );
 
-        assert_eq!(hash.has_key("True"),true);
+        assert!(hash.has_key("True"));
         assert!( hash.get_item("True").is_ok_and(|x| matches!(x,  Some(ItemType::TBoolean(_)))) );
 
-        assert_eq!(hash.has_key("Number"),true);
+        assert!(hash.has_key("Number"));
         assert!( hash.get_item("Number").is_ok_and(|x| matches!(x,  Some(ItemType::TNumber(_)))) );
 
-        assert_eq!(hash.has_key("String"),true);
+        assert!(hash.has_key("String"));
         assert!( hash.get_item("String").is_ok_and(|x| matches!(x,  Some(ItemType::TString(_)))) );
 
-        assert_eq!(hash.has_key("List"),true);
+        assert!(hash.has_key("List"));
         assert!( hash.get_item("List").is_ok_and(|x| matches!(x,  Some(ItemType::TList(_)))) );
 
-        assert_eq!(hash.has_key("Hash"),true);
+        assert!(hash.has_key("Hash"));
         assert!( hash.get_item("Hash").is_ok_and(|x| matches!(x,  Some(ItemType::THash(_)))) );
     }
 
@@ -142,4 +206,1468 @@ mod tests {
         assert_eq!(list.to_string(), "[null]");
     }
 
-}
\ No newline at end of file
+    #[test]
+    fn test_export_fmt_compact() {
+        let buffer = SharedBuffer::default();
+        let mut output = JSONFormatCompact::new(Box::new(buffer.clone()));
+        sample_document().export(&mut output).unwrap();
+        assert_eq!(buffer.contents(), "{\"a\":[1,{\"b\":null},[],{}]}");
+    }
+
+    #[test]
+    fn test_export_fmt_linear() {
+        let buffer = SharedBuffer::default();
+        let mut output = JSONFormatLinear::new(Box::new(buffer.clone()));
+        sample_document().export(&mut output).unwrap();
+        assert_eq!(buffer.contents(), "{ \"a\": [ 1, { \"b\": null }, [ ], { } ] }");
+    }
+
+    #[test]
+    fn test_export_fmt_knr() {
+        let buffer = SharedBuffer::default();
+        let mut output = JSONFormatKNR::new(Box::new(buffer.clone()), Some("  ".to_string()));
+        sample_document().export(&mut output).unwrap();
+        assert_eq!(buffer.contents(), concat!(
+            "{\n",
+            "  \"a\": [\n",
+            "    1,\n",
+            "    {\n",
+            "      \"b\": null\n",
+            "    },\n",
+            "    [],\n",
+            "    {}\n",
+            "  ]\n",
+            "}"));
+    }
+
+    #[test]
+    fn test_export_fmt_allman() {
+        let buffer = SharedBuffer::default();
+        let mut output = JSONFormatAllman::new(Box::new(buffer.clone()), Some("  ".to_string()));
+        sample_document().export(&mut output).unwrap();
+        assert_eq!(buffer.contents(), concat!(
+            "{\n",
+            "  \"a\":\n",
+            "  [\n",
+            "    1,\n",
+            "    {\n",
+            "      \"b\": null\n",
+            "    },\n",
+            "    [ ],\n",
+            "    { }\n",
+            "  ]\n",
+            "}"));
+    }
+
+    #[test]
+    fn test_export_fmt_whitesmith() {
+        let buffer = SharedBuffer::default();
+        let mut output = JSONFormatWhitesmith::new(Box::new(buffer.clone()), Some("  ".to_string()));
+        sample_document().export(&mut output).unwrap();
+        assert_eq!(buffer.contents(), concat!(
+            "{\n",
+            "  \"a\":\n",
+            "    [\n",
+            "    1,\n",
+            "      {\n",
+            "      \"b\": null\n",
+            "      },\n",
+            "    [ ],\n",
+            "    { }\n",
+            "    ]\n",
+            "}"));
+    }
+
+    #[test]
+    fn test_export_scalar_and_empty_roots() {
+        let buffer = SharedBuffer::default();
+        let mut output = OutputFormats::Compact(JSONFormatCompact::new(Box::new(buffer.clone())));
+        ItemNumber::new(Some(2.5)).export(&mut output).unwrap();
+        assert_eq!(buffer.contents(), "2.5");
+
+        let buffer = SharedBuffer::default();
+        let mut output = OutputFormats::Compact(JSONFormatCompact::new(Box::new(buffer.clone())));
+        ItemList::new().export(&mut output).unwrap();
+        assert_eq!(buffer.contents(), "[]");
+    }
+
+    #[test]
+    fn test_json_parse_roundtrip() {
+        let doc = json::parse_str(" {\"a\" : [1, {\"b\": null}, [], {}]} ").unwrap();
+        assert!(doc == sample_document());
+
+        let buffer = SharedBuffer::default();
+        let mut output = JSONFormatCompact::new(Box::new(buffer.clone()));
+        doc.export(&mut output).unwrap();
+        assert!(json::parse_str(&buffer.contents()).unwrap() == doc);
+    }
+
+    #[test]
+    fn test_json_parse_scalars_and_escapes() {
+        let doc = json::parse_str("[true, false, -1.5e2, 0, \"a\\\"\\u00e9\\ud83d\\ude00\\n\"]").unwrap();
+        assert_eq!(doc.to_string(), "[true,false,-150.0,0,a\"\u{e9}\u{1F600}\n]");
+
+        let doc = json::parse_reader("\"caf\u{e9}\"".as_bytes()).unwrap();
+        assert_eq!(doc.to_string(), "caf\u{e9}");
+    }
+
+    #[test]
+    fn test_json_parse_errors() {
+        let err = |input: &str| match json::parse_str(input) {
+            Err(e) => parse_error(e),
+            _ => panic!("expected a parse error for {:?}", input),
+        };
+
+        let e = err("{\n  \"a\": tru }");
+        assert_eq!(e.kind, ParseErrorKind::UnexpectedCharacter(' '));
+        assert_eq!((e.offset, e.line, e.column), (12, 2, 11));
+
+        assert_eq!(err("[1,]").kind, ParseErrorKind::UnexpectedCharacter(']'));
+        assert_eq!(err("[01]").kind, ParseErrorKind::UnexpectedCharacter('1'));
+        assert_eq!(err("\"\\ud800\"").kind, ParseErrorKind::InvalidUnicode);
+        assert_eq!(err("\"a\tb\"").kind, ParseErrorKind::ControlCharacter);
+        assert_eq!(err("{} x").kind, ParseErrorKind::TrailingCharacters);
+        assert_eq!(err("[").kind, ParseErrorKind::UnexpectedEndOfInput);
+    }
+
+    #[test]
+    fn test_json_event_reader() {
+        let events: Vec<JsonEvent> = JsonEventReader::new("{\"a\": [1, {}], \"b\": {\"c\": \"x\"}}".as_bytes())
+            .collect::<Result<_, _>>()
+            .unwrap();
+        assert_eq!(events, vec![
+            JsonEvent::HashOpen,
+            JsonEvent::Key("a".to_string()),
+            JsonEvent::ListOpen,
+            JsonEvent::Number(NumberValue::Int(1)),
+            JsonEvent::EmptyHash,
+            JsonEvent::ListClose,
+            JsonEvent::Key("b".to_string()),
+            JsonEvent::HashOpen,
+            JsonEvent::Key("c".to_string()),
+            JsonEvent::String("x".to_string()),
+            JsonEvent::HashClose,
+            JsonEvent::HashClose,
+        ]);
+
+        let mut events = JsonEventReader::new("[1 2]".as_bytes());
+        assert!(events.next_event().is_ok());
+        assert!(events.next_event().is_ok());
+        assert!(matches!(events.next_event().unwrap_err().get_kind(), DocErrorKind::Parse(_)));
+        assert!(events.next_event().unwrap().is_none());
+    }
+
+    #[test]
+    fn test_json_pipe_to_output() {
+        let input = "{\"a\": [1, {\"b\": null}, [], {}]}";
+        let buffer = SharedBuffer::default();
+        let mut output = OutputFormats::KNR(JSONFormatKNR::new(Box::new(buffer.clone()), Some("  ".to_string())));
+        pipe_str(input, &mut output).unwrap();
+
+        let expected = SharedBuffer::default();
+        let mut output = JSONFormatKNR::new(Box::new(expected.clone()), Some("  ".to_string()));
+        sample_document().export(&mut output).unwrap();
+        assert_eq!(buffer.contents(), expected.contents());
+    }
+
+    #[test]
+    fn test_export_fmt_yaml() {
+        let buffer = SharedBuffer::default();
+        let mut output = YAMLFormat::new(Box::new(buffer.clone()), None);
+        sample_document().export(&mut output).unwrap();
+        assert_eq!(buffer.contents(), concat!(
+            "a:\n",
+            "  - 1\n",
+            "  - b: null\n",
+            "  - []\n",
+            "  - {}"));
+
+        let doc = json::parse_str("[[1, [2]], {\"k\": {\"x: y\": [true]}}, {\"-\": {}}]").unwrap();
+        let buffer = SharedBuffer::default();
+        let mut output = YAMLFormat::new(Box::new(buffer.clone()), Some("    ".to_string()));
+        doc.export(&mut output).unwrap();
+        assert_eq!(buffer.contents(), concat!(
+            "- - 1\n",
+            "  - - 2\n",
+            "- k:\n",
+            "      \"x: y\":\n",
+            "          - true\n",
+            "- \"-\": {}"));
+    }
+
+    #[test]
+    fn test_yaml_scalar_quoting() {
+        for plain in ["hello world", "a:b", "1.2.3", "inf", "key_1", "caf\u{e9}"] {
+            assert_eq!(make_yaml_string(plain), plain);
+        }
+        for quoted in ["", "true", "null", "~", "yes", "1e3", "-1", ".5", "0x1F", ".inf",
+                       "a: b", "a #b", "- a", "[x]", " pad", "end:", "line\nbreak", "..."] {
+            assert!(make_yaml_string(quoted).starts_with('"'), "{:?}", quoted);
+        }
+        assert_eq!(make_yaml_number(&NumberValue::Float(f64::NEG_INFINITY)), "-.inf");
+    }
+
+    #[test]
+    fn test_yaml_parse_block_collections() {
+        let doc = yaml::parse_str(concat!(
+            "# leading comment\n",
+            "---\n",
+            "a:\n",
+            "  - 1\n",
+            "  - b: null   # trailing comment\n",
+            "  - []\n",
+            "  - {}\n",
+            "...\n")).unwrap();
+        assert!(doc == sample_document());
+
+        let doc = yaml::parse_str("- - 1\n  - 2\n- k: v\n  n:\n  - x\n-\n").unwrap();
+        assert!(doc == json::parse_str("[[1, 2], {\"k\": \"v\", \"n\": [\"x\"]}, null]").unwrap());
+    }
+
+    #[test]
+    fn test_yaml_parse_flow_and_scalars() {
+        let doc = yaml::parse_str(concat!(
+            "flow: {a: [1, 'two', \"th\\u0072ee\"], b: }\n",
+            "types: [~, true, False, 0x1F, 0o17, -1.5e1, 1.2.3, yes, '', 'it''s']\n",
+            "multi: this is\n",
+            "  one line\n")).unwrap();
+        let expected = json::parse_str(concat!(
+            "{\"flow\": {\"a\": [1, \"two\", \"three\"], \"b\": null},",
+            " \"types\": [null, true, false, 31, 15, -15, \"1.2.3\", \"yes\", \"\", \"it's\"],",
+            " \"multi\": \"this is one line\"}")).unwrap();
+        assert!(doc == expected);
+        assert!(matches!(yaml::resolve_plain_scalar("-.inf"), ItemType::TNumber(n) if n.get_value() == f64::NEG_INFINITY));
+    }
+
+    #[test]
+    fn test_yaml_parse_block_scalars() {
+        let doc = yaml::parse_str(concat!(
+            "literal: |\n",
+            "  line 1\n",
+            "    indented\n",
+            "\n",
+            "folded: >-\n",
+            "  folded\n",
+            "  text\n",
+            "\n",
+            "  next\n",
+            "keep: |+\n",
+            "  kept\n",
+            "\n",
+            "last: end\n")).unwrap();
+        let get = |key: &str| doc.get_item(key).unwrap().unwrap().to_string();
+        assert_eq!(get("literal"), "line 1\n  indented\n");
+        assert_eq!(get("folded"), "folded text\nnext");
+        assert_eq!(get("keep"), "kept\n\n");
+        assert_eq!(get("last"), "end");
+    }
+
+    #[test]
+    fn test_yaml_roundtrip_with_emitter() {
+        let doc = json::parse_str("{\"list\": [\"true\", \"a: b\", \"\", 1.5, [\"x\", {\"k\": \"-\"}]]}").unwrap();
+        let buffer = SharedBuffer::default();
+        let mut output = YAMLFormat::new(Box::new(buffer.clone()), None);
+        doc.export(&mut output).unwrap();
+        assert!(yaml::parse_str(&buffer.contents()).unwrap() == doc);
+    }
+
+    #[test]
+    fn test_yaml_parse_errors() {
+        let err = |input: &str| match yaml::parse_str(input) {
+            Err(e) => parse_error(e),
+            _ => panic!("expected a parse error for {:?}", input),
+        };
+
+        let e = err("a:\n  b: 1\n c: 2\n");
+        assert_eq!(e.kind, ParseErrorKind::InvalidIndentation);
+        assert_eq!((e.line, e.column, e.offset), (3, 2, 11));
+
+        assert_eq!(err("a: b: c").kind, ParseErrorKind::UnexpectedCharacter('b'));
+        assert_eq!(err("a:\n\t- 1").kind, ParseErrorKind::InvalidIndentation);
+        assert_eq!(err("a: [1, 2").kind, ParseErrorKind::UnexpectedEndOfInput);
+        assert_eq!(err("a: \"open").kind, ParseErrorKind::UnexpectedEndOfInput);
+        assert_eq!(err("a: &anchor 1").kind, ParseErrorKind::UnsupportedFeature);
+        assert_eq!(err("a: 1\n---\nb: 2").kind, ParseErrorKind::TrailingCharacters);
+    }
+
+    #[test]
+    fn test_hash_preserves_insertion_order() {
+        let mut hash = ItemHash::new();
+        for key in ["zeta", "alpha", "mid", "beta"] {
+            hash.add_value(key, Some(key)).unwrap();
+        }
+        hash.remove_item("mid".into()).unwrap();
+        hash.add_value(1, Some("alpha")).unwrap();
+        assert_eq!(hash.to_string(), "{\"zeta\":zeta,\"alpha\":1,\"beta\":beta}");
+
+        let buffer = SharedBuffer::default();
+        let mut output = JSONFormatCompact::new(Box::new(buffer.clone()));
+        json::parse_str("{\"b\": 1, \"a\": {\"y\": 2, \"x\": 3}}").unwrap().export(&mut output).unwrap();
+        assert_eq!(buffer.contents(), "{\"b\":1,\"a\":{\"y\":2,\"x\":3}}");
+    }
+
+    #[test]
+    fn test_hash_reordering() {
+        let mut hash = json::parse_str("{\"c\": 1, \"a\": 3, \"b\": 2}").unwrap();
+        let reversed = json::parse_str("{\"b\": 2, \"a\": 3, \"c\": 1}").unwrap();
+        assert!(hash == reversed);
+
+        hash.sort_keys().unwrap();
+        assert_eq!(hash.to_string(), "{\"a\":3,\"b\":2,\"c\":1}");
+
+        hash.sort_by(|_, a, _, b| a.to_string().cmp(&b.to_string())).unwrap();
+        assert_eq!(hash.to_string(), "{\"c\":1,\"b\":2,\"a\":3}");
+
+        hash.move_key("a", 1).unwrap();
+        assert_eq!(hash.to_string(), "{\"c\":1,\"a\":3,\"b\":2}");
+        assert!(hash.move_key("z", 0).unwrap_err() == ItemError::ItemNotFound);
+        assert!(ItemList::new().sort_keys().unwrap_err() == ItemError::NotAnItemHash);
+    }
+
+    #[test]
+    fn test_hash_swap_removal() {
+        let mut hash = ItemHash::with_removal_order(RemovalOrder::Swap);
+        for key in ["a", "b", "c", "d"] {
+            hash.add_value(key, Some(key)).unwrap();
+        }
+        hash.remove_item("a".into()).unwrap();
+        assert_eq!(hash.count(), 3);
+        assert_eq!(hash.get_keys().unwrap(), vec![ContainerKey::Key("d"), ContainerKey::Key("b"), ContainerKey::Key("c")]);
+        for key in ["b", "c", "d"] {
+            assert!(hash.get_item(key).unwrap().is_some_and(|v| v.to_string() == key));
+        }
+        assert!(matches!(hash.add_hash(Some("h")).unwrap(), ItemType::THash(h) if h.get_removal_order() == RemovalOrder::Swap));
+    }
+
+    #[test]
+    fn test_number_representations() {
+        let mut list = ItemList::new();
+        list.add_value(i64::MAX, None).unwrap();
+        list.add_value(u64::MAX, None).unwrap();
+        list.add_value(42.0, None).unwrap();
+        list.add_value(-7i8, None).unwrap();
+        assert_eq!(list.to_string(), "[9223372036854775807,18446744073709551615,42.0,-7]");
+
+        let buffer = SharedBuffer::default();
+        let mut output = JSONFormatCompact::new(Box::new(buffer.clone()));
+        list.export(&mut output).unwrap();
+        assert_eq!(buffer.contents(), "[9223372036854775807,18446744073709551615,42.0,-7]");
+
+        let doc = json::parse_str(&buffer.contents()).unwrap();
+        let number = |i: usize| match doc.get_item(i).unwrap() {
+            Some(ItemType::TNumber(n)) => n,
+            _ => panic!("not a number"),
+        };
+        assert_eq!(number(0).as_i64(), Some(i64::MAX));
+        assert_eq!(number(1).as_u64(), Some(u64::MAX));
+        assert_eq!(number(1).as_i64(), None);
+        assert!(number(2).is_float() && number(2).as_i64() == Some(42));
+        assert!(number(3).is_integer() && number(3).as_u64().is_none());
+        assert!(doc == list);
+    }
+
+    #[test]
+    fn test_number_precise_decimals() {
+        let doc = json::parse_str("[3.14159265358979323846264338, 0.1, 1e400, 170141183460469231731687303715884105727]").unwrap();
+        assert_eq!(doc.to_string(), "[3.14159265358979323846264338,0.1,1e400,170141183460469231731687303715884105727]");
+        let number = |i: usize| match doc.get_item(i).unwrap() {
+            Some(ItemType::TNumber(n)) => n,
+            _ => panic!("not a number"),
+        };
+        assert!(number(0).is_decimal() && number(0).as_f64().is_none());
+        assert_eq!(number(1).as_f64(), Some(0.1));
+        assert_eq!(number(3).as_i128(), Some(i128::MAX));
+        assert_eq!(number(3).as_u64(), None);
+
+        let floats = [1e300, -2.5e-7, 1e21, 1e20, 0.000001, 0.1 + 0.2];
+        let texts: Vec<String> = floats.iter().map(|f| NumberValue::Float(*f).to_string()).collect();
+        assert_eq!(texts, ["1e300", "-2.5e-7", "1e21", "100000000000000000000.0", "0.000001", "0.30000000000000004"]);
+        assert!(texts.iter().all(|text| NumberValue::parse(text).is_some_and(|n| n.is_float())));
+        assert_eq!(ItemNumber::new(Some(1e300)).to_string(), "1e300");
+
+        let tiny = json::parse_str("[1e-99999999999999999999, -0.5e-9223372036854775808]").unwrap();
+        assert_eq!(tiny, crate::item!([0, 0]));
+        let error = json::parse_str("[1, 1e99999999999999999999]").unwrap_err();
+        assert!(matches!(parse_error(error), ParseError { kind: ParseErrorKind::NumberOutOfRange, offset: 4, .. }));
+
+        assert_eq!(NumberValue::Int(5), NumberValue::Float(5.0));
+        assert_eq!(NumberValue::from_decimal_str("1.50"), Some(NumberValue::Float(1.5)));
+        assert_eq!(NumberValue::from_decimal_str("12e-1").unwrap().to_string(), "1.2");
+        assert_eq!(NumberValue::from_decimal_str("1.2.3"), None);
+        assert!(NumberValue::Int(9007199254740993) > NumberValue::Float(9007199254740992.0));
+        assert!(NumberValue::parse("0.30000000000000000001").unwrap() > NumberValue::Float(0.3));
+        assert!(NumberValue::parse("-1e400").unwrap() < NumberValue::Int(i64::MIN));
+        assert!(NumberValue::parse("1e-400").unwrap() < NumberValue::UInt(1));
+        assert!(NumberValue::Float(f64::INFINITY) > NumberValue::parse("1e400").unwrap());
+        assert_eq!(NumberValue::Float(f64::NAN).partial_cmp(&NumberValue::Int(0)), None);
+        assert_eq!(yaml::resolve_plain_scalar("0xFF").to_string(), "255");
+    }
+
+    #[test]
+    fn test_list_mutation() {
+        let mut list = ItemList::new();
+        for n in 0..4 {
+            list.add_value(n, None).unwrap();
+        }
+        let old = list.replace_item(1.into(), ItemHash::new()).unwrap();
+        assert_eq!(old.to_string(), "1");
+        list.insert_at(0, ItemList::new(), None).unwrap();
+        assert_eq!(list.to_string(), "[[],0,{},2,3]");
+
+        assert!(list.add_or_replace(5.into(), ItemNumber::new(Some(4.5))).unwrap().is_none());
+        assert_eq!(list.add_or_replace(0.into(), ItemNumber::new(Some(9.0))).unwrap().unwrap().to_string(), "[]");
+        list.swap(0.into(), 5.into()).unwrap();
+        list.move_item(1.into(), 4).unwrap();
+        assert_eq!(list.to_string(), "[4.5,{},2,3,0,9.0]");
+
+        list.truncate(2).unwrap();
+        assert_eq!(list.to_string(), "[4.5,{}]");
+        assert!(list.replace_item(2.into(), ItemHash::new()).unwrap_err() == ItemError::ItemNotFound);
+        assert!(list.insert_at(3, ItemHash::new(), None).unwrap_err() == ItemError::IndexOutOfRange(3));
+        assert!(list.move_item(0.into(), 2).unwrap_err() == ItemError::IndexOutOfRange(2));
+        assert!(list.swap("a".into(), 0.into()).unwrap_err() == ItemError::NotAnItemHash);
+        list.clear().unwrap();
+        assert_eq!(list.count(), 0);
+        assert!(ItemNumber::new(None).clear().unwrap_err() == ItemError::NotAnItemContainer);
+    }
+
+    #[test]
+    fn test_hash_mutation() {
+        let mut hash = ItemHash::new();
+        for key in ["a", "b", "c"] {
+            hash.add_value(key, Some(key)).unwrap();
+        }
+        assert_eq!(hash.replace_item("b".into(), ItemList::new()).unwrap().to_string(), "b");
+        hash.insert_at(0, ItemHash::new(), Some("z")).unwrap();
+        hash.insert_at(3, ItemNumber::new(Some(1.0)), Some("a")).unwrap();
+        assert_eq!(hash.to_string(), "{\"z\":{},\"b\":[],\"c\":c,\"a\":1.0}");
+
+        assert!(hash.add_or_replace("d".into(), ItemList::new()).unwrap().is_none());
+        assert_eq!(hash.add_or_replace("z".into(), ItemList::new()).unwrap().unwrap().to_string(), "{}");
+        hash.swap("z".into(), "d".into()).unwrap();
+        hash.move_item("a".into(), 0).unwrap();
+        assert_eq!(hash.to_string(), "{\"a\":1.0,\"d\":[],\"b\":[],\"c\":c,\"z\":[]}");
+
+        hash.truncate(2).unwrap();
+        assert!(!hash.has_key("b") && hash.count() == 2);
+        assert!(hash.replace_item("q".into(), ItemList::new()).unwrap_err() == ItemError::ItemNotFound);
+        assert!(hash.insert_at(0, ItemList::new(), None).unwrap_err() == ItemError::NotAnItemList);
+        assert!(hash.move_item(0.into(), 1).unwrap_err() == ItemError::NotAnItemList);
+        assert!(hash.insert_at(3, ItemList::new(), Some("q")).unwrap_err() == ItemError::IndexOutOfRange(3));
+        assert!(hash.move_key("a", 2).unwrap_err() == ItemError::IndexOutOfRange(2));
+        hash.clear().unwrap();
+        assert!(hash.count() == 0 && !hash.has_key("a"));
+    }
+
+    #[test]
+    fn test_get_item_mut() {
+        let mut doc = sample_document();
+        let list = doc.get_item_mut("a").unwrap().unwrap();
+        list.get_item_mut(1).unwrap().unwrap().add_value(true, Some("c")).unwrap();
+        *list.get_item_mut(0).unwrap().unwrap() = ItemNumber::new(Some(2.5));
+        assert_eq!(doc.to_string(), "{\"a\":[2.5,{\"b\":null,\"c\":true},[],{}]}");
+
+        assert!(doc.get_item_mut("z").unwrap().is_none());
+        assert!(doc.get_item_mut(0).unwrap_err() == ItemError::NotAnItemList);
+        assert!(ItemNumber::new(None).get_item_mut(0).unwrap_err() == ItemError::NotAnItemContainer);
+    }
+
+    #[test]
+    fn test_hash_entry() {
+        let mut doc = ItemHash::new();
+        doc.entry("server").unwrap().or_insert_hash()
+            .entry("ports").unwrap().or_insert_list()
+            .add_value(80, None).unwrap();
+        doc.entry("server").unwrap().or_insert_hash()
+            .entry("ports").unwrap().or_insert_list()
+            .add_value(443, None).unwrap();
+        doc.entry("name").unwrap().or_insert_value("x");
+        doc.entry("name").unwrap().or_insert_value("y");
+        doc.entry("count").unwrap()
+            .and_modify(|_| panic!("vacant entry modified"))
+            .or_insert_with(|| ItemNumber::new(Some(0.0)));
+        doc.entry("count").unwrap()
+            .and_modify(|n| *n = ItemNumber::new(Some(1.0)))
+            .or_insert_null();
+        assert_eq!(doc.to_string(), "{\"server\":{\"ports\":[80,443]},\"name\":x,\"count\":1.0}");
+        assert!(ItemList::new().entry("a").err().unwrap() == ItemError::NotAnItemHash);
+    }
+
+    #[test]
+    fn test_pointer_parse_and_print() {
+        let pointer = ItemPointer::parse("/a~1b/~0c/0/").unwrap();
+        assert_eq!(pointer.get_segments(), ["a/b", "~c", "0", ""]);
+        assert_eq!(pointer.to_string(), "/a~1b/~0c/0/");
+        assert!(ItemPointer::parse("").unwrap().is_root());
+        assert!(ItemPointer::parse("a/b").unwrap_err() == ItemError::InvalidPointer);
+        assert!(ItemPointer::parse("/a~2").unwrap_err() == ItemError::InvalidPointer);
+        assert_eq!(ItemPointer::from_segments(["x", "1"]).join("y").to_string(), "/x/1/y");
+    }
+
+    #[test]
+    fn test_pointer_navigation() {
+        let mut doc = json::parse_str("{\"servers\": [{\"ports\": [80, 443]}], \"a/b\": 1}").unwrap();
+        let ptr = |text: &str| ItemPointer::parse(text).unwrap();
+        assert_eq!(doc.pointer(&ptr("/servers/0/ports/1")).unwrap().to_string(), "443");
+        assert_eq!(doc.pointer(&ptr("/a~1b")).unwrap().to_string(), "1");
+        assert!(doc.pointer(&ptr("")).unwrap() == &doc);
+
+        assert!(doc.pointer(&ptr("/servers/1/ports")).unwrap_err() == ItemError::PointerNotFound(1));
+        assert!(doc.pointer(&ptr("/servers/01")).unwrap_err() == ItemError::PointerInvalidIndex(1));
+        assert!(doc.pointer(&ptr("/servers/-")).unwrap_err() == ItemError::PointerNotFound(1));
+        assert!(doc.pointer(&ptr("/a~1b/x")).unwrap_err() == ItemError::PointerNotAContainer(1));
+
+        *doc.pointer_mut(&ptr("/servers/0/ports/0")).unwrap() = ItemNumber::new(Some(8080.0));
+        assert_eq!(doc.pointer(&ptr("/servers/0/ports")).unwrap().to_string(), "[8080.0,443]");
+    }
+
+    #[test]
+    fn test_pointer_set_and_remove() {
+        let mut doc = ItemHash::new();
+        let ptr = |text: &str| ItemPointer::parse(text).unwrap();
+        assert!(doc.set_at_pointer(&ptr("/a/0/b"), ItemList::new(), false).unwrap_err() == ItemError::PointerNotFound(0));
+        assert!(doc.set_at_pointer(&ptr("/a/0/b"), ItemList::new(), true).unwrap().is_none());
+        doc.set_at_pointer(&ptr("/a/0/b/-"), ItemNumber::new(Some(1.0)), false).unwrap();
+        doc.set_at_pointer(&ptr("/a/-"), ItemNumber::new(Some(2.0)), false).unwrap();
+        assert_eq!(doc.to_string(), "{\"a\":[{\"b\":[1.0]},2.0]}");
+
+        let old = doc.set_at_pointer(&ptr("/a/1"), ItemNumber::new(Some(3.0)), false).unwrap();
+        assert_eq!(old.unwrap().to_string(), "2.0");
+        assert!(doc.set_at_pointer(&ptr("/a/5"), ItemList::new(), true).unwrap_err() == ItemError::PointerNotFound(1));
+
+        assert_eq!(doc.remove_at_pointer(&ptr("/a/0/b/0")).unwrap().to_string(), "1.0");
+        assert_eq!(doc.remove_at_pointer(&ptr("/a/0")).unwrap().to_string(), "{\"b\":[]}");
+        assert!(doc.remove_at_pointer(&ptr("/a/3")).unwrap_err() == ItemError::PointerNotFound(1));
+        assert!(doc.remove_at_pointer(&ptr("")).unwrap_err() == ItemError::InvalidPointer);
+        assert_eq!(doc.to_string(), "{\"a\":[3.0]}");
+    }
+
+    fn store_document() -> ItemType {
+        json::parse_str(concat!(
+            "{\"items\": [",
+            "{\"id\": 1, \"price\": 8.95, \"name\": \"pen\", \"enabled\": true},",
+            "{\"id\": 2, \"price\": 12.5, \"name\": \"book\", \"enabled\": false},",
+            "{\"id\": 3, \"price\": 22, \"name\": \"bag\", \"enabled\": true, \"tags\": [\"a\", \"b\"]}",
+            "], \"owner\": {\"id\": 9, \"name\": \"it's\"}}")).unwrap()
+    }
+
+    fn query_strings(doc: &ItemType, path: &str) -> Vec<String> {
+        doc.query(path).unwrap().iter().map(|m| m.item.to_string()).collect()
+    }
+
+    #[test]
+    fn test_jsonpath_selectors() {
+        let doc = store_document();
+        assert_eq!(query_strings(&doc, "$.items[*].price"), ["8.95", "12.5", "22"]);
+        assert_eq!(query_strings(&doc, "$..id"), ["1", "2", "3", "9"]);
+        assert_eq!(query_strings(&doc, "$.items[-1]['name', 'id']"), ["bag", "3"]);
+        assert_eq!(query_strings(&doc, "$.items[::-2].id"), ["3", "1"]);
+        assert_eq!(query_strings(&doc, "$.items[1:].id"), ["2", "3"]);
+        assert_eq!(query_strings(&doc, "$.items[5]"), Vec::<String>::new());
+
+        let paths: Vec<String> = doc.query("$..tags[0]").unwrap().iter().map(|m| m.path.to_string()).collect();
+        assert_eq!(paths, ["$['items'][2]['tags'][0]"]);
+        let owner = doc.query("$.owner.name").unwrap();
+        assert_eq!(owner[0].path.to_pointer().to_string(), "/owner/name");
+    }
+
+    #[test]
+    fn test_jsonpath_filters() {
+        let doc = store_document();
+        assert_eq!(query_strings(&doc, "$.items[?(@.enabled == true)].name"), ["pen", "bag"]);
+        assert_eq!(query_strings(&doc, "$.items[?@.price < 10 || @.id >= 3].id"), ["1", "3"]);
+        assert_eq!(query_strings(&doc, "$.items[?@.tags].id"), ["3"]);
+        assert_eq!(query_strings(&doc, "$.items[?!@.tags && @.id != 1].id"), ["2"]);
+        assert_eq!(query_strings(&doc, "$.items[?@.id == $.owner.id]"), Vec::<String>::new());
+        assert_eq!(query_strings(&doc, "$.items[?length(@.name) == 4].id"), ["2"]);
+        assert_eq!(query_strings(&doc, "$.items[?count(@.*) > 4].id"), ["3"]);
+        assert_eq!(query_strings(&doc, "$.items[?match(@.name, 'b[a-z]+')].id"), ["2", "3"]);
+        assert_eq!(query_strings(&doc, "$.items[?search(@.name, 'o{2}')].id"), ["2"]);
+        assert_eq!(query_strings(&doc, "$[?value(@..id) == 9].name"), ["it's"]);
+
+        let numbers = json::parse_str("{\"n\": [100000000000000000000000.5, 1e30, 3]}").unwrap();
+        assert_eq!(query_strings(&numbers, "$.n[?@ < 100000000000000000000001]"), ["100000000000000000000000.5", "3"]);
+    }
+
+    #[test]
+    fn test_jsonpath_mutation() {
+        let mut doc = store_document();
+        let updated = doc.query_mut("$.items[?@.enabled == false].enabled", |_, item| {
+            *item = ItemNumber::new(Some(0.0));
+        }).unwrap();
+        assert_eq!(updated, 1);
+        let query = Query::compile("$..price").unwrap();
+        query.apply_mut(&mut doc, |path, item| {
+            let cents = (item.to_string().parse::<f64>().unwrap() * 100.0).round();
+            *item = ItemNumber::new(Some(cents));
+            assert!(path.to_string().starts_with("$['items']["));
+        });
+        assert_eq!(query_strings(&doc, "$.items[*].price"), ["895.0", "1250.0", "2200.0"]);
+        assert_eq!(query_strings(&doc, "$.items[1].enabled"), ["0.0"]);
+
+        let mut nested = json::parse_str("[[1, 2], 3]").unwrap();
+        let visited = nested.query_mut("$..*", |_, item| match item {
+            ItemType::TList(list) => list.insert_at(0, ItemString::new(Some("x".to_string())), None).unwrap(),
+            _ => *item = ItemNumber::new(Some(item.to_string().parse::<f64>().unwrap() * 10.0)),
+        }).unwrap();
+        assert_eq!(visited, 4);
+        assert_eq!(nested.to_string(), "[[x,10.0,20.0],30.0]");
+    }
+
+    #[test]
+    fn test_jsonpath_compile_errors() {
+        let kind = |path: &str| Query::compile(path).err().map(|e| e.kind);
+        assert_eq!(kind("items"), Some(QueryErrorKind::UnexpectedCharacter('i')));
+        assert_eq!(kind("$.items "), Some(QueryErrorKind::TrailingCharacters));
+        assert_eq!(kind("$[01]"), Some(QueryErrorKind::InvalidNumber));
+        assert_eq!(kind("$[?@.* == 1]"), Some(QueryErrorKind::InvalidExpression));
+        assert_eq!(kind("$[?length(@)]"), Some(QueryErrorKind::InvalidExpression));
+        assert_eq!(kind("$[?count(1) == 1]"), Some(QueryErrorKind::InvalidExpression));
+        assert_eq!(kind("$[?nope(@)]"), Some(QueryErrorKind::UnknownFunction));
+        assert_eq!(kind("$['a\\q']"), Some(QueryErrorKind::InvalidEscape));
+        assert!(Query::compile("$ [ 'a' , 0 , 1:2:3 ][?( @.x&&!(@.y) )]").is_ok());
+        assert_eq!(DocError::from(Query::compile("$[?nope(@)]").err().unwrap()).get_kind(), DocErrorKind::Query(QueryErrorKind::UnknownFunction));
+    }
+
+    #[test]
+    fn test_iregexp() {
+        let regex = Regex::new("(ab|c)*d?[0-9x-z]{2,3}").unwrap();
+        assert!(regex.is_match("ababc1x"));
+        assert!(regex.is_match("d123"));
+        assert!(!regex.is_match("d1234"));
+        assert!(regex.is_found("--cz9--"));
+        assert!(Regex::new("\\p{Lu}\\p{Ll}+").unwrap().is_match("Word"));
+        assert!(Regex::new("\\p{Nd}+").unwrap().is_match("7\u{663}") && !Regex::new("\\p{Nd}").unwrap().is_match("\u{b2}"));
+        assert!(Regex::new("\\p{Zs}").unwrap().is_match("\u{3000}") && !Regex::new("\\p{Zs}").unwrap().is_match("\u{2028}"));
+        assert!(Regex::new("\\p{Z}\\p{Lt}\\p{Sc}\\P{L}").unwrap().is_match("\u{2029}\u{1c5}$-"));
+        assert!(Regex::new("\\p{Cn}").unwrap().is_match("\u{378}"));
+        assert!(Regex::new("\\p{Cs}").is_none() && Regex::new("\\p{Lx}").is_none());
+        assert!(Regex::new("[^.]+").unwrap().is_match("abc"));
+        assert!(Regex::new("a{2,1}").is_none());
+        assert!(Regex::new("(a").is_none());
+        assert!(Regex::new("\\d").is_none());
+        assert!(Regex::new("^a$").unwrap().is_match("^a$"));
+
+        let ecma = Regex::ecma("^a|b$").unwrap();
+        assert!(ecma.is_found("ax") && ecma.is_found("xb"));
+        assert!(!ecma.is_found("xa") && !ecma.is_found("bx"));
+        assert!(Regex::ecma("^\\d{3}-\\w+\\s?$").unwrap().is_found("123-ab_c "));
+        assert!(!Regex::ecma("^[\\D]+$").unwrap().is_found("ab1"));
+        assert!(Regex::ecma("\\$\\/$").unwrap().is_found("a$/"));
+        assert!(Regex::ecma("^*").is_none());
+    }
+
+    #[test]
+    fn test_iregexp_limits() {
+        let long = "a".repeat(10_000);
+        assert!(Regex::new("a.*").unwrap().is_match(&long));
+        assert!(Regex::new("a{1000}").unwrap().is_found(&long));
+        assert!(!Regex::new("(a*)*b").unwrap().is_match(&long));
+        assert!(Regex::new("(a|aa)*c").unwrap().is_found(&format!("{}c", long)));
+        assert!(Regex::new("a{1001}").is_none());
+        assert!(Regex::new("((a{1000}){1000}){1000}").is_none());
+        assert!(Regex::new("(((){1000}){1000}){100}").is_none());
+        assert!(Regex::new(&format!("{}a{}", "(".repeat(200), ")".repeat(200))).is_none());
+        assert!(Regex::ecma("a{1000}$").unwrap().is_found(&long));
+        assert!(!Regex::ecma("^a*b").unwrap().is_found(&long));
+    }
+
+    #[test]
+    fn test_json_patch_apply() {
+        let mut doc = json::parse_str("{\"a\": [1, 2], \"b\": {\"c\": \"x\"}}").unwrap();
+        let patch = ItemPatch::from_item(&json::parse_str(concat!("[",
+            "{\"op\": \"add\", \"path\": \"/a/1\", \"value\": 5},",
+            "{\"op\": \"add\", \"path\": \"/a/-\", \"value\": {}},",
+            "{\"op\": \"test\", \"path\": \"/a/0\", \"value\": 1.0},",
+            "{\"op\": \"copy\", \"from\": \"/b/c\", \"path\": \"/a/3/d\"},",
+            "{\"op\": \"move\", \"from\": \"/b\", \"path\": \"/e\"},",
+            "{\"op\": \"replace\", \"path\": \"/e/c\", \"value\": null},",
+            "{\"op\": \"remove\", \"path\": \"/a/2\"}",
+            "]")).unwrap()).unwrap();
+        assert_eq!(patch.count(), 7);
+        doc.apply_patch(&patch).unwrap();
+        assert_eq!(doc.to_string(), "{\"a\":[1,5,{\"d\":x}],\"e\":{\"c\":null}}");
+    }
+
+    #[test]
+    fn test_json_patch_rolls_back() {
+        let original = json::parse_str("{\"a\": [1, 2]}").unwrap();
+        let mut doc = original.clone();
+        let mut patch = ItemPatch::new();
+        patch.push(PatchOperation::Remove { path: ItemPointer::parse("/a/0").unwrap() });
+        patch.push(PatchOperation::Test { path: ItemPointer::parse("/a/0").unwrap(), value: ItemNumber::new(Some(1.0)) });
+        assert_eq!(doc.apply_patch(&patch), Err(PatchError::TestFailed(1)));
+        assert!(doc == original);
+
+        let mut patch = ItemPatch::new();
+        patch.push(PatchOperation::Add { path: ItemPointer::parse("/x/y").unwrap(), value: ItemList::new() });
+        assert_eq!(patch.apply(&mut doc), Err(PatchError::Item(0, ItemError::PointerNotFound(0))));
+        let mut patch = ItemPatch::new();
+        patch.push(PatchOperation::Move { from: ItemPointer::parse("/a").unwrap(), path: ItemPointer::parse("/a/0").unwrap() });
+        assert_eq!(patch.apply(&mut doc), Err(PatchError::InvalidOperation(0)));
+
+        let bad = json::parse_str("[{\"op\": \"add\", \"path\": \"/a\"}]").unwrap();
+        assert_eq!(ItemPatch::from_item(&bad), Err(PatchError::InvalidOperation(0)));
+    }
+
+    #[test]
+    fn test_json_patch_create_and_export() {
+        let old = json::parse_str("{\"a\": [1, 2, 3], \"b\": 1, \"c\": {\"d\": true}}").unwrap();
+        let new = json::parse_str("{\"a\": [1, 4], \"c\": {\"d\": true, \"e\": \"/\"}, \"b\": \"1\"}").unwrap();
+        let patch = ItemPatch::create(&old, &new);
+
+        let buffer = SharedBuffer::default();
+        let mut output = JSONFormatCompact::new(Box::new(buffer.clone()));
+        patch.export(&mut output).unwrap();
+        assert_eq!(buffer.contents(), concat!("[",
+            "{\"op\":\"replace\",\"path\":\"/a/1\",\"value\":4},",
+            "{\"op\":\"remove\",\"path\":\"/a/2\"},",
+            "{\"op\":\"add\",\"path\":\"/c/e\",\"value\":\"/\"},",
+            "{\"op\":\"replace\",\"path\":\"/b\",\"value\":\"1\"}]"));
+
+        let parsed = ItemPatch::from_item(&json::parse_str(&buffer.contents()).unwrap()).unwrap();
+        assert_eq!(parsed, patch);
+        let mut doc = old.clone();
+        doc.apply_patch(&parsed).unwrap();
+        assert!(doc == new);
+    }
+
+    #[test]
+    fn test_merge_patch_rfc_examples() {
+        let cases = [
+            ("{\"a\":\"b\"}", "{\"a\":\"c\"}", "{\"a\":\"c\"}"),
+            ("{\"a\":\"b\"}", "{\"b\":\"c\"}", "{\"a\":\"b\",\"b\":\"c\"}"),
+            ("{\"a\":\"b\",\"b\":\"c\"}", "{\"a\":null}", "{\"b\":\"c\"}"),
+            ("{\"a\":[\"b\"]}", "{\"a\":\"c\"}", "{\"a\":\"c\"}"),
+            ("{\"a\":{\"b\":\"c\"}}", "{\"a\":{\"b\":\"d\",\"c\":null}}", "{\"a\":{\"b\":\"d\"}}"),
+            ("[1,2]", "{\"a\":\"b\",\"c\":null}", "{\"a\":\"b\"}"),
+            ("{\"e\":null}", "{\"a\":1}", "{\"e\":null,\"a\":1}"),
+            ("{}", "{\"a\":{\"bb\":{\"ccc\":null}}}", "{\"a\":{\"bb\":{}}}"),
+            ("{\"a\":\"foo\"}", "null", "null"),
+        ];
+        for (target, patch, expected) in cases {
+            let mut doc = json::parse_str(target).unwrap();
+            doc.merge_patch(&json::parse_str(patch).unwrap());
+            assert!(doc == json::parse_str(expected).unwrap(), "{} + {}", target, patch);
+        }
+    }
+
+    #[test]
+    fn test_create_merge_patch() {
+        let old = json::parse_str("{\"title\": \"Hello\", \"author\": {\"given\": \"J\", \"family\": \"D\"}, \"tags\": [\"a\"]}").unwrap();
+        let new = json::parse_str("{\"title\": \"Hi\", \"author\": {\"given\": \"J\"}, \"tags\": [\"a\", \"b\"], \"n\": 1}").unwrap();
+        let patch = create_merge_patch(&old, &new);
+        assert_eq!(patch.to_string(), "{\"title\":Hi,\"author\":{\"family\":null},\"tags\":[a,b],\"n\":1}");
+
+        let mut doc = old.clone();
+        doc.merge_patch(&patch);
+        assert!(doc == new);
+        assert_eq!(create_merge_patch(&old, &old).to_string(), "{}");
+    }
+
+    #[test]
+    fn test_diff_hashes_and_types() {
+        let old = json::parse_str("{\"name\": \"web\", \"port\": 80, \"tls\": null, \"tags\": {\"env\": \"dev\"}}").unwrap();
+        let new = json::parse_str("{\"name\": \"web\", \"port\": 8080, \"tls\": true, \"tags\": {}, \"owner\": \"ops\"}").unwrap();
+        let changes = old.diff(&new);
+        let summary: Vec<String> = changes.get_changes().iter()
+            .map(|c| format!("{} {}", c.get_name(), c.get_path()))
+            .collect();
+        assert_eq!(summary, vec![
+            "changed /port", "type-changed /tls", "removed /tags/env", "added /owner",
+        ]);
+        assert!(old.diff(&old.clone()).is_empty());
+        assert_eq!(diff(&ItemNumber::new(Some(1.0)), &json::parse_str("\"1\"").unwrap()).to_string(),
+            "@@ / (number -> string) @@\n-1.0\n+\"1\"\n");
+    }
+
+    #[test]
+    fn test_diff_ordered_lists() {
+        let old = json::parse_str("[1, 2, {\"a\": 1}, 4]").unwrap();
+        let new = json::parse_str("[0, 1, 2, {\"a\": 2}]").unwrap();
+        let changes = diff(&old, &new);
+        assert_eq!(changes.get_changes(), &[
+            ItemChange::Added { path: ItemPointer::parse("/0").unwrap(), value: ItemNumber::new(Some(0.0)) },
+            ItemChange::Changed {
+                path: ItemPointer::parse("/3/a").unwrap(),
+                old: ItemNumber::new(Some(1.0)),
+                new: ItemNumber::new(Some(2.0)),
+            },
+            ItemChange::Removed { path: ItemPointer::parse("/3").unwrap(), value: ItemNumber::new(Some(4.0)) },
+        ]);
+        assert_eq!(changes.to_string(), "@@ /0 @@\n+0\n@@ /3/a @@\n-1\n+2\n@@ /3 @@\n-4\n");
+
+        let long: Vec<i64> = (0..50_000).collect();
+        let mut edited = long.clone();
+        edited.remove(10);
+        edited[25_000] = -1;
+        let summary: Vec<String> = diff(&ItemType::from(long), &ItemType::from(edited)).get_changes().iter()
+            .map(|c| format!("{} {}", c.get_name(), c.get_path()))
+            .collect();
+        assert_eq!(summary, vec!["removed /10", "changed /25000"]);
+    }
+
+    #[test]
+    fn test_diff_keyed_lists() {
+        let old = json::parse_str("[{\"id\": 1, \"v\": \"a\"}, {\"id\": 2, \"v\": \"b\"}, \"x\"]").unwrap();
+        let new = json::parse_str("[\"x\", {\"id\": 2, \"v\": \"c\"}, {\"id\": 3, \"v\": \"d\"}]").unwrap();
+        let changes = old.diff_with(&new, &DiffOptions::keyed_by("id"));
+        let summary: Vec<String> = changes.get_changes().iter()
+            .map(|c| format!("{} {}", c.get_name(), c.get_path()))
+            .collect();
+        assert_eq!(summary, vec!["removed /0", "changed /1/v", "added /2"]);
+
+        let reordered = json::parse_str("[\"x\", {\"v\": \"b\", \"id\": 2}, {\"id\": 1, \"v\": \"a\"}]").unwrap();
+        assert!(old.diff_with(&reordered, &DiffOptions::keyed_by("id")).is_empty());
+        assert!(!old.diff(&reordered).is_empty());
+    }
+
+    #[test]
+    fn test_deep_merge_hashes() {
+        let mut config = json::parse_str("{\"db\": {\"host\": \"localhost\", \"port\": 5432}, \"debug\": false}").unwrap();
+        let overrides = json::parse_str("{\"db\": {\"host\": \"db.internal\", \"pool\": 10}, \"debug\": true}").unwrap();
+        config.deep_merge(&overrides, &MergeOptions::new()).unwrap();
+        assert_eq!(config.to_string(), "{\"db\":{\"host\":db.internal,\"port\":5432,\"pool\":10},\"debug\":true}");
+    }
+
+    #[test]
+    fn test_deep_merge_lists() {
+        let left = json::parse_str("[{\"id\": 1, \"v\": 1}, {\"id\": 2, \"v\": 2}]").unwrap();
+        let right = json::parse_str("[{\"id\": 2, \"w\": 3}, {\"id\": 3}]").unwrap();
+        let merged = |lists: ListMerge| {
+            let mut doc = left.clone();
+            doc.deep_merge(&right, &MergeOptions::new().with_lists(lists)).unwrap();
+            doc.to_string()
+        };
+        assert_eq!(merged(ListMerge::Replace), "[{\"id\":2,\"w\":3},{\"id\":3}]");
+        assert_eq!(merged(ListMerge::Append), "[{\"id\":1,\"v\":1},{\"id\":2,\"v\":2},{\"id\":2,\"w\":3},{\"id\":3}]");
+        assert_eq!(merged(ListMerge::ByIndex), "[{\"id\":2,\"v\":1,\"w\":3},{\"id\":3,\"v\":2}]");
+        assert_eq!(merged(ListMerge::ByKey("id".to_string())), "[{\"id\":1,\"v\":1},{\"id\":2,\"v\":2,\"w\":3},{\"id\":3}]");
+    }
+
+    #[test]
+    fn test_deep_merge_type_conflicts() {
+        let left = json::parse_str("{\"a\": {\"b\": [1]}, \"c\": 1}").unwrap();
+        let right = json::parse_str("{\"a\": {\"b\": \"x\"}, \"c\": 2}").unwrap();
+
+        let mut doc = left.clone();
+        let options = MergeOptions::new().with_conflicts(TypeConflict::Error);
+        assert_eq!(doc.deep_merge(&right, &options), Err(MergeError::TypeConflict(ItemPointer::parse("/a/b").unwrap())));
+        assert!(doc == left);
+
+        doc.deep_merge(&right, &MergeOptions::new().with_conflicts(TypeConflict::PreferLeft)).unwrap();
+        assert_eq!(doc.to_string(), "{\"a\":{\"b\":[1]},\"c\":2}");
+        doc.deep_merge(&right, &MergeOptions::new()).unwrap();
+        assert_eq!(doc.to_string(), "{\"a\":{\"b\":x},\"c\":2}");
+        assert!(ItemNumber::new(Some(1.0)).deep_merge(&ItemHash::new(), &options).is_err());
+    }
+
+    #[test]
+    fn test_container_iterators() {
+        let mut doc = json::parse_str("{\"a\": 1, \"b\": [2, 3], \"c\": null}").unwrap();
+        let keys: Vec<ContainerKey> = doc.iter().map(|(key, _)| key).collect();
+        assert_eq!(keys, vec![ContainerKey::Key("a"), ContainerKey::Key("b"), ContainerKey::Key("c")]);
+        assert_eq!(ItemNumber::new(Some(1.0)).iter().count(), 0);
+
+        if let Ok(Some(ItemType::TList(list))) = doc.get_item_mut("b") {
+            for (key, item) in list.iter_mut() {
+                if let ContainerKey::Idx(i) = key {
+                    *item = ItemNumber::new(Some(i as f64 * 10.0));
+                }
+            }
+            assert_eq!(list.iter().next_back().map(|(_, item)| item.to_string()), Some("10.0".to_string()));
+        }
+        assert_eq!(doc.to_string(), "{\"a\":1,\"b\":[0.0,10.0],\"c\":null}");
+
+        if let ItemType::THash(hash) = doc {
+            assert_eq!(hash.iter().len(), 3);
+            let owned: Vec<String> = hash.into_iter().map(|(key, item)| format!("{}={}", key, item)).collect();
+            assert_eq!(owned, vec!["a=1", "b=[0.0,10.0]", "c=null"]);
+        }
+        if let ItemType::TList(list) = json::parse_str("[1, null]").unwrap() {
+            let owned: Vec<String> = list.into_iter().map(|(index, item)| format!("{}={}", index, item)).collect();
+            assert_eq!(owned, vec!["0=1", "1=null"]);
+        }
+    }
+
+    #[test]
+    fn test_walk_orders() {
+        let doc = sample_document();
+        let pre: Vec<String> = doc.walk().map(|e| format!("{}:{}", e.depth, e.path)).collect();
+        assert_eq!(pre, vec!["0:", "1:/a", "2:/a/0", "2:/a/1", "3:/a/1/b", "2:/a/2", "2:/a/3"]);
+
+        let post: Vec<String> = doc.walk_with(WalkOrder::PostOrder).map(|e| e.path.to_string()).collect();
+        assert_eq!(post, vec!["/a/0", "/a/1/b", "/a/1", "/a/2", "/a/3", "/a", ""]);
+    }
+
+    #[test]
+    fn test_walk_prune() {
+        let doc = json::parse_str("{\"skip\": {\"x\": 1}, \"keep\": {\"y\": [2]}}").unwrap();
+        let mut walk = doc.walk();
+        let mut visited = Vec::new();
+        while let Some(entry) = walk.next() {
+            if entry.path.last() == Some("skip") {
+                walk.prune();
+            }
+            visited.push(entry.path.to_string());
+        }
+        assert_eq!(visited, vec!["", "/skip", "/keep", "/keep/y", "/keep/y/0"]);
+    }
+
+    #[test]
+    fn test_item_visitor() {
+        #[derive(Default)]
+        struct Census { strings: usize, numbers: f64, depth: usize, deepest: usize, keys: Vec<String> }
+
+        impl ItemVisitor for Census {
+            fn visit_member(&mut self, key: ContainerKey<'_>, item: &ItemType) {
+                if let ContainerKey::Key(name) = key {
+                    self.keys.push(name.to_string());
+                }
+                self.visit_item(item);
+            }
+            fn visit_number(&mut self, number: &ItemNumber) { self.numbers += number.get_value(); }
+            fn visit_string(&mut self, _string: &ItemString) { self.strings += 1; }
+            fn enter_list(&mut self, _list: &ItemList) {
+                self.depth += 1;
+                self.deepest = self.deepest.max(self.depth);
+            }
+            fn leave_list(&mut self, _list: &ItemList) { self.depth -= 1; }
+        }
+
+        let doc = json::parse_str("{\"a\": [1, [2, \"x\"]], \"b\": {\"c\": \"y\", \"d\": 3}}").unwrap();
+        let mut census = Census::default();
+        doc.accept(&mut census);
+        assert_eq!((census.strings, census.numbers, census.deepest), (2, 6.0, 2));
+        assert_eq!(census.keys, vec!["a", "b", "c", "d"]);
+    }
+
+    #[test]
+    fn test_item_visitor_mut() {
+        struct Tidy;
+
+        impl ItemVisitorMut for Tidy {
+            fn visit_number(&mut self, number: &mut ItemNumber) {
+                number.set_value(number.get_value().round());
+            }
+            fn leave_list(&mut self, list: &mut ItemList) {
+                list.retain(|item| !item.is_null());
+            }
+            fn leave_hash(&mut self, hash: &mut ItemHash) {
+                hash.retain(|_, item| !item.is_null());
+                let keys: Vec<String> = hash.iter().filter_map(|(key, _)| match key {
+                    ContainerKey::Key(name) => Some(name.to_string()),
+                    ContainerKey::Idx(_) => None,
+                }).collect();
+                for key in keys {
+                    hash.rename_key(&key, &key.to_uppercase()).unwrap();
+                }
+            }
+        }
+
+        let mut doc = json::parse_str("{\"a\": 1.4, \"b\": null, \"c\": [2.6, null, {\"d\": null, \"e\": 0.5}]}").unwrap();
+        doc.accept_mut(&mut Tidy);
+        assert_eq!(doc.to_string(), "{\"A\":1.0,\"C\":[3.0,{\"E\":1.0}]}");
+
+        if let ItemType::THash(hash) = &mut doc {
+            assert!(hash.rename_key("A", "C").unwrap_err() == ItemError::ItemAdditionFailed);
+            assert!(hash.rename_key("Z", "Y").unwrap_err() == ItemError::ItemNotFound);
+        }
+    }
+
+    #[test]
+    fn test_item_macro_literals() {
+        let doc = crate::item!({
+            "name": "x",
+            "ports": [80, 443],
+            "tls": null,
+            "nested": { "ratio": 0.5, "flags": [true, false, null], "empty": {} },
+            "list": [],
+            "neg": -1,
+        });
+        assert_eq!(doc.to_string(), "{\"name\":x,\"ports\":[80,443],\"tls\":null,\"nested\":{\"ratio\":0.5,\"flags\":[true,false,null],\"empty\":{}},\"list\":[],\"neg\":-1}");
+        assert!(doc == json::parse_str("{\"name\": \"x\", \"ports\": [80, 443], \"tls\": null, \"nested\": {\"ratio\": 0.5, \"flags\": [true, false, null], \"empty\": {}}, \"list\": [], \"neg\": -1}").unwrap());
+        assert!(crate::item!(null).is_null());
+        assert_eq!(crate::item!("a").to_string(), "a");
+    }
+
+    #[test]
+    fn test_item_macro_interpolation() {
+        let port: u16 = 8080;
+        let host = String::from("example.org");
+        let key = "dynamic";
+        let child = crate::item!([1, 2]);
+        let doc = crate::item!({
+            "host": host.as_str(),
+            "port": port + 1,
+            key: child.clone(),
+            (format!("k{}", 2)): if port > 80 { "high" } else { "low" },
+            "items": [port as f64 / 2.0, child, crate::item!({ "deep": true })],
+        });
+        assert_eq!(doc.to_string(), "{\"host\":example.org,\"port\":8081,\"dynamic\":[1,2],\"k2\":high,\"items\":[4040.0,[1,2],{\"deep\":true}]}");
+    }
+
+    #[test]
+    fn test_collections_into_item() {
+        assert_eq!(ItemType::from(vec![1, 2, 3]).to_string(), "[1,2,3]");
+        assert_eq!(ItemType::from(&["a", "b"][..]).to_string(), "[a,b]");
+        assert_eq!(ItemType::from([true, false]).to_string(), "[true,false]");
+        assert_eq!(ItemType::from(vec![Some(1.5), None]).to_string(), "[1.5,null]");
+        assert_eq!(ItemType::from((1, "two", None::<bool>, vec![3u8])).to_string(), "[1,two,null,[3]]");
+
+        let mut map = HashMap::new();
+        map.insert("b".to_string(), vec![2]);
+        map.insert("a".to_string(), vec![1]);
+        assert_eq!(ItemType::from(map).to_string(), "{\"a\":[1],\"b\":[2]}");
+        let tree: BTreeMap<String, ItemType> = [("z".to_string(), crate::item!(null))].into_iter().collect();
+        assert_eq!(ItemType::from(tree).to_string(), "{\"z\":null}");
+
+        let ports = vec![80, 443];
+        assert_eq!(crate::item!({ "ports": ports, "tls": None::<bool> }).to_string(), "{\"ports\":[80,443],\"tls\":null}");
+    }
+
+    #[test]
+    fn test_item_try_into_rust() {
+        let doc = json::parse_str("{\"n\": 300, \"f\": 1.5, \"s\": \"hi\", \"b\": true, \"l\": [1, null], \"h\": {\"x\": 1}}").unwrap();
+        let get = |key: &str| doc.get_item(key).unwrap().unwrap();
+        assert_eq!(u16::try_from(get("n")), Ok(300));
+        assert_eq!(i64::try_from(get("n")), Ok(300));
+        assert_eq!(u8::try_from(get("n")), Err(ConversionError::OutOfRange { target: "u8" }));
+        assert_eq!(i32::try_from(get("f")), Err(ConversionError::OutOfRange { target: "i32" }));
+        assert_eq!(f64::try_from(get("f")), Ok(1.5));
+        assert_eq!(<&str>::try_from(get("s")), Ok("hi"));
+        assert_eq!(String::try_from(get("s")), Ok("hi".to_string()));
+        assert_eq!(bool::try_from(get("b")), Ok(true));
+        assert_eq!(Vec::<Option<u8>>::try_from(get("l")), Ok(vec![Some(1), None]));
+        assert_eq!(BTreeMap::<String, i32>::try_from(get("h")).unwrap().get("x"), Some(&1));
+
+        let error = bool::try_from(get("s")).unwrap_err();
+        assert_eq!(error, ConversionError::WrongKind { expected: ItemKind::Boolean, found: ItemKind::String });
+        assert_eq!(error.to_string(), "Expected a boolean item but found a string!");
+        assert!(Vec::<u8>::try_from(get("l")).is_err());
+
+        let pair = crate::item!([8080, "http"]);
+        assert_eq!(<(u16, String)>::try_from(&pair), Ok((8080, "http".to_string())));
+        assert_eq!(<[Option<u8>; 2]>::try_from(get("l")), Ok([Some(1), None]));
+        assert_eq!(<(u16, String, bool)>::try_from(&pair), Err(ConversionError::WrongLength { expected: 3, found: 2 }));
+        assert_eq!(<[i32; 1]>::try_from(get("l")).unwrap_err().to_string(), "Expected a list of 1 members but found 2!");
+        assert_eq!(<(bool,)>::try_from(get("h")), Err(ConversionError::WrongKind { expected: ItemKind::List, found: ItemKind::Hash }));
+    }
+
+    #[test]
+    fn test_doc_error_context() {
+        let mut doc = crate::item!({ "a": "text", "l": [] });
+        let error = doc.get_item_mut("a").unwrap().unwrap().add_list(None).unwrap_err();
+        assert!(error == ItemError::NotAnItemContainer);
+        assert_eq!(error.get_item_error(), Some(ItemError::NotAnItemContainer));
+        assert_eq!((error.get_expected(), error.get_found()), (Some(ItemKind::List), Some(ItemKind::String)));
+        assert_eq!(error.to_string(), "Not an item container! Expected a list item but found a string!");
+
+        let error = doc.get_item_mut("l").unwrap().unwrap().add_hash(Some("k")).unwrap_err();
+        assert!(matches!(error.get_kind(), DocErrorKind::Item(ItemError::NotAnItemHash)));
+        assert_eq!((error.get_expected(), error.get_found()), (Some(ItemKind::Hash), Some(ItemKind::List)));
+        assert!(doc.add_null(Some("n")).is_ok());
+
+        let pointer = ItemPointer::parse("/l/0/x").unwrap();
+        let error = doc.pointer(&pointer).unwrap_err();
+        assert_eq!(error.get_path(), Some(&ItemPointer::parse("/l/0").unwrap()));
+        assert_eq!(error.to_string(), "At '/l/0': Pointer segment 1 not found!");
+        let error = doc.set_at_pointer(&ItemPointer::parse("/a/b/c").unwrap(), ItemList::new(), true).unwrap_err();
+        assert_eq!(error.to_string(), "At '/a': Pointer segment 1 is not within a container!");
+        let error = doc.get_item_mut("l").unwrap().unwrap().replace_item(3.into(), ItemList::new()).unwrap_err();
+        assert_eq!(error.to_string(), "At '/3': Item not found!");
+    }
+
+    // Accepts `limit` bytes and then fails every write.
+    struct FailingWriter {
+        limit: usize,
+    }
+
+    impl Write for FailingWriter {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            if buf.len() > self.limit {
+                return Err(std::io::Error::new(std::io::ErrorKind::BrokenPipe, "closed"));
+            }
+            self.limit -= buf.len();
+            Ok(buf.len())
+        }
+        fn flush(&mut self) -> std::io::Result<()> { Ok(()) }
+    }
+
+    #[test]
+    fn test_doc_error_from_import_and_export() {
+        let error = json::parse_str("[1,]").unwrap_err();
+        assert_eq!(error.get_kind(), DocErrorKind::Parse(ParseErrorKind::UnexpectedCharacter(']')));
+        assert_eq!(error.get_position().map(|p| (p.line, p.column)), Some((1, 4)));
+        assert_eq!(error.to_string(), "At '/1': Unexpected character ']'! (line 1, column 4, offset 3)");
+        let source = std::error::Error::source(&error).unwrap();
+        assert!(source.downcast_ref::<ParseError>().is_some());
+
+        let error = json::parse_str("{\"a\": [1, {\"b\": tru}]}").unwrap_err();
+        assert_eq!(error.get_path().map(|path| path.to_string()), Some("/a/1/b".to_string()));
+        let error = yaml::parse_str("a:\n  - 1\n  - b: &x 2\n").unwrap_err();
+        assert_eq!(error.get_path().map(|path| path.to_string()), Some("/a/1/b".to_string()));
+
+        let mut output = JSONFormatCompact::new(Box::new(FailingWriter { limit: 13 }));
+        let error = sample_document().export(&mut output).unwrap_err();
+        assert_eq!(error.get_kind(), DocErrorKind::Io(std::io::ErrorKind::BrokenPipe));
+        assert_eq!(error.get_path().map(|path| path.to_string()), Some("/a/1/b".to_string()));
+        assert_eq!(std::error::Error::source(&error).unwrap().to_string(), "closed");
+    }
+
+    fn violations(schema: &ItemType, instance: &ItemType) -> Vec<(String, String, &'static str)> {
+        let schema = Schema::compile(schema).unwrap();
+        instance.validate(&schema).into_iter()
+            .map(|v| (v.instance_path.to_string(), v.schema_path.to_string(), v.keyword))
+            .collect()
+    }
+
+    #[test]
+    fn test_schema_validation_keywords() {
+        let schema = crate::item!({
+            "type": "object",
+            "required": ["name", "port", "version"],
+            "properties": {
+                "name": { "type": "string", "pattern": "^[a-z]+$", "maxLength": 8 },
+                "port": { "type": "integer", "minimum": 1, "exclusiveMaximum": 65536 },
+                "mode": { "enum": ["fast", "safe"] },
+                "version": { "const": 2 },
+                "tags": { "type": "array", "items": { "type": "string" }, "minItems": 1 }
+            },
+            "additionalProperties": false
+        });
+        let good = crate::item!({ "name": "db", "port": 5432, "mode": "safe", "version": 2.0, "tags": ["a"] });
+        assert!(violations(&schema, &good).is_empty());
+
+        let bad = crate::item!({ "name": "Database01", "port": 0, "mode": "slow", "tags": [1], "extra": true });
+        assert_eq!(violations(&schema, &bad), vec![
+            ("".to_string(), "/required".to_string(), "required"),
+            ("/name".to_string(), "/properties/name/maxLength".to_string(), "maxLength"),
+            ("/name".to_string(), "/properties/name/pattern".to_string(), "pattern"),
+            ("/port".to_string(), "/properties/port/minimum".to_string(), "minimum"),
+            ("/mode".to_string(), "/properties/mode/enum".to_string(), "enum"),
+            ("/tags/0".to_string(), "/properties/tags/items/type".to_string(), "type"),
+            ("/extra".to_string(), "/additionalProperties".to_string(), "false"),
+        ]);
+        let compiled = Schema::compile(&schema).unwrap();
+        let messages: Vec<String> = compiled.validate(&bad).iter().map(|v| v.to_string()).collect();
+        assert_eq!(messages[0], "At '/': Missing required property 'version'! (schema '#/required')");
+        assert_eq!(messages[5], "At '/tags/0': Expected string but found integer! (schema '#/properties/tags/items/type')");
+    }
+
+    #[test]
+    fn test_schema_combinators_and_refs() {
+        let schema = crate::item!({
+            "$defs": {
+                "node": {
+                    "type": "object",
+                    "properties": {
+                        "value": { "oneOf": [{ "type": "integer" }, { "type": "number", "minimum": 10 }] },
+                        "children": { "type": "array", "items": { "$ref": "#/$defs/node" } }
+                    }
+                }
+            },
+            "allOf": [{ "$ref": "#/$defs/node" }, { "not": { "required": ["skip"] } }],
+            "anyOf": [{ "required": ["value"] }, { "required": ["children"] }]
+        });
+        let tree = crate::item!({ "value": 1, "children": [{ "value": 2.5, "children": [{ "value": 3 }] }] });
+        assert_eq!(violations(&schema, &tree), vec![
+            ("/children/0/value".to_string(), "/$defs/node/properties/value/oneOf".to_string(), "oneOf"),
+        ]);
+        assert_eq!(violations(&schema, &crate::item!({ "value": 15, "skip": true })), vec![
+            ("/value".to_string(), "/$defs/node/properties/value/oneOf".to_string(), "oneOf"),
+            ("".to_string(), "/allOf/1/not".to_string(), "not"),
+        ]);
+        assert_eq!(violations(&schema, &crate::item!({})), vec![("".to_string(), "/anyOf".to_string(), "anyOf")]);
+
+        let tuple = crate::item!({ "prefixItems": [{ "type": "string" }, { "type": "boolean" }], "items": false });
+        assert!(violations(&tuple, &crate::item!(["a", true])).is_empty());
+        assert_eq!(violations(&tuple, &crate::item!(["a", 1, null])), vec![
+            ("/1".to_string(), "/prefixItems/1/type".to_string(), "type"),
+            ("/2".to_string(), "/items".to_string(), "false"),
+        ]);
+    }
+
+    #[test]
+    fn test_schema_patterns_bounds_and_depth() {
+        let pattern = crate::item!({ "pattern": "^a|b$" });
+        assert!(violations(&pattern, &crate::item!("ax")).is_empty());
+        assert!(violations(&pattern, &crate::item!("xb")).is_empty());
+        assert_eq!(violations(&pattern, &crate::item!("xa")).len(), 1);
+        assert!(violations(&crate::item!({ "pattern": "^\\d+-\\w+$" }), &crate::item!("42-id")).is_empty());
+
+        let bounds = crate::item!({ "maximum": 9007199254740992_i64, "exclusiveMinimum": 0.1 });
+        assert_eq!(violations(&bounds, &json::parse_str("9007199254740993").unwrap()), vec![
+            ("".to_string(), "/maximum".to_string(), "maximum"),
+        ]);
+        assert!(violations(&bounds, &json::parse_str("0.10000000000000000001").unwrap()).is_empty());
+        assert_eq!(violations(&bounds, &json::parse_str("0.1").unwrap()).len(), 1);
+
+        let tree = crate::item!({ "type": "array", "items": { "$ref": "#" } });
+        let deep = json::parse_str(&format!("{}{}", "[".repeat(200), "]".repeat(200))).unwrap();
+        assert!(violations(&tree, &deep).is_empty());
+        let cycle = crate::item!({ "$defs": { "a": { "allOf": [{ "$ref": "#/$defs/a" }] } }, "$ref": "#/$defs/a" });
+        assert_eq!(violations(&cycle, &crate::item!(1)).len(), 1);
+        let branching = crate::item!({ "anyOf": [{ "$ref": "#" }, { "$ref": "#" }] });
+        assert_eq!(violations(&branching, &crate::item!(1)), vec![("".to_string(), "/anyOf".to_string(), "anyOf")]);
+    }
+
+    #[test]
+    fn test_schema_compile_errors() {
+        let error = |schema: ItemType| {
+            let error = Schema::compile(&schema).unwrap_err();
+            (error.get_kind(), error.get_path().map(|path| path.to_string()))
+        };
+        assert_eq!(error(crate::item!({ "type": "text" })), (DocErrorKind::Schema(SchemaError::InvalidKeyword), Some("/type".to_string())));
+        assert_eq!(error(crate::item!({ "items": 3 })), (DocErrorKind::Schema(SchemaError::InvalidSchema), Some("/items".to_string())));
+        assert_eq!(Schema::compile(&crate::item!({ "pattern": "(a" })).unwrap_err().to_string(), "At '/pattern': Unsupported pattern!");
+        assert_eq!(Schema::compile(&crate::item!({ "$ref": "#/$defs/missing" })).unwrap_err().to_string(), "At '/$ref': Unresolvable reference!");
+        assert_eq!(error(crate::item!({ "$ref": "other.json" })).0, DocErrorKind::Schema(SchemaError::InvalidReference));
+        assert!(Schema::compile(&crate::item!(true)).unwrap().is_valid(&crate::item!([1, "x"])));
+    }
+
+    #[test]
+    fn test_schema_inference_merges_samples() {
+        let samples = vec![
+            crate::item!({ "id": 1, "name": "a", "score": 2, "tags": ["x"], "owner": { "id": 7 } }),
+            crate::item!({ "id": 2, "name": null, "score": 2.5, "tags": [], "owner": { "id": 8, "team": "core" } }),
+            crate::item!({ "id": 3, "name": "c", "tags": ["y", "z"], "owner": { "id": 9 } }),
+        ];
+        let schema = infer(&samples);
+        assert_eq!(schema, crate::item!({
+            "$schema": "https://json-schema.org/draft/2020-12/schema",
+            "type": "object",
+            "properties": {
+                "id": { "type": "integer" },
+                "name": { "type": ["string", "null"] },
+                "score": { "type": "number" },
+                "tags": { "type": "array", "items": { "type": "string" } },
+                "owner": {
+                    "type": "object",
+                    "properties": { "id": { "type": "integer" }, "team": { "type": "string" } },
+                    "required": ["id"]
+                }
+            },
+            "required": ["id", "name", "tags", "owner"]
+        }));
+        let compiled = Schema::compile(&schema).unwrap();
+        assert!(samples.iter().all(|sample| compiled.is_valid(sample)));
+        assert!(!compiled.is_valid(&crate::item!({ "id": "1", "name": "a", "tags": [], "owner": { "id": 1 } })));
+        assert_eq!(infer([&crate::item!([1, "a"])]).get_item("items").unwrap().unwrap(), &crate::item!({ "type": ["string", "integer"] }));
+    }
+
+    #[test]
+    fn test_schema_inference_enums() {
+        let samples: Vec<ItemType> = ["open", "closed", "open", "merged"].into_iter()
+            .map(|state| crate::item!({ "state": state, "title": state.to_uppercase() }))
+            .collect();
+        let state = |schema: &ItemType| schema.pointer(&ItemPointer::parse("/properties/state").unwrap()).unwrap().clone();
+        assert_eq!(state(&infer(&samples)), crate::item!({ "type": "string", "enum": ["open", "closed", "merged"] }));
+        assert_eq!(state(&infer_with(&samples, &InferOptions::new().with_enum_threshold(2))), crate::item!({ "type": "string" }));
+        assert_eq!(state(&infer_with(&samples, &InferOptions::new().with_enum_threshold(0))), crate::item!({ "type": "string" }));
+        assert_eq!(state(&infer(&samples[..2])), crate::item!({ "type": "string" }));
+
+        let nullable = [crate::item!("low"), crate::item!(null), crate::item!("low")];
+        assert_eq!(infer(&nullable).get_item("enum").unwrap().unwrap(), &crate::item!(["low", null]));
+    }
+
+    #[test]
+    fn test_arena_conversion() {
+        let text = r#"{ "users": [{ "id": 1, "name": "ann", "admin": true }, { "id": 2, "name": "bob", "admin": false, "id": 3 }], "next": null, "ratio": 0.25 }"#;
+        let item = json::parse_str(text).unwrap();
+        let parsed = ItemArena::parse_json_str(text).unwrap();
+        assert_eq!(parsed.to_item(), item);
+        assert_eq!(parsed.key_count(), 6);
+        assert_eq!(parsed.pointer(&ItemPointer::parse("/users/1/id").unwrap()).map(|id| parsed.get_number(id).unwrap().as_i128()).unwrap(), Some(3));
+        assert_eq!(parsed.get_keys(parsed.pointer(&ItemPointer::parse("/users/1").unwrap()).unwrap()).unwrap(), vec![
+            ContainerKey::Key("id"), ContainerKey::Key("name"), ContainerKey::Key("admin"),
+        ]);
+
+        let arena = ItemArena::from(&item);
+        assert_eq!(ItemType::from(&arena), item);
+        assert_eq!(arena.node_to_item(arena.pointer(&ItemPointer::parse("/users/0").unwrap()).unwrap()), *item.get_item("users").unwrap().unwrap().get_item(0).unwrap().unwrap());
+        assert_eq!(ItemArena::parse_json_str("[1, 2").unwrap_err().to_string(), json::parse_str("[1, 2").unwrap_err().to_string());
+    }
+
+    #[test]
+    fn test_arena_navigation_and_mutation() {
+        let mut arena = ItemArena::from_item(&crate::item!({ "name": "svc", "ports": [80, 443], "tags": { "env": "prod" } }));
+        let root = arena.get_root();
+        let ports = arena.get_item(root, "ports").unwrap().unwrap();
+        assert_eq!(arena.get_kind(ports), ItemKind::List);
+        assert_eq!(arena.get_str(arena.get_item(root, "name").unwrap().unwrap()), Some("svc"));
+        assert_eq!(arena.iter(ports).map(|(key, id)| (key, arena.get_number(id).unwrap().to_f64())).collect::<Vec<_>>(), vec![
+            (ContainerKey::Idx(0), 80.0), (ContainerKey::Idx(1), 443.0),
+        ]);
+
+        arena.add_value(ports, 8080, None).unwrap();
+        let limits = arena.add_hash(root, Some("limits")).unwrap();
+        arena.add_value(limits, "2g", Some("memory")).unwrap();
+        arena.add_null(limits, Some("cpu")).unwrap();
+        arena.add_item(root, &crate::item!([true]), Some("name")).unwrap();
+        let tags = arena.get_item(root, "tags").unwrap().unwrap();
+        let removed = arena.remove_item(tags, ContainerKey::Key("env")).unwrap().unwrap();
+        assert_eq!(arena.get_str(removed), Some("prod"));
+        arena.replace_item(ports, ContainerKey::Idx(0), &crate::item!("http")).unwrap();
+        arena.set_item(limits, &crate::item!({ "cpu": 2 })).unwrap();
+
+        let expected = crate::item!({ "name": [true], "ports": ["http", 443, 8080], "tags": {}, "limits": { "cpu": 2 } });
+        assert_eq!(arena.to_item(), expected);
+        assert_eq!(arena.get_item(ports, "x").unwrap_err(), ItemError::NotAnItemHash);
+        assert_eq!(arena.add_null(tags, None).unwrap_err(), ItemError::NotAnItemList);
+        let error = arena.add_list(removed, Some("x")).unwrap_err();
+        assert_eq!(error, ItemError::NotAnItemContainer);
+        assert_eq!((error.get_expected(), error.get_found()), (Some(ItemKind::Hash), Some(ItemKind::String)));
+        let error = arena.pointer(&ItemPointer::parse("/ports/9").unwrap()).unwrap_err();
+        assert_eq!(error, ItemError::PointerNotFound(1));
+        assert_eq!(error.get_path().map(|path| path.to_string()), Some("/ports/9".to_string()));
+        assert_eq!(arena.pointer(&ItemPointer::parse("/ports/0/x").unwrap()).unwrap_err(), ItemError::PointerNotAContainer(2));
+
+        let stored = arena.node_count();
+        arena.compact();
+        assert!(arena.node_count() < stored);
+        assert_eq!(arena.get_key_id("env"), None);
+        assert_eq!(arena.to_item(), expected);
+    }
+
+    #[test]
+    fn test_arena_reordering_pointers_and_export() {
+        let mut arena = ItemArena::from_item(&crate::item!({ "a": [1, 2, 3], "b": { "x": 1, "y": 2 } }));
+        let root = arena.get_root();
+        let list = arena.get_item(root, "a").unwrap().unwrap();
+        arena.insert_at(list, 0, &crate::item!(0), None).unwrap();
+        assert_eq!(arena.insert_at(list, 9, &crate::item!(0), None).unwrap_err(), ItemError::IndexOutOfRange(9));
+        assert!(arena.add_or_replace(list, 4, &crate::item!(4)).unwrap().is_none());
+        let old = arena.add_or_replace(list, 0, &crate::item!("zero")).unwrap().unwrap();
+        assert_eq!(arena.get_number(old).unwrap().as_i64(), Some(0));
+        arena.swap(list, 0, 4).unwrap();
+        arena.move_item(list, 4, 1).unwrap();
+        arena.truncate(list, 3).unwrap();
+
+        let hash = arena.get_item(root, "b").unwrap().unwrap();
+        arena.insert_at(hash, 0, &crate::item!(true), Some("y")).unwrap();
+        arena.move_item(hash, "x", 0).unwrap();
+        assert_eq!(arena.move_item(hash, "x", 2).unwrap_err(), ItemError::IndexOutOfRange(2));
+        assert_eq!(arena.swap(hash, "x", "q").unwrap_err(), ItemError::ItemNotFound);
+
+        assert!(arena.set_at_pointer(&ItemPointer::parse("/c/d/0").unwrap(), &crate::item!(null), true).unwrap().is_none());
+        let error = arena.set_at_pointer(&ItemPointer::parse("/a/7").unwrap(), &crate::item!(1), false).unwrap_err();
+        assert_eq!(error, ItemError::PointerNotFound(1));
+        assert_eq!(error.get_path().map(|path| path.to_string()), Some("/a/7".to_string()));
+        let removed = arena.remove_at_pointer(&ItemPointer::parse("/b/y").unwrap()).unwrap();
+        assert_eq!(arena.get_bool(removed), Some(true));
+        let expected = crate::item!({ "a": [4, "zero", 1], "b": { "x": 1 }, "c": { "d": [null] } });
+        assert_eq!(arena.to_item(), expected);
+
+        let buffer = SharedBuffer::default();
+        arena.export(&mut JSONFormatCompact::new(Box::new(buffer.clone()))).unwrap();
+        assert_eq!(buffer.contents(), "{\"a\":[4,\"zero\",1],\"b\":{\"x\":1},\"c\":{\"d\":[null]}}");
+    }
+
+    #[test]
+    fn test_arena_large_hashes_and_foreign_ids() {
+        let keys: Vec<String> = (0..40).map(|i| format!("k{}", i)).collect();
+        let text = format!("{{{}, \"k3\": true}}", keys.iter().enumerate().map(|(i, key)| format!("\"{}\": {}", key, i)).collect::<Vec<_>>().join(", "));
+        let mut arena = ItemArena::parse_json_str(&text).unwrap();
+        let root = arena.get_root();
+        assert_eq!(arena.count(root), 40);
+        assert_eq!(arena.get_bool(arena.get_item(root, "k3").unwrap().unwrap()), Some(true));
+        arena.remove_item(root, ContainerKey::Key("k0")).unwrap();
+        arena.add_value(root, "x", Some("k39")).unwrap();
+        arena.add_null(root, Some("new")).unwrap();
+        assert_eq!(arena.get_keys(root).unwrap().last(), Some(&ContainerKey::Key("new")));
+        assert_eq!(arena.get_str(arena.get_item(root, "k39").unwrap().unwrap()), Some("x"));
+        assert_eq!(arena.get_number(arena.get_item(root, "k20").unwrap().unwrap()).unwrap().as_i64(), Some(20));
+        assert_eq!(arena.get_item(root, "k0").unwrap(), None);
+        assert_eq!(arena.count(root), 40);
+        arena.compact();
+        assert_eq!(arena.get_number(arena.get_item(arena.get_root(), "k38").unwrap().unwrap()).unwrap().as_i64(), Some(38));
+
+        let small = ItemArena::new();
+        let foreign = arena.get_item(arena.get_root(), "k38").unwrap().unwrap();
+        assert_eq!(small.get_item(foreign, 0).unwrap_err(), ItemError::ItemNotFound);
+        assert_eq!(ItemArena::new().add_null(foreign, None).unwrap_err(), ItemError::ItemNotFound);
+        assert_eq!(small.pointer_from(foreign, &ItemPointer::root()).unwrap_err(), ItemError::ItemNotFound);
+    }
+
+    #[cfg(feature = "serde")]
+    mod serde_support {
+        use super::*;
+        use crate::{from_item, to_item, SerdeError};
+        use serde::{Deserialize, Serialize};
+
+        #[derive(Debug, PartialEq, Serialize, Deserialize)]
+        enum Mode { Active, Limited(u8), Window { from: u16, to: u16 } }
+
+        #[derive(Debug, PartialEq, Serialize, Deserialize)]
+        struct Service {
+            name: String,
+            ports: Vec<u16>,
+            tls: Option<bool>,
+            labels: BTreeMap<String, String>,
+            modes: Vec<Mode>,
+            weight: f64,
+        }
+
+        fn service() -> Service {
+            Service {
+                name: "web".to_string(),
+                ports: vec![80, 443],
+                tls: None,
+                labels: [("env".to_string(), "prod".to_string())].into_iter().collect(),
+                modes: vec![Mode::Active, Mode::Limited(3), Mode::Window { from: 1, to: 2 }],
+                weight: 0.5,
+            }
+        }
+
+        #[test]
+        fn test_serde_struct_roundtrip() {
+            let item = to_item(&service()).unwrap();
+            assert_eq!(item.to_string(), "{\"name\":web,\"ports\":[80,443],\"tls\":null,\"labels\":{\"env\":prod},\"modes\":[Active,{\"Limited\":3},{\"Window\":{\"from\":1,\"to\":2}}],\"weight\":0.5}");
+            assert_eq!(from_item::<Service>(&item).unwrap(), service());
+
+            let parsed = json::parse_str("{\"name\": \"web\", \"ports\": [80.0, 443], \"tls\": true, \"labels\": {}, \"modes\": [], \"weight\": 1}").unwrap();
+            let read: Service = from_item(&parsed).unwrap();
+            assert_eq!((read.ports, read.tls, read.weight), (vec![80, 443], Some(true), 1.0));
+        }
+
+        #[test]
+        fn test_serde_item_type_roundtrip() {
+            let doc = store_document();
+            assert!(to_item(&doc).unwrap() == doc);
+            assert!(from_item::<ItemType>(&doc).unwrap() == doc);
+            let strings = crate::item!(["a", "b"]);
+            let borrowed: Vec<&str> = from_item(&strings).unwrap();
+            assert_eq!(borrowed, vec!["a", "b"]);
+        }
+
+        #[test]
+        fn test_serde_decimal_precision() {
+            let doc = json::parse_str("[0.10000000000000000000000000001, 1e400, 340282366920938463463374607431768211455]").unwrap();
+            assert!(to_item(&doc).unwrap() == doc);
+            assert!(from_item::<ItemType>(&doc).unwrap() == doc);
+            assert_eq!(to_item(&doc).unwrap().to_string(), doc.to_string());
+            assert_eq!(from_item::<u128>(doc.get_item(2).unwrap().unwrap()).unwrap(), u128::MAX);
+            assert_eq!(from_item::<f64>(doc.get_item(0).unwrap().unwrap()).unwrap(), 0.1);
+            assert_eq!(from_item::<f32>(doc.get_item(1).unwrap().unwrap()).unwrap(), f32::INFINITY);
+
+            #[derive(Debug, Deserialize)]
+            struct Reading { x: f64 }
+            let read = |text: &str| from_item::<Reading>(&json::parse_str(text).unwrap()).unwrap().x;
+            assert_eq!(read("{\"x\": 3.14159265358979323846264338327950288}"), std::f64::consts::PI);
+            assert_eq!(read("{\"x\": 1e400}"), f64::INFINITY);
+            assert_eq!(read("{\"x\": 123456789012345678901234567890}"), 1.2345678901234568e29);
+        }
+
+        #[test]
+        fn test_serde_errors() {
+            let mut keyed = HashMap::new();
+            keyed.insert(vec![1], 2);
+            assert_eq!(to_item(&keyed), Err(SerdeError::KeyMustBeAString));
+            assert!(from_item::<Service>(&crate::item!({ "name": 1 })).is_err());
+            assert!(from_item::<u8>(&crate::item!(300)).is_err());
+            assert!(from_item::<Mode>(&crate::item!("Missing")).is_err());
+        }
+    }
+
+}
@@ -0,0 +1,151 @@
+use std::collections::BTreeMap;
+
+use itemdoc::item;
+use itemdoc::itemdoc::core::convert::ConversionError;
+use itemdoc::itemdoc::core::items::ItemKind;
+use itemdoc::itemdoc::core::pointers::ItemPointer;
+use itemdoc::{FromItem, FromItemError, IntoItem};
+#[cfg(not(feature = "derive"))]
+use itemdoc_derive::{FromItem, IntoItem};
+
+#[derive(Debug, PartialEq, IntoItem, FromItem)]
+struct Server {
+    #[item(rename = "host-name")]
+    host: String,
+    port: u16,
+    #[item(default)]
+    tags: Vec<String>,
+    #[item(default = "default_retries")]
+    retries: u8,
+    #[item(skip)]
+    connected: bool,
+    #[item(flatten)]
+    limits: Limits,
+    owner: Option<String>,
+}
+
+#[derive(Debug, PartialEq, IntoItem, FromItem)]
+struct Limits {
+    max_connections: u32,
+}
+
+fn default_retries() -> u8 {
+    3
+}
+
+#[derive(Debug, PartialEq, IntoItem, FromItem)]
+enum Shape {
+    Empty,
+    Circle(f64),
+    Point(i32, i32),
+    #[item(rename = "rect")]
+    Rectangle { width: u32, height: u32 },
+}
+
+#[derive(Debug, PartialEq, IntoItem, FromItem)]
+#[item(tag = "kind")]
+enum Event {
+    Started,
+    Stopped { code: i32 },
+    Resized(Limits),
+}
+
+#[derive(Debug, PartialEq, IntoItem, FromItem)]
+#[item(tag = "t", content = "c")]
+enum Message {
+    Ping,
+    Text(String),
+}
+
+#[derive(Debug, PartialEq, IntoItem, FromItem)]
+#[item(untagged)]
+enum Value {
+    Flag(bool),
+    Count(u64),
+    Pair(String, String),
+    Table(BTreeMap<String, u64>),
+}
+
+#[test]
+fn struct_attributes_round_trip() {
+    let server = Server {
+        host: "db".to_string(),
+        port: 5432,
+        tags: vec!["primary".to_string()],
+        retries: 5,
+        connected: true,
+        limits: Limits { max_connections: 10 },
+        owner: None,
+    };
+    let item = server.into_item();
+    assert_eq!(item, item!({
+        "host-name": "db",
+        "port": 5432,
+        "tags": ["primary"],
+        "retries": 5,
+        "max_connections": 10,
+        "owner": null
+    }));
+
+    let parsed = Server::from_item(&item!({ "host-name": "db", "port": 5432, "max_connections": 10 })).unwrap();
+    assert_eq!(parsed, Server {
+        host: "db".to_string(),
+        port: 5432,
+        tags: vec![],
+        retries: 3,
+        connected: false,
+        limits: Limits { max_connections: 10 },
+        owner: None,
+    });
+}
+
+#[test]
+fn enum_tagging_styles() {
+    assert_eq!(Shape::Empty.into_item(), item!("Empty"));
+    assert_eq!(Shape::Circle(1.5).into_item(), item!({ "Circle": 1.5 }));
+    assert_eq!(Shape::Point(1, 2).into_item(), item!({ "Point": [1, 2] }));
+    let rect = item!({ "rect": { "width": 2, "height": 3 } });
+    assert_eq!(Shape::Rectangle { width: 2, height: 3 }.into_item(), rect);
+    assert_eq!(Shape::from_item(&rect).unwrap(), Shape::Rectangle { width: 2, height: 3 });
+    assert_eq!(Shape::from_item(&item!("Empty")).unwrap(), Shape::Empty);
+
+    let stopped = item!({ "kind": "Stopped", "code": 1 });
+    assert_eq!(Event::Stopped { code: 1 }.into_item(), stopped);
+    assert_eq!(Event::from_item(&stopped).unwrap(), Event::Stopped { code: 1 });
+    assert_eq!(Event::from_item(&item!({ "kind": "Started" })).unwrap(), Event::Started);
+    let resized = item!({ "kind": "Resized", "max_connections": 4 });
+    assert_eq!(Event::Resized(Limits { max_connections: 4 }).into_item(), resized);
+    assert_eq!(Event::from_item(&resized).unwrap(), Event::Resized(Limits { max_connections: 4 }));
+
+    let text = item!({ "t": "Text", "c": "hi" });
+    assert_eq!(Message::Text("hi".to_string()).into_item(), text);
+    assert_eq!(Message::from_item(&text).unwrap(), Message::Text("hi".to_string()));
+    assert_eq!(Message::Ping.into_item(), item!({ "t": "Ping" }));
+
+    assert_eq!(Value::from_item(&item!(true)).unwrap(), Value::Flag(true));
+    assert_eq!(Value::from_item(&item!(7)).unwrap(), Value::Count(7));
+    assert_eq!(Value::from_item(&item!(["a", "b"])).unwrap(), Value::Pair("a".to_string(), "b".to_string()));
+    assert_eq!(Value::Count(7).into_item(), item!(7));
+}
+
+#[test]
+fn extraction_errors_name_path_and_kind() {
+    let error = Server::from_item(&item!({ "host-name": "db", "port": "x", "max_connections": 1 })).unwrap_err();
+    assert_eq!(error, FromItemError::Conversion(
+        ItemPointer::root().join("port"),
+        ConversionError::WrongKind { expected: ItemKind::Number, found: ItemKind::String },
+    ));
+    assert_eq!(error.to_string(), "At '/port': Expected a number item but found a string!");
+
+    let error = Server::from_item(&item!({ "port": 1, "max_connections": 1 })).unwrap_err();
+    assert_eq!(error.to_string(), "Missing key at '/host-name'!");
+
+    let error = Shape::from_item(&item!({ "rect": { "width": 2, "height": -1 } })).unwrap_err();
+    assert_eq!(error.get_path().to_string(), "/rect/height");
+
+    let error = Event::from_item(&item!({ "kind": "Paused" })).unwrap_err();
+    assert_eq!(error.to_string(), "Unknown variant 'Paused' at '/kind'!");
+
+    let error = Value::from_item(&item!(null)).unwrap_err();
+    assert_eq!(error.to_string(), "No variant matches the null item at '/'!");
+}